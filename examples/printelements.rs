@@ -1,43 +1,20 @@
 use rjvm::bytecode::attributes::{
-    element_value_string, Container, MethodParametersInfo, RuntimeInvisibleAnnotationsInfo,
+    element_value_string, MethodParametersInfo, RuntimeInvisibleAnnotationsInfo,
 };
-use rjvm::bytecode::pool::ConstantPool;
-use rjvm::bytecode::reader::attributes::{
-    CodeAttributeFactory, ConstantValueAttributeFactory, InnerClassesAttributeFactory,
-    LineNumberTableAttributeFactory, LocalVariableTableAttributeFactory,
-    MethodParametersAttributeFactory, NestMembersAttributeFactory,
-    RuntimeInvisibleAnnotationsAttributeFactory, SourceFileAttributeFactory,
-};
-use rjvm::bytecode::reader::containers::read_classfile;
-use rjvm::bytecode::reader::BufferedReader;
-use rjvm::bytecode::{Descriptor, DescriptorKind};
+use rjvm::bytecode::ClassFile;
 use rjvm::{Annotation, Method, Parameter, TypeRef};
 
 fn main() {
     let input = include_bytes!("./testdata/org/example/Simple.class");
 
-    let mut container = Container::new();
-    container.register("ConstantValue", ConstantValueAttributeFactory);
-    container.register("Code", CodeAttributeFactory);
-    container.register("LineNumberTable", LineNumberTableAttributeFactory);
-    container.register(
-        "RuntimeInvisibleAnnotations",
-        RuntimeInvisibleAnnotationsAttributeFactory,
-    );
-    container.register("SourceFile", SourceFileAttributeFactory);
-    container.register("NestMembers", NestMembersAttributeFactory);
-    container.register("InnerClasses", InnerClassesAttributeFactory);
-    container.register("LocalVariableTable", LocalVariableTableAttributeFactory);
-    container.register("MethodParameters", MethodParametersAttributeFactory);
-
-    let mut buffer = BufferedReader::new(input);
-    let mut cp = ConstantPool::new();
-    let cf = read_classfile(&mut buffer, &mut cp, &container).unwrap();
+    let cf = ClassFile::parse(input).unwrap();
+    let cp = &cf.constant_pool;
 
     println!("Class name: {}", cp.text_of(cf.this_class).unwrap());
 
     cf.fields.iter().for_each(|field| {
-        println!("Field {} - {}", field.name, field.descriptor);
+        let descriptor = cp.text_of(field.descriptor_index).unwrap();
+        println!("Field {} - {}", field.name, descriptor);
     });
 
     let methods = cf
@@ -45,9 +22,7 @@ fn main() {
         .iter()
         .map(|method| {
             let attr_params: Vec<String> = method
-                .attributes
-                .get("MethodParameters")
-                .and_then(|attr| attr.as_any_ref().downcast_ref::<MethodParametersInfo>())
+                .attribute::<MethodParametersInfo>()
                 .map(|params| {
                     params
                         .parameters
@@ -57,18 +32,14 @@ fn main() {
                 })
                 .unwrap_or_default();
 
-            let desc_params = method
-                .descriptor
-                .iter()
-                .filter(|desc| desc.kind == DescriptorKind::Parameter)
-                .collect::<Vec<&Descriptor>>();
-
-            let parameters = desc_params
+            let descriptor = method.descriptor.as_ref().unwrap();
+            let parameters = descriptor
+                .params
                 .iter()
                 .enumerate()
-                .map(|(i, desc)| {
+                .map(|(i, param)| {
                     let name: Option<String> = attr_params.get(i).cloned();
-                    let ty = desc.ty.to_string();
+                    let ty = param.to_string();
                     Parameter {
                         name,
                         ty: TypeRef { name: ty },
@@ -77,18 +48,12 @@ fn main() {
                 })
                 .collect();
 
-            let ty: Option<TypeRef> = method
-                .descriptor
-                .iter()
-                .find(|desc| desc.kind == DescriptorKind::Return)
-                .map(|desc| TypeRef {
-                    name: desc.ty.to_string(),
-                });
+            let ty: Option<TypeRef> = Some(TypeRef {
+                name: descriptor.ret.to_string(),
+            });
 
             let mut annotations: Vec<Annotation> = vec![];
-            if let Some(attr) = method
-                .get_attribute::<RuntimeInvisibleAnnotationsInfo>("RuntimeInvisibleAnnotations")
-            {
+            if let Some(attr) = method.attribute::<RuntimeInvisibleAnnotationsInfo>() {
                 let items: Vec<Annotation> = attr
                     .annotations
                     .iter()
@@ -96,7 +61,7 @@ fn main() {
                         let name = cp.text_of(item.type_index).unwrap();
                         let fields = item.element_value_pairs.iter().map(|pair| {
                             let key = cp.text_of(pair.element_name_index).unwrap();
-                            let value = match element_value_string(&pair.value, &cp) {
+                            let value = match element_value_string(&pair.value, cp) {
                                 Ok(value) => value,
                                 Err(_) => {
                                     // TODO: handle error case
@@ -0,0 +1,19 @@
+package org.example;
+
+public class Constants {
+
+    public static final long MAX_LONG = Long.MAX_VALUE;
+    public static final long NEGATIVE_LONG = -1234567890123L;
+    public static final double PI = 3.141592653589793;
+    public static final double NEGATIVE_DOUBLE = -0.5e-300;
+    public static final int AFTER_WIDE = 123456789;
+    public static final String NAME = "constants";
+
+    public long sum(long value) {
+        return value + 9876543210L;
+    }
+
+    public double scale(double value) {
+        return value * 2.718281828459045;
+    }
+}
@@ -0,0 +1,32 @@
+package org.example;
+
+public class Frames {
+    private final String name;
+
+    public Frames(String name) {
+        this.name = name == null ? "" : name;
+    }
+
+    public static int sum(int[] values) {
+        int total = 0;
+        for (int value : values) {
+            total += value;
+        }
+        return total;
+    }
+
+    public long count(long limit) {
+        long count = 0;
+        while (count < limit) {
+            count++;
+        }
+        return count;
+    }
+
+    public String describe(Object value) {
+        if (value instanceof String s) {
+            return s;
+        }
+        return name;
+    }
+}
@@ -0,0 +1,12 @@
+package org.example;
+
+public class Strings {
+
+    public static final String NULL_CHARACTER = "before\0after";
+    public static final String EMOJI = "smile 😀";
+    public static final String UMLAUT = "Grüße";
+
+    public String greet(String name) {
+        return "Hello, " + name + " ❤";
+    }
+}
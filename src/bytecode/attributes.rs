@@ -1,10 +1,37 @@
 use std::collections::HashMap;
 
-use super::pool::ConstantPool;
+use super::compaction::{
+    visit_annotation_indices, visit_attributes_indices, visit_code_indices,
+    visit_elementvalue_indices, visit_stackmapframe_indices, visit_typeannotation_indices,
+};
+use super::pool::{ConstantPool, ConstantPoolView};
+use super::reader::options::ParseOptions;
 use super::reader::BufferedReader;
-use super::BytecodeError;
+use super::writer::BufferedWriter;
+use super::{BytecodeError, FieldType};
 use crate::bytecode::flags::InnerClassAccessFlags;
 use crate::bytecode::pool::ConstantPoolIndex;
+use crate::bytecode::reader::attributes::{
+    AnnotationDefaultAttributeFactory, BootstrapMethodsAttributeFactory, CodeAttributeFactory,
+    ConstantValueAttributeFactory, DeprecatedAttributeFactory, EnclosingMethodAttributeFactory,
+    ExceptionsAttributeFactory, InnerClassesAttributeFactory, LineNumberTableAttributeFactory,
+    LocalVariableTableAttributeFactory, LocalVariableTypeTableAttributeFactory,
+    MethodParametersAttributeFactory, ModuleAttributeFactory, ModuleMainClassAttributeFactory,
+    ModulePackagesAttributeFactory, NestHostAttributeFactory, NestMembersAttributeFactory,
+    PermittedSubtypesAttributeFactory, RecordAttributeFactory,
+    RuntimeInvisibleAnnotationsAttributeFactory,
+    RuntimeInvisibleParameterAnnotationsAttributeFactory,
+    RuntimeInvisibleTypeAnnotationsAttributeFactory, RuntimeVisibleAnnotationsAttributeFactory,
+    RuntimeVisibleParameterAnnotationsAttributeFactory,
+    RuntimeVisibleTypeAnnotationsAttributeFactory, SignatureAttributeFactory,
+    SourceDebugExtensionAttributeFactory, SourceFileAttributeFactory,
+    StackMapTableAttributeFactory, SyntheticAttributeFactory,
+};
+use crate::bytecode::writer::attributes::{
+    write_annotation, write_attribute, write_elementvalue, write_parameter_annotation,
+    write_stackmapframe, write_typeannotation,
+};
+use crate::error::Error;
 
 pub trait Attribute {
     /// Returns the name of the attribute.
@@ -14,20 +41,132 @@ pub trait Attribute {
     fn name(&self) -> &'static str {
         "[unknown attribute]"
     }
+
+    /// Writes the attribute, including its `attribute_name_index` and `attribute_length`, to the
+    /// given `writer`.
+    ///
+    /// Attributes that do not override this function cannot be written and return
+    /// [BytecodeError::UnsupportedAttributeName].
+    fn write(&self, _writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        Err(BytecodeError::UnsupportedAttributeName(
+            self.name().to_string(),
+        ))
+    }
+
+    /// Calls `visit` with every constant pool index that the attribute references, including its
+    /// `attribute_name_index`, so that the indices can be rewritten when the constant pool is
+    /// [compacted](crate::bytecode::ClassFile::compact_constant_pool).
+    ///
+    /// Attributes that do not override this function may reference the constant pool in ways
+    /// that are unknown and return [BytecodeError::UnsupportedAttributeName].
+    fn visit_indices(&mut self, _visit: &mut VisitIndex) -> Result<(), Error> {
+        Err(Error::new(BytecodeError::UnsupportedAttributeName(
+            self.name().to_string(),
+        )))
+    }
 }
 
-pub trait AttributeFactory: std::fmt::Debug {
+/// A function that is called with every [ConstantPoolIndex] referenced by an attribute, see
+/// [Attribute::visit_indices]. Unused indices (`0`) are visited as well.
+pub type VisitIndex<'a> = dyn FnMut(&mut ConstantPoolIndex) -> Result<(), BytecodeError> + 'a;
+
+pub trait AttributeFactory: std::fmt::Debug + Send + Sync {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        pool: &dyn ConstantPoolView,
+        container: &Container,
+    ) -> Result<Box<dyn AnyAttribute>, Error>;
+
+    /// Makes the attribute like [make](AttributeFactory::make), but according to the given
+    /// [ParseOptions]. Factories of attributes that contain other attributes override this to
+    /// read them with the same options.
+    fn make_with(
+        &self,
+        reader: &mut BufferedReader,
+        pool: &dyn ConstantPoolView,
         container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError>;
+        _options: &mut ParseOptions,
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
+        self.make(reader, pool, container)
+    }
+}
+
+/// An attribute with a fixed name, e.g. one of the attributes predefined by the JVMS.
+pub trait NamedAttribute: Attribute {
+    /// The name of the attribute in the class file.
+    const NAME: &'static str;
+}
+
+/// An [AttributeFactory] for a [NamedAttribute], which is registered under the name of its
+/// attribute with [Container::register].
+pub trait NamedAttributeFactory: AttributeFactory + Default + 'static {
+    type Attribute: NamedAttribute;
+}
+
+/// Determines how attributes are handled for which no [AttributeFactory] is registered in a
+/// [Container].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownAttributePolicy {
+    /// Abort parsing with [BytecodeError::UnsupportedAttributeName].
+    #[default]
+    Error,
+    /// Skip the attribute entirely, it will not be part of the parsed structure.
+    Skip,
+    /// Keep the attribute's payload as a [RawAttribute].
+    KeepRaw,
+}
+
+/// The attributes predefined by the JVMS together with the first major version of the class file
+/// format in which they are defined.
+/// <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7-320>
+const PREDEFINED_ATTRIBUTES: [(&str, u16); 30] = [
+    ("ConstantValue", 45),
+    ("Code", 45),
+    ("StackMapTable", 50),
+    ("Exceptions", 45),
+    ("InnerClasses", 45),
+    ("EnclosingMethod", 49),
+    ("Synthetic", 45),
+    ("Signature", 49),
+    ("SourceFile", 45),
+    ("SourceDebugExtension", 49),
+    ("LineNumberTable", 45),
+    ("LocalVariableTable", 45),
+    ("LocalVariableTypeTable", 49),
+    ("Deprecated", 45),
+    ("RuntimeVisibleAnnotations", 49),
+    ("RuntimeInvisibleAnnotations", 49),
+    ("RuntimeVisibleParameterAnnotations", 49),
+    ("RuntimeInvisibleParameterAnnotations", 49),
+    ("RuntimeVisibleTypeAnnotations", 52),
+    ("RuntimeInvisibleTypeAnnotations", 52),
+    ("AnnotationDefault", 49),
+    ("BootstrapMethods", 51),
+    ("MethodParameters", 52),
+    ("Module", 53),
+    ("ModulePackages", 53),
+    ("ModuleMainClass", 53),
+    ("NestHost", 55),
+    ("NestMembers", 55),
+    ("Record", 60),
+    ("PermittedSubclasses", 61),
+];
+
+/// Returns the first major version of the class file format in which the attribute with the given
+/// `name` is predefined by the JVMS, e.g., `52` for `MethodParameters` (Java SE 8). Returns `None`
+/// if the attribute is not predefined.
+pub fn predefined_since(name: &str) -> Option<u16> {
+    PREDEFINED_ATTRIBUTES
+        .iter()
+        .find(|(attribute, _)| *attribute == name)
+        .map(|(_, major)| *major)
 }
 
 #[derive(Debug)]
 pub struct Container {
     inner: HashMap<&'static str, Box<dyn AttributeFactory>>,
+    unknown_attribute_policy: UnknownAttributePolicy,
 }
 
 impl Default for Container {
@@ -40,31 +179,114 @@ impl Container {
     pub fn new() -> Self {
         Self {
             inner: HashMap::new(),
+            unknown_attribute_policy: UnknownAttributePolicy::default(),
         }
     }
 
-    pub fn register(&mut self, name: &'static str, factory: impl AttributeFactory + 'static) {
+    /// Returns a [Container] with factories for all attributes predefined by the JVMS, registered
+    /// under their JVMS names.
+    pub fn standard() -> Self {
+        let mut container = Self::new();
+        container.register::<ConstantValueAttributeFactory>();
+        container.register::<CodeAttributeFactory>();
+        container.register::<StackMapTableAttributeFactory>();
+        container.register::<ExceptionsAttributeFactory>();
+        container.register::<InnerClassesAttributeFactory>();
+        container.register::<EnclosingMethodAttributeFactory>();
+        container.register::<SyntheticAttributeFactory>();
+        container.register::<SignatureAttributeFactory>();
+        container.register::<SourceFileAttributeFactory>();
+        container.register::<SourceDebugExtensionAttributeFactory>();
+        container.register::<LineNumberTableAttributeFactory>();
+        container.register::<LocalVariableTableAttributeFactory>();
+        container.register::<LocalVariableTypeTableAttributeFactory>();
+        container.register::<DeprecatedAttributeFactory>();
+        container.register::<RuntimeVisibleAnnotationsAttributeFactory>();
+        container.register::<RuntimeInvisibleAnnotationsAttributeFactory>();
+        container.register::<RuntimeVisibleParameterAnnotationsAttributeFactory>();
+        container.register::<RuntimeInvisibleParameterAnnotationsAttributeFactory>();
+        container.register::<RuntimeVisibleTypeAnnotationsAttributeFactory>();
+        container.register::<RuntimeInvisibleTypeAnnotationsAttributeFactory>();
+        container.register::<AnnotationDefaultAttributeFactory>();
+        container.register::<BootstrapMethodsAttributeFactory>();
+        container.register::<MethodParametersAttributeFactory>();
+        container.register::<ModuleAttributeFactory>();
+        container.register::<ModulePackagesAttributeFactory>();
+        container.register::<ModuleMainClassAttributeFactory>();
+        container.register::<NestHostAttributeFactory>();
+        container.register::<NestMembersAttributeFactory>();
+        container.register::<RecordAttributeFactory>();
+        container.register::<PermittedSubtypesAttributeFactory>();
+        container
+    }
+
+    /// Returns a [Container] with factories for the attributes that are predefined for class
+    /// files of the given `major` version, e.g., `52` for Java SE 8 or `65` for Java SE 21.
+    ///
+    /// Attributes that were introduced in a later version are not registered, so reading them is
+    /// subject to the container's [UnknownAttributePolicy]. With the default policy, this reports
+    /// version-illegal attributes as [BytecodeError::UnsupportedAttributeName].
+    pub fn for_version(major: u16) -> Self {
+        let mut container = Self::standard();
+        container
+            .inner
+            .retain(|name, _| predefined_since(name).is_some_and(|since| since <= major));
+        container
+    }
+
+    /// Registers the factory `F` under the name of the attribute it reads.
+    pub fn register<F: NamedAttributeFactory>(&mut self) {
+        self.register_as(<F::Attribute as NamedAttribute>::NAME, F::default());
+    }
+
+    /// Registers the `factory` under the given `name`, e.g. a [RawAttributeFactory] for keeping
+    /// a specific attribute as it is.
+    ///
+    /// [RawAttributeFactory]: crate::bytecode::reader::attributes::RawAttributeFactory
+    pub fn register_as(&mut self, name: &'static str, factory: impl AttributeFactory + 'static) {
         self.inner.insert(name, Box::new(factory));
     }
 
     pub fn get_by_name(&self, name: &str) -> Option<&dyn AttributeFactory> {
         self.inner.get(name).map(|f| f.as_ref())
     }
+
+    /// Sets the [UnknownAttributePolicy] that is applied to attributes without a registered
+    /// [AttributeFactory].
+    pub fn set_unknown_attribute_policy(&mut self, policy: UnknownAttributePolicy) {
+        self.unknown_attribute_policy = policy;
+    }
+
+    /// Returns the [UnknownAttributePolicy] that is applied to attributes without a registered
+    /// [AttributeFactory].
+    pub fn unknown_attribute_policy(&self) -> UnknownAttributePolicy {
+        self.unknown_attribute_policy
+    }
 }
 
 impl Attribute for Box<dyn Attribute> {
     fn name(&self) -> &'static str {
         self.as_ref().name()
     }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        self.as_ref().write(writer)
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        self.as_mut().visit_indices(visit)
+    }
 }
 
-pub trait AnyAttribute: std::fmt::Debug {
+pub trait AnyAttribute: std::fmt::Debug + Send + Sync {
     fn as_any_ref(&self) -> &dyn std::any::Any;
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
     fn name_any(&self) -> &'static str;
+    fn write_any(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError>;
+    fn visit_indices_any(&mut self, visit: &mut VisitIndex) -> Result<(), Error>;
 }
 
-impl<T: std::fmt::Debug + Attribute + 'static> AnyAttribute for T {
+impl<T: std::fmt::Debug + Attribute + Send + Sync + 'static> AnyAttribute for T {
     fn as_any_ref(&self) -> &dyn std::any::Any {
         self
     }
@@ -76,185 +298,1017 @@ impl<T: std::fmt::Debug + Attribute + 'static> AnyAttribute for T {
     fn name_any(&self) -> &'static str {
         self.name()
     }
+
+    fn write_any(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        self.write(writer)
+    }
+
+    fn visit_indices_any(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        self.visit_indices(visit)
+    }
+}
+
+/// The attributes of a class file, field, method or `Code` attribute in the order in which they
+/// appear in the class file, including duplicates.
+///
+/// Each attribute is stored under its name in the constant pool, so that attributes read by custom
+/// factories can be looked up by their actual name as well.
+#[derive(Debug, Default)]
+pub struct Attributes {
+    entries: Vec<(String, Box<dyn AnyAttribute>)>,
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Appends the `attribute` under the given `name`.
+    pub fn push(&mut self, name: impl Into<String>, attribute: Box<dyn AnyAttribute>) {
+        self.entries.push((name.into(), attribute));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns `true` if there is at least one attribute with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns the first attribute with the given name.
+    pub fn get(&self, name: &str) -> Option<&dyn AnyAttribute> {
+        self.iter()
+            .find(|(attr_name, _)| *attr_name == name)
+            .map(|(_, attr)| attr)
+    }
+
+    /// Returns all attributes with the given name in their original order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a dyn AnyAttribute> {
+        self.iter()
+            .filter(move |(attr_name, _)| *attr_name == name)
+            .map(|(_, attr)| attr)
+    }
+
+    /// Returns the first attribute with the given name if it is a `T`.
+    pub fn get_attribute<T: AnyAttribute + 'static>(&self, name: &str) -> Option<&T> {
+        self.get(name)
+            .and_then(|attr| attr.as_any_ref().downcast_ref::<T>())
+    }
+
+    /// Returns the first attribute named [T::NAME](NamedAttribute::NAME) if it is a `T`.
+    pub fn attribute<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Option<&T> {
+        self.get_attribute(T::NAME)
+    }
+
+    /// Returns the first attribute named [T::NAME](NamedAttribute::NAME) mutably if it is a `T`.
+    pub fn attribute_mut<T: NamedAttribute + AnyAttribute + 'static>(&mut self) -> Option<&mut T> {
+        self.entries
+            .iter_mut()
+            .find(|(name, _)| name == T::NAME)
+            .and_then(|(_, attr)| attr.as_any_mut().downcast_mut::<T>())
+    }
+
+    /// Keeps only the attributes whose name satisfies `keep`, e.g. to strip debug information.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.entries.retain(|(name, _)| keep(name));
+    }
+
+    /// Returns the attributes together with their names in their original order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &dyn AnyAttribute)> {
+        self.entries
+            .iter()
+            .map(|(name, attr)| (name.as_str(), attr.as_ref()))
+    }
+
+    /// Returns the names of the attributes in their original order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(name, _)| name)
+    }
+
+    /// Returns the attributes in their original order.
+    pub fn values(&self) -> impl Iterator<Item = &dyn AnyAttribute> {
+        self.iter().map(|(_, attr)| attr)
+    }
+
+    /// Returns the attributes mutably together with their names in their original order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut dyn AnyAttribute)> {
+        self.entries
+            .iter_mut()
+            .map(|(name, attr)| (name.as_str(), attr.as_mut() as &mut dyn AnyAttribute))
+    }
+}
+
+impl NamedAttribute for ConstantValueInfo {
+    const NAME: &'static str = "ConstantValue";
 }
 
 impl Attribute for ConstantValueInfo {
     fn name(&self) -> &'static str {
-        "ConstantValue"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_index(self.constantvalue_index)
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit(&mut self.constantvalue_index)?;
+        Ok(())
     }
 }
 
+impl NamedAttribute for CodeInfo {
+    const NAME: &'static str = "Code";
+}
+
 impl Attribute for CodeInfo {
     fn name(&self) -> &'static str {
-        "Code"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put(self.max_stack);
+            writer.put(self.max_locals);
+            writer.put_count::<u32>(self.code.len())?;
+            writer.put_bytes(&self.code);
+            writer.put_count::<u16>(self.exception_table.len())?;
+            for entry in &self.exception_table {
+                writer.put(entry.start_pc);
+                writer.put(entry.end_pc);
+                writer.put(entry.handler_pc);
+                writer.put_index(entry.catch_type)?;
+            }
+            writer.put_count::<u16>(self.attributes.len())?;
+            for attribute in self.attributes.values() {
+                write_attribute(writer, attribute)?;
+            }
+            Ok(())
+        })
     }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit_code_indices(&mut self.code, visit)?;
+        for entry in &mut self.exception_table {
+            visit(&mut entry.catch_type)?;
+        }
+        visit_attributes_indices(&mut self.attributes, visit)?;
+        Ok(())
+    }
+}
+
+impl NamedAttribute for StackMapTableInfo {
+    const NAME: &'static str = "StackMapTable";
 }
 
 impl Attribute for StackMapTableInfo {
     fn name(&self) -> &'static str {
-        "StackMapTable"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.entries.len())?;
+            for entry in &self.entries {
+                write_stackmapframe(writer, entry)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for entry in &mut self.entries {
+            visit_stackmapframe_indices(entry, visit)?;
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for ExceptionsInfo {
+    const NAME: &'static str = "Exceptions";
+}
+
 impl Attribute for ExceptionsInfo {
     fn name(&self) -> &'static str {
-        "Exceptions"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.exception_index_table.len())?;
+            for index in &self.exception_index_table {
+                writer.put_index(*index)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for index in &mut self.exception_index_table {
+            visit(index)?;
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for InnerClassesInfo {
+    const NAME: &'static str = "InnerClasses";
+}
+
 impl Attribute for InnerClassesInfo {
     fn name(&self) -> &'static str {
-        "InnerClasses"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.classes.len())?;
+            for class in &self.classes {
+                writer.put_index(class.inner_class_info_index)?;
+                writer.put_index(class.outer_class_info_index)?;
+                writer.put_index(class.inner_name_index)?;
+                writer.put(class.inner_class_access_flags.bits());
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for class in &mut self.classes {
+            visit(&mut class.inner_class_info_index)?;
+            visit(&mut class.outer_class_info_index)?;
+            visit(&mut class.inner_name_index)?;
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for EnclosingMethodInfo {
+    const NAME: &'static str = "EnclosingMethod";
+}
+
 impl Attribute for EnclosingMethodInfo {
     fn name(&self) -> &'static str {
-        "EnclosingMethod"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_index(self.class_index)?;
+            writer.put_index(self.method_index)
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit(&mut self.class_index)?;
+        visit(&mut self.method_index)?;
+        Ok(())
     }
 }
 
+impl NamedAttribute for SyntheticInfo {
+    const NAME: &'static str = "Synthetic";
+}
+
 impl Attribute for SyntheticInfo {
     fn name(&self) -> &'static str {
-        "Synthetic"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |_| Ok(()))
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        Ok(())
     }
 }
 
+impl NamedAttribute for SignatureInfo {
+    const NAME: &'static str = "Signature";
+}
+
 impl Attribute for SignatureInfo {
     fn name(&self) -> &'static str {
-        "Signature"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_index(self.signature_index)
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit(&mut self.signature_index)?;
+        Ok(())
     }
 }
 
+impl NamedAttribute for SourceFileInfo {
+    const NAME: &'static str = "SourceFile";
+}
+
 impl Attribute for SourceFileInfo {
     fn name(&self) -> &'static str {
-        "SourceFile"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_index(self.sourcefile_index)
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit(&mut self.sourcefile_index)?;
+        Ok(())
     }
 }
 
+impl NamedAttribute for SourceDebugExtensionInfo {
+    const NAME: &'static str = "SourceDebugExtension";
+}
+
 impl Attribute for SourceDebugExtensionInfo {
     fn name(&self) -> &'static str {
-        "SourceDebugExtension"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_bytes(&self.debug_extension);
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        Ok(())
     }
 }
 
+impl NamedAttribute for LineNumberTableInfo {
+    const NAME: &'static str = "LineNumberTable";
+}
+
 impl Attribute for LineNumberTableInfo {
     fn name(&self) -> &'static str {
-        "LineNumberTable"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.line_number_table.len())?;
+            for entry in &self.line_number_table {
+                writer.put(entry.start_pc);
+                writer.put(entry.line_number);
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        Ok(())
     }
 }
 
+impl NamedAttribute for LocalVariableTableInfo {
+    const NAME: &'static str = "LocalVariableTable";
+}
+
 impl Attribute for LocalVariableTableInfo {
     fn name(&self) -> &'static str {
-        "LocalVariableTable"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.local_variable_table.len())?;
+            for entry in &self.local_variable_table {
+                writer.put(entry.start_pc);
+                writer.put(entry.length);
+                writer.put_index(entry.name_index)?;
+                writer.put_index(entry.descriptor_index)?;
+                writer.put_index(entry.index)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for entry in &mut self.local_variable_table {
+            visit(&mut entry.name_index)?;
+            visit(&mut entry.descriptor_index)?;
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for LocalVariableTypeTableInfo {
+    const NAME: &'static str = "LocalVariableTypeTable";
+}
+
 impl Attribute for LocalVariableTypeTableInfo {
     fn name(&self) -> &'static str {
-        "LocalVariableTypeTable"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.local_variable_type_table.len())?;
+            for entry in &self.local_variable_type_table {
+                writer.put(entry.start_pc);
+                writer.put(entry.length);
+                writer.put_index(entry.name_index)?;
+                writer.put_index(entry.signature_index)?;
+                writer.put_index(entry.index)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for entry in &mut self.local_variable_type_table {
+            visit(&mut entry.name_index)?;
+            visit(&mut entry.signature_index)?;
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for DeprecatedInfo {
+    const NAME: &'static str = "Deprecated";
+}
+
 impl Attribute for DeprecatedInfo {
     fn name(&self) -> &'static str {
-        "Deprecated"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |_| Ok(()))
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        Ok(())
     }
 }
 
+impl NamedAttribute for RuntimeVisibleAnnotationsInfo {
+    const NAME: &'static str = "RuntimeVisibleAnnotations";
+}
+
 impl Attribute for RuntimeVisibleAnnotationsInfo {
     fn name(&self) -> &'static str {
-        "RuntimeVisibleAnnotations"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.annotations.len())?;
+            for annotation in &self.annotations {
+                write_annotation(writer, annotation)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for annotation in &mut self.annotations {
+            visit_annotation_indices(annotation, visit)?;
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for RuntimeInvisibleAnnotationsInfo {
+    const NAME: &'static str = "RuntimeInvisibleAnnotations";
+}
+
 impl Attribute for RuntimeInvisibleAnnotationsInfo {
     fn name(&self) -> &'static str {
-        "RuntimeInvisibleAnnotations"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.annotations.len())?;
+            for annotation in &self.annotations {
+                write_annotation(writer, annotation)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for annotation in &mut self.annotations {
+            visit_annotation_indices(annotation, visit)?;
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for RuntimeVisibleParameterAnnotationsInfo {
+    const NAME: &'static str = "RuntimeVisibleParameterAnnotations";
+}
+
 impl Attribute for RuntimeVisibleParameterAnnotationsInfo {
     fn name(&self) -> &'static str {
-        "RuntimeVisibleParameterAnnotations"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u8>(self.parameter_annotations.len())?;
+            for parameter in &self.parameter_annotations {
+                write_parameter_annotation(writer, parameter)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for parameter in &mut self.parameter_annotations {
+            for annotation in &mut parameter.annotations {
+                visit_annotation_indices(annotation, visit)?;
+            }
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for RuntimeInvisibleParameterAnnotationsInfo {
+    const NAME: &'static str = "RuntimeInvisibleParameterAnnotations";
+}
+
 impl Attribute for RuntimeInvisibleParameterAnnotationsInfo {
     fn name(&self) -> &'static str {
-        "RuntimeInvisibleParameterAnnotations"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u8>(self.parameter_annotations.len())?;
+            for parameter in &self.parameter_annotations {
+                write_parameter_annotation(writer, parameter)?;
+            }
+            Ok(())
+        })
     }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for parameter in &mut self.parameter_annotations {
+            for annotation in &mut parameter.annotations {
+                visit_annotation_indices(annotation, visit)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NamedAttribute for RuntimeVisibleTypeAnnotationsInfo {
+    const NAME: &'static str = "RuntimeVisibleTypeAnnotations";
 }
 
 impl Attribute for RuntimeVisibleTypeAnnotationsInfo {
     fn name(&self) -> &'static str {
-        "RuntimeVisibleTypeAnnotations"
+        Self::NAME
     }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.annotations.len())?;
+            for annotation in &self.annotations {
+                write_typeannotation(writer, annotation)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for annotation in &mut self.annotations {
+            visit_typeannotation_indices(annotation, visit)?;
+        }
+        Ok(())
+    }
+}
+
+impl NamedAttribute for RuntimeInvisibleTypeAnnotationsInfo {
+    const NAME: &'static str = "RuntimeInvisibleTypeAnnotations";
 }
 
 impl Attribute for RuntimeInvisibleTypeAnnotationsInfo {
     fn name(&self) -> &'static str {
-        "RuntimeInvisibleTypeAnnotations"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.annotations.len())?;
+            for annotation in &self.annotations {
+                write_typeannotation(writer, annotation)?;
+            }
+            Ok(())
+        })
     }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for annotation in &mut self.annotations {
+            visit_typeannotation_indices(annotation, visit)?;
+        }
+        Ok(())
+    }
+}
+
+impl NamedAttribute for AnnotationDefaultInfo {
+    const NAME: &'static str = "AnnotationDefault";
 }
 
 impl Attribute for AnnotationDefaultInfo {
     fn name(&self) -> &'static str {
-        "AnnotationDefault"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            write_elementvalue(writer, &self.default_value)
+        })
     }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit_elementvalue_indices(&mut self.default_value, visit)?;
+        Ok(())
+    }
+}
+
+impl NamedAttribute for BootstrapMethodsInfo {
+    const NAME: &'static str = "BootstrapMethods";
 }
 
 impl Attribute for BootstrapMethodsInfo {
     fn name(&self) -> &'static str {
-        "BootstrapMethods"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.bootstrap_methods.len())?;
+            for method in &self.bootstrap_methods {
+                writer.put_index(method.bootstrap_method_ref)?;
+                writer.put_count::<u16>(method.bootstrap_arguments.len())?;
+                for argument in &method.bootstrap_arguments {
+                    writer.put_index(*argument)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for method in &mut self.bootstrap_methods {
+            visit(&mut method.bootstrap_method_ref)?;
+            for argument in &mut method.bootstrap_arguments {
+                visit(argument)?;
+            }
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for MethodParametersInfo {
+    const NAME: &'static str = "MethodParameters";
+}
+
 impl Attribute for MethodParametersInfo {
     fn name(&self) -> &'static str {
-        "MethodParameters"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u8>(self.parameters.len())?;
+            for parameter in &self.parameters {
+                writer.put_index(parameter.name_index)?;
+                writer.put(parameter.access_flags);
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for parameter in &mut self.parameters {
+            visit(&mut parameter.name_index)?;
+        }
+        Ok(())
     }
 }
 
+impl NamedAttribute for ModuleInfo {
+    const NAME: &'static str = "Module";
+}
+
 impl Attribute for ModuleInfo {
     fn name(&self) -> &'static str {
-        "Module"
+        Self::NAME
     }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_index(self.module_name_index)?;
+            writer.put(self.module_flags);
+            writer.put_index(self.module_version_index)?;
+
+            writer.put_count::<u16>(self.requires.len())?;
+            for requires in &self.requires {
+                writer.put_index(requires.requires_index)?;
+                writer.put(requires.requires_flags);
+                writer.put_index(requires.requires_version_index)?;
+            }
+
+            writer.put_count::<u16>(self.exports.len())?;
+            for exports in &self.exports {
+                writer.put_index(exports.exports_index)?;
+                writer.put(exports.exports_flags);
+                writer.put_count::<u16>(exports.exports_to_index.len())?;
+                for index in &exports.exports_to_index {
+                    writer.put_index(*index)?;
+                }
+            }
+
+            writer.put_count::<u16>(self.opens.len())?;
+            for opens in &self.opens {
+                writer.put_index(opens.opens_index)?;
+                writer.put(opens.opens_flags);
+                writer.put_count::<u16>(opens.opens_to_index.len())?;
+                for index in &opens.opens_to_index {
+                    writer.put_index(*index)?;
+                }
+            }
+
+            writer.put_count::<u16>(self.uses_index.len())?;
+            for index in &self.uses_index {
+                writer.put_index(*index)?;
+            }
+
+            writer.put_count::<u16>(self.provides.len())?;
+            for provides in &self.provides {
+                writer.put_index(provides.provides_index)?;
+                writer.put_count::<u16>(provides.provides_with_index.len())?;
+                for index in &provides.provides_with_index {
+                    writer.put_index(*index)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit(&mut self.module_name_index)?;
+        visit(&mut self.module_version_index)?;
+        for requires in &mut self.requires {
+            visit(&mut requires.requires_index)?;
+            visit(&mut requires.requires_version_index)?;
+        }
+        for exports in &mut self.exports {
+            visit(&mut exports.exports_index)?;
+            for index in &mut exports.exports_to_index {
+                visit(index)?;
+            }
+        }
+        for opens in &mut self.opens {
+            visit(&mut opens.opens_index)?;
+            for index in &mut opens.opens_to_index {
+                visit(index)?;
+            }
+        }
+        for index in &mut self.uses_index {
+            visit(index)?;
+        }
+        for provides in &mut self.provides {
+            visit(&mut provides.provides_index)?;
+            for index in &mut provides.provides_with_index {
+                visit(index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NamedAttribute for ModulePackagesInfo {
+    const NAME: &'static str = "ModulePackages";
 }
 
 impl Attribute for ModulePackagesInfo {
     fn name(&self) -> &'static str {
-        "ModulePackages"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.package_index.len())?;
+            for index in &self.package_index {
+                writer.put_index(*index)?;
+            }
+            Ok(())
+        })
     }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for index in &mut self.package_index {
+            visit(index)?;
+        }
+        Ok(())
+    }
+}
+
+impl NamedAttribute for ModuleMainClassInfo {
+    const NAME: &'static str = "ModuleMainClass";
 }
 
 impl Attribute for ModuleMainClassInfo {
     fn name(&self) -> &'static str {
-        "ModuleMainClass"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_index(self.main_class_index)
+        })
     }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit(&mut self.main_class_index)?;
+        Ok(())
+    }
+}
+
+impl NamedAttribute for NestHostInfo {
+    const NAME: &'static str = "NestHost";
 }
 
 impl Attribute for NestHostInfo {
     fn name(&self) -> &'static str {
-        "NestHost"
+        Self::NAME
     }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_index(self.host_class_index)
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        visit(&mut self.host_class_index)?;
+        Ok(())
+    }
+}
+
+impl NamedAttribute for NestMembersInfo {
+    const NAME: &'static str = "NestMembers";
 }
 
 impl Attribute for NestMembersInfo {
     fn name(&self) -> &'static str {
-        "NestMembers"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.classes.len())?;
+            for class in &self.classes {
+                writer.put_index(*class)?;
+            }
+            Ok(())
+        })
     }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for index in &mut self.classes {
+            visit(index)?;
+        }
+        Ok(())
+    }
+}
+
+impl NamedAttribute for RecordInfo {
+    const NAME: &'static str = "Record";
 }
 
 impl Attribute for RecordInfo {
     fn name(&self) -> &'static str {
-        "Record"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.components.len())?;
+            for component in &self.components {
+                writer.put_index(component.name_index)?;
+                writer.put_index(component.descriptor_index)?;
+                writer.put_count::<u16>(component.attributes.len())?;
+                for attribute in component.attributes.values() {
+                    write_attribute(writer, attribute)?;
+                }
+            }
+            Ok(())
+        })
     }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for component in &mut self.components {
+            visit(&mut component.name_index)?;
+            visit(&mut component.descriptor_index)?;
+            visit_attributes_indices(&mut component.attributes, visit)?;
+        }
+        Ok(())
+    }
+}
+
+impl NamedAttribute for PermittedSubtypesInfo {
+    const NAME: &'static str = "PermittedSubclasses";
 }
 
 impl Attribute for PermittedSubtypesInfo {
     fn name(&self) -> &'static str {
-        "PermittedSubtypes"
+        Self::NAME
+    }
+
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_count::<u16>(self.classes.len())?;
+            for class in &self.classes {
+                writer.put_index(*class)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_indices(&mut self, visit: &mut VisitIndex) -> Result<(), Error> {
+        visit(&mut self.attribute_name_index)?;
+        for index in &mut self.classes {
+            visit(index)?;
+        }
+        Ok(())
+    }
+}
+
+impl Attribute for RawAttribute {
+    fn write(&self, writer: &mut BufferedWriter) -> Result<(), BytecodeError> {
+        writer.put_attribute(self.attribute_name_index, |writer| {
+            writer.put_bytes(&self.bytes);
+            Ok(())
+        })
+    }
+
+    /// The payload of an unknown attribute may reference the constant pool, so its indices
+    /// cannot be visited.
+    fn visit_indices(&mut self, _visit: &mut VisitIndex) -> Result<(), Error> {
+        Err(Error::new(BytecodeError::UnsupportedAttributeName(
+            self.name.clone(),
+        )))
     }
 }
 
@@ -348,7 +1402,11 @@ pub enum ElementTag {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ElementValue {
-    ConstValueIndex(ConstantPoolIndex),
+    ConstValueIndex {
+        /// The tag of the constant, i.e., one of `B`, `C`, `D`, `F`, `I`, `J`, `S`, `Z` or `s`.
+        tag: u8,
+        const_value_index: ConstantPoolIndex,
+    },
     EnumConstValue {
         type_name_index: ConstantPoolIndex,
         const_name_index: ConstantPoolIndex,
@@ -366,25 +1424,48 @@ pub fn element_value_string(
     pool: &ConstantPool,
 ) -> Result<String, BytecodeError> {
     match value {
-        ElementValue::ConstValueIndex(idx) => match pool.text_of(*idx) {
+        ElementValue::ConstValueIndex {
+            const_value_index, ..
+        } => match pool.text_of(*const_value_index) {
             Some(str) => Ok(str.to_string()),
-            None => Err(BytecodeError::ConstantPoolEntryNotFound),
+            None => Err(BytecodeError::ConstantPoolEntryNotFound(
+                const_value_index.index(),
+            )),
         },
         ElementValue::EnumConstValue {
-            type_name_index: _,
-            const_name_index: _,
-        } => {
-            todo!()
-        }
+            type_name_index,
+            const_name_index,
+        } => Ok(format!(
+            "{}.{}",
+            pool.utf8_at(*type_name_index)?,
+            pool.utf8_at(*const_name_index)?
+        )),
         ElementValue::ClassInfoIndex(idx) => match pool.text_of(*idx) {
             Some(str) => Ok(str.to_string()),
-            None => Err(BytecodeError::ConstantPoolEntryNotFound),
+            None => Err(BytecodeError::ConstantPoolEntryNotFound(idx.index())),
         },
-        ElementValue::Annotation(_annotation) => {
-            todo!()
+        ElementValue::Annotation(annotation) => {
+            let pairs = annotation
+                .element_value_pairs
+                .iter()
+                .map(|pair| {
+                    let name = pool.utf8_at(pair.element_name_index)?;
+                    let value = element_value_string(&pair.value, pool)?;
+                    Ok(format!("{}={}", name, value))
+                })
+                .collect::<Result<Vec<_>, BytecodeError>>()?;
+            Ok(format!(
+                "@{}({})",
+                pool.utf8_at(annotation.type_index)?,
+                pairs.join(", ")
+            ))
         }
-        ElementValue::Array { values: _, .. } => {
-            todo!()
+        ElementValue::Array { values, .. } => {
+            let values = values
+                .iter()
+                .map(|value| element_value_string(value, pool))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("{{{}}}", values.join(", ")))
         }
     }
 }
@@ -515,12 +1596,28 @@ pub struct BootstrapMethod {
     pub bootstrap_arguments: Vec<ConstantPoolIndex>,
 }
 
+/// A component of a record, e.g. `int x` of `record Point(int x, int y)`.
 #[derive(Debug)]
 pub struct RecordComponent {
     pub name_index: ConstantPoolIndex,
+    pub name: String,
     pub descriptor_index: ConstantPoolIndex,
+    /// The type of the component, `None` if its descriptor is invalid and the record was read
+    /// leniently.
+    pub descriptor: Option<FieldType>,
     pub attributes_count: u16,
-    pub attributes: Vec<Box<dyn AnyAttribute>>,
+    /// The attributes of the component, e.g. its `Signature` if its type is generic.
+    pub attributes: Attributes,
+}
+
+impl RecordComponent {
+    pub fn get_attribute<T: AnyAttribute + 'static>(&self, name: &str) -> Option<&T> {
+        self.attributes.get_attribute(name)
+    }
+
+    pub fn attribute<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Option<&T> {
+        self.attributes.attribute()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -577,7 +1674,17 @@ pub struct CodeInfo {
     pub exception_table_length: u16,
     pub exception_table: Vec<ExceptionTableEntry>,
     pub attributes_count: u16,
-    pub attributes: Vec<Box<dyn AnyAttribute>>,
+    pub attributes: Attributes,
+}
+
+impl CodeInfo {
+    pub fn get_attribute<T: AnyAttribute + 'static>(&self, name: &str) -> Option<&T> {
+        self.attributes.get_attribute(name)
+    }
+
+    pub fn attribute<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Option<&T> {
+        self.attributes.attribute()
+    }
 }
 
 #[derive(Debug)]
@@ -794,7 +1901,7 @@ pub struct RecordInfo {
     pub attribute_name_index: ConstantPoolIndex,
     pub attribute_length: u32,
     pub component_count: u16,
-    pub components: Vec<Box<dyn AnyAttribute>>,
+    pub components: Vec<RecordComponent>,
 }
 
 #[derive(Debug)]
@@ -802,5 +1909,133 @@ pub struct PermittedSubtypesInfo {
     pub attribute_name_index: ConstantPoolIndex,
     pub attribute_length: u32,
     pub number_of_classes: u16,
-    pub classes: Vec<String>,
+    pub classes: Vec<ConstantPoolIndex>,
+}
+
+/// An attribute that is not known to the [Container] it was read with. The payload of the
+/// attribute is kept as is, so it can be inspected or written back unchanged.
+#[derive(Debug)]
+pub struct RawAttribute {
+    pub attribute_name_index: ConstantPoolIndex,
+    pub attribute_length: u32,
+    /// The name of the attribute as referenced by `attribute_name_index`.
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::attributes::{
+        predefined_since, Container, NestMembersInfo, RawAttribute, SourceFileInfo,
+        PREDEFINED_ATTRIBUTES,
+    };
+    use crate::bytecode::reader::attributes::RawAttributeFactory;
+    use crate::bytecode::reader::containers::read_classfile;
+    use crate::bytecode::reader::parser::ClassParser;
+    use crate::bytecode::reader::BufferedReader;
+    use crate::bytecode::tests::{Layout, SIMPLE};
+    use crate::bytecode::writer::containers::write_classfile;
+    use crate::bytecode::writer::BufferedWriter;
+    use crate::bytecode::BytecodeError;
+
+    #[test]
+    fn test_standard_container() {
+        let container = Container::standard();
+        for (name, _) in PREDEFINED_ATTRIBUTES {
+            assert!(container.get_by_name(name).is_some(), "{name} is missing");
+        }
+
+        let mut reader = BufferedReader::new(SIMPLE);
+        let cf = read_classfile(&mut reader, &container).unwrap();
+        assert_eq!(cf.attributes.len(), 3);
+    }
+
+    #[test]
+    fn test_container_for_version() {
+        assert_eq!(predefined_since("StackMapTable"), Some(50));
+        assert_eq!(predefined_since("PermittedSubclasses"), Some(61));
+        assert_eq!(predefined_since("SourceID"), None);
+
+        let java8 = Container::for_version(52);
+        assert!(java8.get_by_name("MethodParameters").is_some());
+        assert!(java8.get_by_name("NestMembers").is_none());
+        assert!(java8.get_by_name("Record").is_none());
+
+        let java21 = Container::for_version(65);
+        assert!(java21.get_by_name("PermittedSubclasses").is_some());
+
+        // `Simple.class` is compiled for Java SE 21 and contains a `NestMembers` attribute,
+        // which is not defined for Java SE 8.
+        let mut reader = BufferedReader::new(SIMPLE);
+        let ret = read_classfile(&mut reader, &java8);
+        assert_eq!(
+            ret.unwrap_err().kind(),
+            &BytecodeError::UnsupportedAttributeName("NestMembers".to_string())
+        );
+    }
+
+    #[test]
+    fn test_attribute_order() {
+        let mut container = Container::standard();
+        container.register_as("SourceFile", RawAttributeFactory);
+        let mut cf = ClassParser::new()
+            .container(container)
+            .parse(SIMPLE)
+            .unwrap();
+        let names = cf.attributes.names().collect::<Vec<_>>();
+        assert_eq!(names, ["SourceFile", "NestMembers", "InnerClasses"]);
+        assert!(cf.attribute::<NestMembersInfo>().is_some());
+        assert!(cf.attribute::<SourceFileInfo>().is_none());
+
+        // Custom attributes are stored under their actual name, not the name of their type.
+        let raw = cf.get_attribute::<RawAttribute>("SourceFile").unwrap();
+        assert_eq!(raw.name, "SourceFile");
+
+        let duplicate = RawAttribute {
+            attribute_name_index: raw.attribute_name_index,
+            attribute_length: raw.attribute_length,
+            name: raw.name.clone(),
+            bytes: raw.bytes.clone(),
+        };
+        cf.attributes.push("SourceFile", Box::new(duplicate));
+        assert_eq!(cf.attributes.len(), 4);
+        assert_eq!(cf.attributes.get_all("SourceFile").count(), 2);
+        assert_eq!(cf.attributes.names().last(), Some("SourceFile"));
+    }
+
+    #[test]
+    fn test_duplicate_attributes() {
+        // Insert a second `SourceFile` attribute of 8 bytes after the first one, whose
+        // `sourcefile_index` refers to its own name so that the two can be told apart.
+        let source_file = Layout::of(SIMPLE).class_attribute("SourceFile");
+        let end = source_file + 8;
+        let mut duplicate = SIMPLE[source_file..end].to_vec();
+        duplicate.copy_within(0..2, 6);
+        let mut input = [&SIMPLE[..end], &duplicate, &SIMPLE[end..]].concat();
+        // `SourceFile` is the first attribute of the class, right after `attributes_count`.
+        input[source_file - 1] += 1;
+
+        let mut reader = BufferedReader::new(&input);
+        let cf = read_classfile(&mut reader, &Container::standard()).unwrap();
+        let names = cf.attributes.names().collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["SourceFile", "SourceFile", "NestMembers", "InnerClasses"]
+        );
+        let indices = cf
+            .attributes
+            .get_all("SourceFile")
+            .map(|attr| {
+                let attr = attr.as_any_ref().downcast_ref::<SourceFileInfo>().unwrap();
+                (attr.attribute_name_index, attr.sourcefile_index)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(indices.len(), 2);
+        assert_ne!(indices[0].1, indices[0].0);
+        assert_eq!(indices[1].1, indices[1].0);
+
+        let mut writer = BufferedWriter::new();
+        write_classfile(&mut writer, &cf).unwrap();
+        assert_eq!(writer.as_bytes(), input.as_slice());
+    }
 }
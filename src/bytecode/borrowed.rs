@@ -0,0 +1,475 @@
+//! A borrowed representation of class files, whose strings and byte payloads reference the input
+//! buffer instead of being copied.
+//!
+//! Attributes are kept as the [raw bytes](Attribute::info) of the class file and decoded on first
+//! access, e.g. with [Method::code] or [Attribute::get], so that reading the header, the constant
+//! pool and the member signatures of a class doesn't pay for decoding its bytecode. Decoded
+//! attributes are cached, and all attributes are decoded when converting the class file into the
+//! owned [ClassFile](crate::bytecode::ClassFile) with [into_owned](ClassFile::into_owned).
+//!
+//! The attributes that carry a payload also have borrowed forms, which are read without decoding
+//! the whole attribute: [Method::code] borrows the bytecode and the nested attributes of a
+//! method, [Attribute::source_debug_extension] borrows the debug extension, and
+//! [Attribute::annotations] decodes annotations one at a time while iterating over them.
+//!
+//! Attributes are decoded by the same [AttributeFactory]s as owned class files, which resolve
+//! constants in the borrowed [ConstantPool] through [ConstantPoolView], so decoding an attribute
+//! never copies the constant pool.
+//!
+//! [AttributeFactory]: crate::bytecode::attributes::AttributeFactory
+//!
+//! Class files, their members and their attributes are `Send` and `Sync`, so that they can be
+//! handed to other threads and decoded there.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+use crate::bytecode::attributes::{
+    Annotation, AnyAttribute, Container, ExceptionTableEntry, NamedAttribute,
+    RuntimeInvisibleAnnotationsInfo, RuntimeVisibleAnnotationsInfo, SourceDebugExtensionInfo,
+};
+use crate::bytecode::flags::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+use crate::bytecode::pool::{self, ConstantPoolEntry, ConstantPoolIndex, ConstantPoolView};
+use crate::bytecode::reader::borrowed::{
+    decode_attribute, decode_code, next_annotation, read_annotations, to_owned_classfile,
+};
+use crate::bytecode::reader::options::{Diagnostic, ParseMode};
+use crate::bytecode::reader::BufferedReader;
+use crate::bytecode::{mutf8, BytecodeError, ClassFileVersion, Interface};
+use crate::error::Error;
+
+/// A class file that borrows its strings and attributes from the input buffer.
+#[derive(Debug)]
+pub struct ClassFile<'a> {
+    pub version: ClassFileVersion,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: ConstantPoolIndex,
+    pub super_class: ConstantPoolIndex,
+    pub interfaces: Vec<Interface>,
+    pub fields: Vec<Field<'a>>,
+    pub methods: Vec<Method<'a>>,
+    pub attributes: Vec<Attribute<'a>>,
+    pub(crate) shared: Arc<Shared<'a>>,
+}
+
+impl<'a> ClassFile<'a> {
+    pub fn constant_pool(&self) -> &ConstantPool<'a> {
+        &self.shared.constant_pool
+    }
+
+    /// Returns the first attribute with the given name.
+    pub fn attribute(&self, name: &str) -> Option<&Attribute<'a>> {
+        find_attribute(&self.attributes, name)
+    }
+
+    /// Returns the `SourceDebugExtension` attribute of the class, see
+    /// [Attribute::source_debug_extension].
+    pub fn source_debug_extension(&self) -> Option<SourceDebugExtension<'a>> {
+        self.attribute(SourceDebugExtensionInfo::NAME)?
+            .source_debug_extension()
+    }
+
+    /// Returns the annotations of the attribute with the given name, see
+    /// [Attribute::annotations].
+    pub fn annotations(&self, name: &str) -> Result<Option<Annotations<'a>>, Error> {
+        find_annotations(&self.attributes, name)
+    }
+
+    /// Returns the diagnostics that have been recorded so far in [ParseMode::Lenient], while
+    /// reading the class file and while decoding its attributes.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.shared.diagnostics()
+    }
+
+    /// Converts the class file into an owned [ClassFile](crate::bytecode::ClassFile) by copying
+    /// the constant pool and decoding all attributes with the [Container] of the parser. In
+    /// [ParseMode::Lenient], recovered problems are dropped; use
+    /// [into_owned_with_diagnostics](ClassFile::into_owned_with_diagnostics) to inspect them.
+    pub fn into_owned(self) -> Result<crate::bytecode::ClassFile, Error> {
+        to_owned_classfile(self)
+    }
+
+    /// Converts the class file like [into_owned](ClassFile::into_owned) and returns it together
+    /// with all diagnostics that have been recorded for it in [ParseMode::Lenient].
+    pub fn into_owned_with_diagnostics(
+        self,
+    ) -> Result<(crate::bytecode::ClassFile, Vec<Diagnostic>), Error> {
+        let shared = Arc::clone(&self.shared);
+        let cf = to_owned_classfile(self)?;
+        Ok((cf, shared.diagnostics()))
+    }
+}
+
+/// The state a class file shares with its attributes, which is needed for decoding them.
+#[derive(Debug)]
+pub(crate) struct Shared<'a> {
+    /// The container with the factories used for decoding the attributes.
+    pub(crate) container: &'a Container,
+    pub(crate) constant_pool: ConstantPool<'a>,
+    /// The mode the class file has been read in, which also applies to decoding its attributes.
+    pub(crate) mode: ParseMode,
+    diagnostics: Mutex<Vec<Diagnostic>>,
+}
+
+impl<'a> Shared<'a> {
+    pub(crate) fn new(
+        container: &'a Container,
+        constant_pool: ConstantPool<'a>,
+        mode: ParseMode,
+    ) -> Self {
+        Self {
+            container,
+            constant_pool,
+            mode,
+            diagnostics: Mutex::new(vec![]),
+        }
+    }
+
+    /// Appends the given diagnostics to the ones recorded for the class file.
+    pub(crate) fn record(&self, diagnostics: Vec<Diagnostic>) {
+        if !diagnostics.is_empty() {
+            self.lock_diagnostics().extend(diagnostics);
+        }
+    }
+
+    pub(crate) fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.lock_diagnostics().clone()
+    }
+
+    fn lock_diagnostics(&self) -> MutexGuard<'_, Vec<Diagnostic>> {
+        // The diagnostics stay consistent even if another thread panicked while recording them.
+        self.diagnostics
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A constant pool entry that borrows the bytes of `CONSTANT_Utf8_info` entries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant<'a> {
+    /// The modified UTF-8 bytes of a `CONSTANT_Utf8_info` entry.
+    Utf8(&'a [u8]),
+    /// Any other entry, which doesn't contain data that could be borrowed.
+    Entry(ConstantPoolEntry),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConstantPool<'a> {
+    /// The entries by their index, where the first slot is always empty.
+    entries: Vec<Option<Constant<'a>>>,
+}
+
+impl ConstantPoolView for ConstantPool<'_> {
+    fn utf8_at(&self, index: ConstantPoolIndex) -> Result<Cow<'_, str>, BytecodeError> {
+        ConstantPool::utf8_at(self, index)
+    }
+}
+
+impl<'a> ConstantPool<'a> {
+    pub fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    /// Sets the entry at the given index, which is expected to be the next free slot of the pool
+    /// while reading it.
+    pub(crate) fn push(&mut self, index: usize, constant: Constant<'a>) {
+        self.entries.resize(index, None);
+        self.entries.push(Some(constant));
+    }
+
+    /// Returns the entry at the given index, or `None` if the index is not present.
+    pub fn get(&self, index: ConstantPoolIndex) -> Option<&Constant<'a>> {
+        self.entries.get(index.index())?.as_ref()
+    }
+
+    /// Returns the decoded `CONSTANT_Utf8_info` entry at the given index, which borrows the
+    /// bytes of the class file if possible. See [utf8_at](pool::ConstantPool::utf8_at) for the
+    /// errors that are reported.
+    pub fn utf8_at(&self, index: ConstantPoolIndex) -> Result<Cow<'a, str>, BytecodeError> {
+        match self.get(index) {
+            Some(Constant::Utf8(bytes)) => mutf8::decode_borrowed(bytes),
+            Some(_) => Err(BytecodeError::UnexpectedConstant {
+                index: index.index(),
+                expected: "Utf8 constant",
+            }),
+            None => Err(BytecodeError::ConstantPoolEntryNotFound(index.index())),
+        }
+    }
+
+    /// Returns the number of slots in the [ConstantPool], including unusable slots.
+    pub fn size(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    /// Copies the entries into an owned [ConstantPool](pool::ConstantPool).
+    pub fn into_owned(self) -> pool::ConstantPool {
+        let mut pool = pool::ConstantPool::new();
+        for (index, constant) in self.entries.into_iter().enumerate() {
+            let entry = match constant {
+                Some(Constant::Utf8(bytes)) => ConstantPoolEntry::Utf8 {
+                    length: bytes.len() as u16,
+                    bytes: bytes.to_vec(),
+                },
+                // The unusable slots are filled when inserting the wide entry before them.
+                Some(Constant::Entry(ConstantPoolEntry::Unusable)) | None => continue,
+                Some(Constant::Entry(entry)) => entry,
+            };
+            // The indices are unique, so inserting an entry can't fail.
+            let _ = pool.insert(index.into(), entry);
+        }
+        pool
+    }
+}
+
+#[derive(Debug)]
+pub struct Field<'a> {
+    pub access_flags: FieldAccessFlags,
+    pub name_index: ConstantPoolIndex,
+    pub name: Cow<'a, str>,
+    pub descriptor_index: ConstantPoolIndex,
+    /// The unparsed field descriptor.
+    pub descriptor: Cow<'a, str>,
+    pub attributes: Vec<Attribute<'a>>,
+}
+
+impl<'a> Field<'a> {
+    /// Returns the first attribute with the given name.
+    pub fn attribute(&self, name: &str) -> Option<&Attribute<'a>> {
+        find_attribute(&self.attributes, name)
+    }
+
+    /// Decodes the first attribute with the given name, see [Attribute::get].
+    pub fn get_attribute<T: AnyAttribute + 'static>(
+        &self,
+        name: &str,
+    ) -> Result<Option<&T>, Error> {
+        get_attribute(&self.attributes, name)
+    }
+
+    /// Decodes the first attribute named [T::NAME](NamedAttribute::NAME), see [Attribute::get].
+    pub fn get<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Result<Option<&T>, Error> {
+        get_attribute(&self.attributes, T::NAME)
+    }
+
+    /// Returns the annotations of the attribute with the given name, see
+    /// [Attribute::annotations].
+    pub fn annotations(&self, name: &str) -> Result<Option<Annotations<'a>>, Error> {
+        find_annotations(&self.attributes, name)
+    }
+}
+
+#[derive(Debug)]
+pub struct Method<'a> {
+    pub access_flags: MethodAccessFlags,
+    pub name_index: ConstantPoolIndex,
+    pub name: Cow<'a, str>,
+    pub descriptor_index: ConstantPoolIndex,
+    /// The unparsed method descriptor.
+    pub descriptor: Cow<'a, str>,
+    pub attributes: Vec<Attribute<'a>>,
+    /// The `Code` attribute, which is read on first access.
+    pub(crate) code: OnceLock<Option<Code<'a>>>,
+}
+
+impl<'a> Method<'a> {
+    /// Returns the first attribute with the given name.
+    pub fn attribute(&self, name: &str) -> Option<&Attribute<'a>> {
+        find_attribute(&self.attributes, name)
+    }
+
+    /// Decodes the first attribute with the given name, see [Attribute::get].
+    pub fn get_attribute<T: AnyAttribute + 'static>(
+        &self,
+        name: &str,
+    ) -> Result<Option<&T>, Error> {
+        get_attribute(&self.attributes, name)
+    }
+
+    /// Decodes the first attribute named [T::NAME](NamedAttribute::NAME), see [Attribute::get].
+    pub fn get<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Result<Option<&T>, Error> {
+        get_attribute(&self.attributes, T::NAME)
+    }
+
+    /// Returns the annotations of the attribute with the given name, see
+    /// [Attribute::annotations].
+    pub fn annotations(&self, name: &str) -> Result<Option<Annotations<'a>>, Error> {
+        find_annotations(&self.attributes, name)
+    }
+
+    /// Reads the `Code` attribute of the method on first access, whose `code` and nested
+    /// attributes borrow the bytes of the class file. Returns `None` for abstract and native
+    /// methods, and in [ParseMode::Lenient] also if the attribute is malformed.
+    pub fn code(&self) -> Result<Option<&Code<'a>>, Error> {
+        if self.code.get().is_none() {
+            let Some(attr) = self.attribute("Code") else {
+                return Ok(None);
+            };
+            let (code, diagnostics) = decode_code(attr)?;
+            // Diagnostics are only recorded by the thread whose result is kept.
+            if self.code.set(code).is_ok() {
+                attr.shared.record(diagnostics);
+            }
+        }
+        Ok(self.code.get().and_then(Option::as_ref))
+    }
+}
+
+/// An attribute that is decoded on first access.
+pub struct Attribute<'a> {
+    pub name_index: ConstantPoolIndex,
+    pub name: Cow<'a, str>,
+    /// The bytes following `attribute_length`, e.g. the debug extension of a
+    /// `SourceDebugExtension` attribute.
+    pub info: &'a [u8],
+    /// A reader that is bounded to the whole attribute, including its header.
+    pub(crate) reader: BufferedReader<'a>,
+    pub(crate) shared: Arc<Shared<'a>>,
+    /// The decoded attribute, or `None` if it was skipped by the container.
+    pub(crate) decoded: OnceLock<Option<Box<dyn AnyAttribute>>>,
+}
+
+impl<'a> Attribute<'a> {
+    /// Returns the offset of the attribute within the class file.
+    pub fn offset(&self) -> usize {
+        self.reader.offset()
+    }
+
+    /// Decodes the attribute with the factory the [Container] of the parser has registered for
+    /// its name. The attribute is decoded only once and returns `None` if the container skips it.
+    /// In [ParseMode::Lenient], a malformed attribute is skipped as well, and the problem is
+    /// recorded in the [diagnostics](ClassFile::diagnostics) of its class file.
+    pub fn decode(&self) -> Result<Option<&dyn AnyAttribute>, Error> {
+        if self.decoded.get().is_none() {
+            let (decoded, diagnostics) = decode_attribute(self)?;
+            // Diagnostics are only recorded by the thread whose result is kept.
+            if self.decoded.set(decoded).is_ok() {
+                self.shared.record(diagnostics);
+            }
+        }
+        Ok(self.decoded.get().and_then(Option::as_deref))
+    }
+
+    /// Decodes the attribute like [decode](Attribute::decode) and returns it if it is a `T`.
+    pub fn get<T: AnyAttribute + 'static>(&self) -> Result<Option<&T>, Error> {
+        Ok(self
+            .decode()?
+            .and_then(|attr| attr.as_any_ref().downcast_ref::<T>()))
+    }
+
+    /// Returns the debug extension of a `SourceDebugExtension` attribute, which borrows the
+    /// bytes of the class file, or `None` if the attribute has another name.
+    pub fn source_debug_extension(&self) -> Option<SourceDebugExtension<'a>> {
+        (self.name == SourceDebugExtensionInfo::NAME).then_some(SourceDebugExtension {
+            debug_extension: self.info,
+        })
+    }
+
+    /// Returns the annotations of a `RuntimeVisibleAnnotations` or `RuntimeInvisibleAnnotations`
+    /// attribute, or `None` if the attribute has another name. The annotations are decoded
+    /// while iterating over them, regardless of the [ParseMode].
+    pub fn annotations(&self) -> Result<Option<Annotations<'a>>, Error> {
+        if self.name != RuntimeVisibleAnnotationsInfo::NAME
+            && self.name != RuntimeInvisibleAnnotationsInfo::NAME
+        {
+            return Ok(None);
+        }
+        read_annotations(self).map(Some)
+    }
+}
+
+impl fmt::Debug for Attribute<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Attribute")
+            .field("name_index", &self.name_index)
+            .field("name", &self.name)
+            .field("offset", &self.offset())
+            .field("decoded", &self.decoded)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A `SourceDebugExtension` attribute, whose debug extension borrows the bytes of the class file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceDebugExtension<'a> {
+    pub debug_extension: &'a [u8],
+}
+
+/// The annotations of a `RuntimeVisibleAnnotations` or `RuntimeInvisibleAnnotations` attribute,
+/// which are decoded one at a time from the bytes of the class file. Iterating stops after the
+/// first malformed annotation.
+#[derive(Debug, Clone)]
+pub struct Annotations<'a> {
+    pub num_annotations: u16,
+    /// The name of the attribute, which is reported within errors.
+    pub(crate) name: Cow<'a, str>,
+    /// A reader that is positioned at the next annotation.
+    pub(crate) reader: BufferedReader<'a>,
+    /// The index of the next annotation.
+    pub(crate) index: usize,
+}
+
+impl Iterator for Annotations<'_> {
+    type Item = Result<Annotation, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        next_annotation(self)
+    }
+}
+
+/// A `Code` attribute, whose `code` and nested attributes borrow the bytes of the class file.
+#[derive(Debug)]
+pub struct Code<'a> {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: &'a [u8],
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub attributes: Vec<Attribute<'a>>,
+}
+
+impl<'a> Code<'a> {
+    /// Returns the first attribute with the given name.
+    pub fn attribute(&self, name: &str) -> Option<&Attribute<'a>> {
+        find_attribute(&self.attributes, name)
+    }
+
+    /// Decodes the first attribute with the given name, see [Attribute::get].
+    pub fn get_attribute<T: AnyAttribute + 'static>(
+        &self,
+        name: &str,
+    ) -> Result<Option<&T>, Error> {
+        get_attribute(&self.attributes, name)
+    }
+
+    /// Decodes the first attribute named [T::NAME](NamedAttribute::NAME), see [Attribute::get].
+    pub fn get<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Result<Option<&T>, Error> {
+        get_attribute(&self.attributes, T::NAME)
+    }
+}
+
+fn find_attribute<'b, 'a>(
+    attributes: &'b [Attribute<'a>],
+    name: &str,
+) -> Option<&'b Attribute<'a>> {
+    attributes.iter().find(|attr| attr.name == name)
+}
+
+fn find_annotations<'a>(
+    attributes: &[Attribute<'a>],
+    name: &str,
+) -> Result<Option<Annotations<'a>>, Error> {
+    match find_attribute(attributes, name) {
+        Some(attr) => attr.annotations(),
+        None => Ok(None),
+    }
+}
+
+fn get_attribute<'b, T: AnyAttribute + 'static>(
+    attributes: &'b [Attribute],
+    name: &str,
+) -> Result<Option<&'b T>, Error> {
+    match find_attribute(attributes, name) {
+        Some(attr) => attr.get::<T>(),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,397 @@
+//! Removal of unused constant pool entries, e.g. after methods or debug attributes have been
+//! removed from a class file, see [ClassFile::compact_constant_pool].
+
+use crate::bytecode::attributes::{
+    Annotation, Attributes, ElementValue, StackMapFrame, TypeAnnotation, VerificationTypeInfo,
+    VisitIndex,
+};
+use crate::bytecode::pool::{ConstantPool, ConstantPoolEntry, ConstantPoolIndex};
+use crate::bytecode::reader::BufferedReader;
+use crate::bytecode::{BytecodeError, ClassFile};
+use crate::decoder::instructions::parse_instruction;
+use crate::error::{Context, Error};
+
+impl ClassFile {
+    /// Removes the constant pool entries that are not referenced, directly or through other
+    /// entries, by the class, its fields, methods, attributes and bytecode. The remaining entries
+    /// keep their order and are renumbered without gaps, and every index that refers to them is
+    /// rewritten.
+    ///
+    /// Fails without changing the class file if an attribute cannot report its indices, see
+    /// [Attribute::visit_indices](crate::bytecode::attributes::Attribute::visit_indices), if the
+    /// bytecode of a method cannot be decoded or if an index does not refer to a usable entry.
+    pub fn compact_constant_pool(&mut self) -> Result<(), Error> {
+        let mut pending = vec![];
+        visit_classfile_indices(self, &mut |index| {
+            pending.push(*index);
+            Ok(())
+        })?;
+
+        let end = self.constant_pool.size() + 1;
+        let mut reachable = vec![false; end];
+        while let Some(index) = pending.pop() {
+            // Optional references, e.g. the `super_class` of `java/lang/Object`, are `0`.
+            if index.index() == 0 || reachable.get(index.index()) == Some(&true) {
+                continue;
+            }
+            let entry = self
+                .constant_pool
+                .get(index)
+                .ok_or(BytecodeError::ConstantPoolEntryNotFound(index.index()))?;
+            if entry.tag().is_none() {
+                return Err(Error::new(BytecodeError::UnexpectedConstant {
+                    index: index.index(),
+                    expected: "usable constant",
+                }));
+            }
+            reachable[index.index()] = true;
+            visit_entry_indices(&mut entry.clone(), |index| pending.push(*index));
+        }
+
+        // As the entries keep their order, indices only ever decrease and the operands of `ldc`
+        // instructions still fit into a single byte.
+        let mut remapped = vec![0; end];
+        let mut next = 1;
+        for (index, entry) in self.constant_pool.iter() {
+            if reachable[index.index()] {
+                remapped[index.index()] = next;
+                next += if entry.is_wide() { 2 } else { 1 };
+            }
+        }
+        let remap = |index: &mut ConstantPoolIndex| {
+            *index = ConstantPoolIndex::new(remapped[index.index()]);
+        };
+
+        let mut pool = ConstantPool::new();
+        for (index, entry) in self.constant_pool.iter() {
+            if reachable[index.index()] {
+                let mut entry = entry.clone();
+                visit_entry_indices(&mut entry, remap);
+                pool.insert(ConstantPoolIndex::new(remapped[index.index()]), entry)?;
+            }
+        }
+        let count = u16::try_from(next).map_err(|_| BytecodeError::ValueOutOfRange(next))?;
+
+        // Every index has been visited successfully above and refers to a reachable entry, so
+        // rewriting them cannot fail halfway.
+        visit_classfile_indices(self, &mut |index| {
+            remap(index);
+            Ok(())
+        })?;
+        self.constant_pool = pool;
+        self.constant_pool_count = count;
+        Ok(())
+    }
+}
+
+/// Calls `visit` with every index of the class file that refers to the constant pool.
+fn visit_classfile_indices(cf: &mut ClassFile, visit: &mut VisitIndex) -> Result<(), Error> {
+    let within_class = |err: BytecodeError| Error::new(err).within(Context::Class);
+    visit(&mut cf.this_class).map_err(within_class)?;
+    visit(&mut cf.super_class).map_err(within_class)?;
+    for interface in &mut cf.interfaces {
+        visit(&mut interface.name_index).map_err(within_class)?;
+    }
+    for field in &mut cf.fields {
+        let mut visit_field = || -> Result<(), Error> {
+            visit(&mut field.name_index)?;
+            visit(&mut field.descriptor_index)?;
+            visit_attributes_indices(&mut field.attributes, visit)
+        };
+        visit_field().map_err(|err| err.within(Context::Field(field.name.clone())))?;
+    }
+    for method in &mut cf.methods {
+        let mut visit_method = || -> Result<(), Error> {
+            visit(&mut method.name_index)?;
+            visit(&mut method.descriptor_index)?;
+            visit_attributes_indices(&mut method.attributes, visit)
+        };
+        visit_method().map_err(|err| err.within(Context::Method(method.name.clone())))?;
+    }
+    visit_attributes_indices(&mut cf.attributes, visit)
+}
+
+/// Calls `visit` with every constant pool index that `entry` refers to. The
+/// `bootstrap_method_attr_index` of dynamic entries is an index into the `BootstrapMethods`
+/// attribute instead and is not visited.
+fn visit_entry_indices(
+    entry: &mut ConstantPoolEntry,
+    mut visit: impl FnMut(&mut ConstantPoolIndex),
+) {
+    match entry {
+        ConstantPoolEntry::Class { name_index }
+        | ConstantPoolEntry::Module { name_index }
+        | ConstantPoolEntry::Package { name_index } => visit(name_index),
+        ConstantPoolEntry::FieldRef {
+            class_index,
+            name_and_type_index,
+        }
+        | ConstantPoolEntry::MethodRef {
+            class_index,
+            name_and_type_index,
+        }
+        | ConstantPoolEntry::InterfaceMethodRef {
+            class_index,
+            name_and_type_index,
+        } => {
+            visit(class_index);
+            visit(name_and_type_index);
+        }
+        ConstantPoolEntry::String { string_index } => visit(string_index),
+        ConstantPoolEntry::NameAndType {
+            name_index,
+            descriptor_index,
+        } => {
+            visit(name_index);
+            visit(descriptor_index);
+        }
+        ConstantPoolEntry::MethodHandle {
+            reference_index, ..
+        } => visit(reference_index),
+        ConstantPoolEntry::MethodType { descriptor_index } => visit(descriptor_index),
+        ConstantPoolEntry::Dynamic {
+            name_and_type_index,
+            ..
+        }
+        | ConstantPoolEntry::InvokeDynamic {
+            name_and_type_index,
+            ..
+        } => visit(name_and_type_index),
+        ConstantPoolEntry::Integer { .. }
+        | ConstantPoolEntry::Float { .. }
+        | ConstantPoolEntry::Long { .. }
+        | ConstantPoolEntry::Double { .. }
+        | ConstantPoolEntry::Utf8 { .. }
+        | ConstantPoolEntry::Unusable => {}
+    }
+}
+
+/// Calls `visit` with the indices of all `attributes`, see
+/// [Attribute::visit_indices](crate::bytecode::attributes::Attribute::visit_indices).
+pub(crate) fn visit_attributes_indices(
+    attributes: &mut Attributes,
+    visit: &mut VisitIndex,
+) -> Result<(), Error> {
+    for (name, attribute) in attributes.iter_mut() {
+        attribute
+            .visit_indices_any(visit)
+            .map_err(|err| err.within(Context::Attribute(name.to_string())))?;
+    }
+    Ok(())
+}
+
+/// Calls `visit` with the constant pool operands of the instructions in `code` and writes the
+/// visited indices back.
+pub(crate) fn visit_code_indices(code: &mut [u8], visit: &mut VisitIndex) -> Result<(), Error> {
+    // The operands are located first, as they cannot be rewritten while the code is being read.
+    let mut operands = vec![];
+    let mut reader = BufferedReader::new(code);
+    while reader.remaining() > 0 {
+        let position = reader.position();
+        let opcode = reader.take::<u8>()?;
+        match opcode {
+            // ldc
+            0x12 => operands.push((position + 1, false)),
+            // ldc_w, ldc2_w, getstatic to invokedynamic, new, anewarray, checkcast, instanceof
+            // and multianewarray
+            0x13 | 0x14 | 0xb2..=0xbb | 0xbd | 0xc0 | 0xc1 | 0xc5 => {
+                operands.push((position + 1, true))
+            }
+            _ => {}
+        }
+        parse_instruction(opcode, &mut reader)?;
+    }
+
+    for (position, wide) in operands {
+        if wide {
+            let mut index =
+                ConstantPoolIndex::new(u16::from_be_bytes([code[position], code[position + 1]]));
+            visit(&mut index)?;
+            let value = u16::try_from(index.index())
+                .map_err(|_| BytecodeError::ValueOutOfRange(index.index()))?;
+            code[position..position + 2].copy_from_slice(&value.to_be_bytes());
+        } else {
+            let mut index = ConstantPoolIndex::new(code[position]);
+            visit(&mut index)?;
+            code[position] = u8::try_from(index.index())
+                .map_err(|_| BytecodeError::ValueOutOfRange(index.index()))?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn visit_stackmapframe_indices(
+    frame: &mut StackMapFrame,
+    visit: &mut VisitIndex,
+) -> Result<(), Error> {
+    let infos = match frame {
+        StackMapFrame::SameLocals1StackItemFrame { stack, .. }
+        | StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => {
+            std::slice::from_mut(stack)
+        }
+        StackMapFrame::AppendFrame { locals, .. } => locals.as_mut_slice(),
+        StackMapFrame::FullFrame { locals, stack, .. } => {
+            for info in locals.iter_mut().chain(stack.iter_mut()) {
+                visit_verificationtype_indices(info, visit)?;
+            }
+            return Ok(());
+        }
+        StackMapFrame::SameFrame { .. }
+        | StackMapFrame::ChopFrame { .. }
+        | StackMapFrame::SameFrameExtended { .. } => &mut [],
+    };
+    for info in infos {
+        visit_verificationtype_indices(info, visit)?;
+    }
+    Ok(())
+}
+
+fn visit_verificationtype_indices(
+    info: &mut VerificationTypeInfo,
+    visit: &mut VisitIndex,
+) -> Result<(), Error> {
+    if let VerificationTypeInfo::Object { class } = info {
+        visit(class)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn visit_annotation_indices(
+    annotation: &mut Annotation,
+    visit: &mut VisitIndex,
+) -> Result<(), Error> {
+    visit(&mut annotation.type_index)?;
+    for pair in &mut annotation.element_value_pairs {
+        visit(&mut pair.element_name_index)?;
+        visit_elementvalue_indices(&mut pair.value, visit)?;
+    }
+    Ok(())
+}
+
+/// Calls `visit` with the indices of the type and the element values of `annotation`. The
+/// indices of its target and type path are positions within the annotated construct and are not
+/// visited.
+pub(crate) fn visit_typeannotation_indices(
+    annotation: &mut TypeAnnotation,
+    visit: &mut VisitIndex,
+) -> Result<(), Error> {
+    visit(&mut annotation.type_index)?;
+    for pair in &mut annotation.element_value_pairs {
+        visit(&mut pair.element_name_index)?;
+        visit_elementvalue_indices(&mut pair.value, visit)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn visit_elementvalue_indices(
+    value: &mut ElementValue,
+    visit: &mut VisitIndex,
+) -> Result<(), Error> {
+    match value {
+        ElementValue::ConstValueIndex {
+            const_value_index, ..
+        } => visit(const_value_index)?,
+        ElementValue::EnumConstValue {
+            type_name_index,
+            const_name_index,
+        } => {
+            visit(type_name_index)?;
+            visit(const_name_index)?;
+        }
+        ElementValue::ClassInfoIndex(index) => visit(index)?,
+        ElementValue::Annotation(annotation) => visit_annotation_indices(annotation, visit)?,
+        ElementValue::Array { values, .. } => {
+            for value in values {
+                visit_elementvalue_indices(value, visit)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::attributes::{CodeInfo, RawAttribute};
+    use crate::bytecode::pool::{ConstantTag, MemberKind};
+    use crate::bytecode::tests::{CONSTANTS, FRAMES, POINT, SIMPLE};
+    use crate::bytecode::writer::containers::write_classfile;
+    use crate::bytecode::writer::BufferedWriter;
+    use crate::bytecode::{BytecodeError, ClassFile};
+    use crate::error::Context;
+
+    #[test]
+    fn compact_constant_pool() {
+        // javac leaves the class `java/lang/Long` of the folded constant `Long.MAX_VALUE` in the
+        // pool of `Constants`, the other pools are already compact.
+        let fixtures: [(&[u8], usize); 4] = [(SIMPLE, 0), (CONSTANTS, 2), (FRAMES, 0), (POINT, 0)];
+        for (input, unused) in fixtures {
+            let mut cf = ClassFile::parse(input).unwrap();
+            let size = cf.constant_pool.size();
+            cf.compact_constant_pool().unwrap();
+            assert_eq!(cf.constant_pool.size(), size - unused);
+            assert_eq!(cf.validate(), Ok(()));
+            let mut writer = BufferedWriter::new();
+            write_classfile(&mut writer, &cf).unwrap();
+            if unused == 0 {
+                assert_eq!(writer.as_bytes(), input);
+            }
+        }
+
+        // Without the generated record methods, their invokedynamic call sites, bootstrap method
+        // and line numbers are unused.
+        let mut cf = ClassFile::parse(POINT).unwrap();
+        let size = cf.constant_pool.size();
+        cf.methods
+            .retain(|method| !["toString", "hashCode", "equals"].contains(&method.name.as_str()));
+        cf.attributes.retain(|name| name != "BootstrapMethods");
+        for method in &mut cf.methods {
+            let code = method.attributes.attribute_mut::<CodeInfo>().unwrap();
+            code.attributes.retain(|name| name != "LineNumberTable");
+        }
+        cf.compact_constant_pool().unwrap();
+        assert!(cf.constant_pool.size() < size);
+        assert_eq!(cf.constant_pool_count as usize, cf.constant_pool.size() + 1);
+        assert_eq!(cf.validate(), Ok(()));
+
+        let mut writer = BufferedWriter::new();
+        write_classfile(&mut writer, &cf).unwrap();
+        let cf = ClassFile::parse(writer.as_bytes()).unwrap();
+        let pool = &cf.constant_pool;
+        assert_eq!(pool.class_name(cf.this_class).unwrap(), "org/example/Point");
+        assert_eq!(pool.class_name(cf.super_class).unwrap(), "java/lang/Record");
+        assert!(pool
+            .iter()
+            .all(|(_, entry)| entry.tag() != Some(ConstantTag::InvokeDynamic)));
+
+        // `x()` is `aload_0; getfield #x; ireturn`.
+        let x = cf.methods.iter().find(|method| method.name == "x").unwrap();
+        let code = &x.attribute::<CodeInfo>().unwrap().code;
+        let field = u16::from_be_bytes([code[2], code[3]]);
+        let field = pool.member_ref(field.into()).unwrap();
+        assert_eq!(field.kind, MemberKind::Field);
+        assert_eq!((field.name.as_str(), field.descriptor.as_str()), ("x", "I"));
+    }
+
+    #[test]
+    fn compact_with_unknown_attribute() {
+        let mut cf = ClassFile::parse(POINT).unwrap();
+        let pool = cf.constant_pool.clone();
+        cf.attributes.push(
+            "Unknown",
+            Box::new(RawAttribute {
+                attribute_name_index: cf.this_class,
+                attribute_length: 0,
+                name: "Unknown".to_string(),
+                bytes: vec![],
+            }),
+        );
+
+        let err = cf.compact_constant_pool().unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &BytecodeError::UnsupportedAttributeName("Unknown".to_string())
+        );
+        assert_eq!(err.context(), [Context::Attribute("Unknown".to_string())]);
+        assert_eq!(cf.constant_pool, pool);
+    }
+}
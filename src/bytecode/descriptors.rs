@@ -1,57 +1,8 @@
-use super::BytecodeError;
-use crate::bytecode::{BaseType, Descriptor, DescriptorKind, FieldType};
+use crate::bytecode::{
+    BaseType, BytecodeError, Descriptor, FieldType, MethodDescriptor, ReturnType,
+};
 
 impl Descriptor {
-    /// ```text
-    /// MethodDescriptor:
-    ///     ({ParameterDescriptor}) ReturnDescriptor
-    ///
-    /// ParameterDescriptor:
-    ///     FieldType
-    ///
-    /// ReturnDescriptor:
-    ///     FieldType
-    ///     VoidDescriptor
-    ///
-    /// VoidDescriptor:
-    ///     'V'
-    /// ```
-    pub fn parse_from_method(descriptor: String) -> Vec<Descriptor> {
-        let mut chars = descriptor.chars();
-        let mut descriptors: Vec<Descriptor> = vec![];
-
-        // check if the method has parameters by checking if the first character is '('
-        // if it is, then we have at least one parameter to parse.
-        // see: https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.3.3
-        let mut param_chars: Vec<char> = vec![];
-        if chars.next() == Some('(') {
-            param_chars = chars.by_ref().take_while(|c| *c != ')').collect();
-            param_chars.reverse();
-        }
-
-        while !param_chars.is_empty() {
-            if let Some(ty) = parse_field_type(&mut param_chars) {
-                descriptors.push(Descriptor {
-                    kind: DescriptorKind::Parameter,
-                    ty,
-                });
-            }
-        }
-
-        // at this point we should have parsed all parameters and the next element is the return
-        // type
-        let mut ret_chars: Vec<char> = chars.collect();
-        ret_chars.reverse();
-        if let Some(ty) = parse_field_type(&mut ret_chars) {
-            descriptors.push(Descriptor {
-                kind: DescriptorKind::Return,
-                ty,
-            });
-        }
-
-        descriptors
-    }
-
     /// ```text
     /// FieldDescriptor:
     ///     FieldType
@@ -74,68 +25,261 @@ impl Descriptor {
     ///     FieldType
     /// ```
     pub fn parse_from_field(descriptor: String) -> Result<Descriptor, BytecodeError> {
-        let mut chars = descriptor.chars().collect::<Vec<char>>();
-        chars.reverse();
-        let ty = match parse_field_type(&mut chars) {
-            Some(ty) => ty,
-            None => return Err(BytecodeError::InvalidDescriptor),
+        let mut parser = Parser::new(&descriptor);
+        let ty = parser.field_type()?;
+        parser.finish()?;
+        Ok(Descriptor { ty })
+    }
+}
+
+impl MethodDescriptor {
+    /// ```text
+    /// MethodDescriptor:
+    ///     ({ParameterDescriptor}) ReturnDescriptor
+    ///
+    /// ParameterDescriptor:
+    ///     FieldType
+    ///
+    /// ReturnDescriptor:
+    ///     FieldType
+    ///     VoidDescriptor
+    ///
+    /// VoidDescriptor:
+    ///     'V'
+    /// ```
+    pub fn parse(descriptor: &str) -> Result<MethodDescriptor, BytecodeError> {
+        let mut parser = Parser::new(descriptor);
+        let mut params = vec![];
+
+        parser.expect('(')?;
+        while !parser.eat(')') {
+            params.push(parser.field_type()?);
+        }
+
+        let ret = match parser.eat('V') {
+            true => ReturnType::Void,
+            false => ReturnType::Type(parser.field_type()?),
         };
-        Ok(Descriptor {
-            kind: DescriptorKind::Type,
-            ty,
-        })
+        parser.finish()?;
+
+        Ok(MethodDescriptor { params, ret })
+    }
+
+    /// Encodes the descriptor as a string, e.g. `(ILjava/lang/String;)V`. This is the inverse of
+    /// [parse](MethodDescriptor::parse).
+    pub fn to_descriptor(&self) -> String {
+        let mut encoded = String::from("(");
+        for param in &self.params {
+            param.encode(&mut encoded);
+        }
+        encoded.push(')');
+        match &self.ret {
+            ReturnType::Void => encoded.push('V'),
+            ReturnType::Type(ty) => ty.encode(&mut encoded),
+        }
+        encoded
+    }
+
+    /// Returns the number of local variables that the parameters occupy, where `long` and
+    /// `double` take two. The implicit `this` of instance methods is not included.
+    pub fn parameter_slots(&self) -> u16 {
+        self.params.iter().map(FieldType::slots).sum()
+    }
+
+    /// Returns the local variable index of each parameter. Instance methods receive `this` in
+    /// local variable 0, so their first parameter is at index 1.
+    pub fn parameter_indices(&self, is_static: bool) -> Vec<u16> {
+        let mut index = if is_static { 0 } else { 1 };
+        self.params
+            .iter()
+            .map(|param| {
+                let current = index;
+                index += param.slots();
+                current
+            })
+            .collect()
     }
 }
 
-pub(crate) fn parse_field_type(chars: &mut Vec<char>) -> Option<FieldType> {
-    match chars.pop() {
-        Some('B') => Some(FieldType::Base(BaseType::Byte)),
-        Some('C') => Some(FieldType::Base(BaseType::Char)),
-        Some('D') => Some(FieldType::Base(BaseType::Double)),
-        Some('F') => Some(FieldType::Base(BaseType::Float)),
-        Some('I') => Some(FieldType::Base(BaseType::Int)),
-        Some('J') => Some(FieldType::Base(BaseType::Long)),
-        Some('S') => Some(FieldType::Base(BaseType::Short)),
-        Some('Z') => Some(FieldType::Base(BaseType::Boolean)),
-        Some('L') => {
-            let mut class_name = String::new();
-            while let Some(c) = chars.pop() {
-                if c == ';' {
-                    break;
-                }
-                class_name.push(c);
+impl FieldType {
+    /// Returns the number of local variables or operand stack entries that a value of the type
+    /// occupies, which is two for `long` and `double`.
+    pub fn slots(&self) -> u16 {
+        match self {
+            FieldType::Base(BaseType::Long | BaseType::Double) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Encodes the type as a field descriptor, e.g. `[Ljava/lang/String;` or `I` for `int`.
+    pub fn to_descriptor(&self) -> String {
+        let mut encoded = String::new();
+        self.encode(&mut encoded);
+        encoded
+    }
+
+    fn encode(&self, encoded: &mut String) {
+        match self {
+            FieldType::Base(ty) => encoded.push(ty.to_descriptor()),
+            FieldType::Object(class_name) => {
+                encoded.push('L');
+                encoded.push_str(class_name);
+                encoded.push(';');
+            }
+            FieldType::Array(component) => {
+                encoded.push('[');
+                component.encode(encoded);
+            }
+        }
+    }
+}
+
+impl BaseType {
+    /// Returns the character that denotes the type in descriptors, e.g. `I` for `int`.
+    pub fn to_descriptor(&self) -> char {
+        match self {
+            BaseType::Byte => 'B',
+            BaseType::Char => 'C',
+            BaseType::Double => 'D',
+            BaseType::Float => 'F',
+            BaseType::Int => 'I',
+            BaseType::Long => 'J',
+            BaseType::Short => 'S',
+            BaseType::Boolean => 'Z',
+        }
+    }
+}
+
+/// A parser over the characters of a descriptor, which reports the position of the first invalid
+/// character as part of [BytecodeError::InvalidDescriptor].
+struct Parser<'a> {
+    descriptor: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(descriptor: &'a str) -> Self {
+        Self {
+            descriptor,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.descriptor[self.position..].chars().next()
+    }
+
+    /// Consumes the next character if it is `expected`.
+    fn eat(&mut self, expected: char) -> bool {
+        let matches = self.peek() == Some(expected);
+        if matches {
+            self.position += expected.len_utf8();
+        }
+        matches
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), BytecodeError> {
+        match self.eat(expected) {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    /// Fails if there are characters left after the descriptor.
+    fn finish(&self) -> Result<(), BytecodeError> {
+        match self.position == self.descriptor.len() {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn error(&self) -> BytecodeError {
+        BytecodeError::InvalidDescriptor {
+            descriptor: self.descriptor.to_string(),
+            position: self.position,
+        }
+    }
+
+    fn field_type(&mut self) -> Result<FieldType, BytecodeError> {
+        let ty = match self.peek() {
+            Some('B') => FieldType::Base(BaseType::Byte),
+            Some('C') => FieldType::Base(BaseType::Char),
+            Some('D') => FieldType::Base(BaseType::Double),
+            Some('F') => FieldType::Base(BaseType::Float),
+            Some('I') => FieldType::Base(BaseType::Int),
+            Some('J') => FieldType::Base(BaseType::Long),
+            Some('S') => FieldType::Base(BaseType::Short),
+            Some('Z') => FieldType::Base(BaseType::Boolean),
+            Some('L') => {
+                self.position += 1;
+                return self.class_name().map(FieldType::Object);
+            }
+            Some('[') => return self.array_type(),
+            _ => return Err(self.error()),
+        };
+        self.position += 1;
+        Ok(ty)
+    }
+
+    /// Reads the class name of an object type up to and including the terminating `;`. The name
+    /// is in internal form, so it consists of non-empty identifiers separated by `/`.
+    fn class_name(&mut self) -> Result<String, BytecodeError> {
+        let start = self.position;
+        let mut segment_start = self.position;
+        loop {
+            match self.peek() {
+                Some(';') | Some('/') if self.position == segment_start => return Err(self.error()),
+                Some(';') => break,
+                Some('/') => segment_start = self.position + 1,
+                Some('.') | Some('[') | None => return Err(self.error()),
+                Some(_) => {}
+            }
+            self.position += self.peek().map_or(0, char::len_utf8);
+        }
+        let class_name = self.descriptor[start..self.position].to_string();
+        self.position += 1;
+        Ok(class_name)
+    }
+
+    fn array_type(&mut self) -> Result<FieldType, BytecodeError> {
+        // An array type may have at most 255 dimensions, which also bounds the nesting of the
+        // resulting type.
+        let mut dimensions = 0;
+        while self.eat('[') {
+            dimensions += 1;
+            if dimensions > 255 {
+                self.position -= 1;
+                return Err(self.error());
             }
-            Some(FieldType::Object(class_name))
         }
-        Some('[') => {
-            let child = parse_field_type(chars);
-            child.map(|ty| FieldType::Array(Box::new(ty)))
+        let mut ty = self.field_type()?;
+        for _ in 0..dimensions {
+            ty = FieldType::Array(Box::new(ty));
         }
-        _ => Some(FieldType::Base(BaseType::Void)),
+        Ok(ty)
     }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use crate::bytecode::descriptors::{BaseType, Descriptor, DescriptorKind, FieldType};
+    use crate::bytecode::descriptors::{BaseType, Descriptor, FieldType};
+    use crate::bytecode::{BytecodeError, MethodDescriptor, ReturnType};
 
     #[test]
     fn test_parse_field_descriptor() {
         let input = [
             "I",
             "D",
-            "V",
-            "Ljava/lang/String",
+            "Ljava/lang/String;",
             "[D",
-            "[Ljava/lang/String",
+            "[Ljava/lang/String;",
             "[[D",
-            "[[Ljava/lang/String",
+            "[[Ljava/lang/String;",
         ];
 
         let expected = [
             FieldType::Base(BaseType::Int),
             FieldType::Base(BaseType::Double),
-            FieldType::Base(BaseType::Void),
             FieldType::Object("java/lang/String".to_string()),
             FieldType::Array(Box::new(FieldType::Base(BaseType::Double))),
             FieldType::Array(Box::new(FieldType::Object("java/lang/String".to_string()))),
@@ -150,33 +294,88 @@ pub mod tests {
         for (idx, t) in input.iter().enumerate() {
             let ret = Descriptor::parse_from_field(t.to_string());
             assert!(ret.is_ok());
-            assert_eq!(ret.unwrap().ty, expected[idx]);
+            let ty = ret.unwrap().ty;
+            assert_eq!(ty, expected[idx]);
+            assert_eq!(ty.to_descriptor(), *t);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_descriptors() {
+        let input = [
+            ("", 0),
+            ("V", 0),
+            ("X", 0),
+            ("II", 1),
+            ("Ljava/lang/String", 17),
+            ("L;", 1),
+            ("Ljava//String;", 6),
+            ("Ljava.lang.String;", 5),
+            ("[V", 1),
+        ];
+        for (descriptor, position) in input {
+            let err = Descriptor::parse_from_field(descriptor.to_string()).unwrap_err();
+            assert_eq!(
+                err,
+                BytecodeError::InvalidDescriptor {
+                    descriptor: descriptor.to_string(),
+                    position
+                }
+            );
+        }
+
+        let input = [
+            ("", 0),
+            ("IV", 0),
+            ("(V)V", 1),
+            ("(I", 2),
+            ("(I)", 3),
+            ("(I)VV", 4),
+            ("(Ljava/lang/String)V", 20),
+        ];
+        for (descriptor, position) in input {
+            let err = MethodDescriptor::parse(descriptor).unwrap_err();
+            assert_eq!(
+                err,
+                BytecodeError::InvalidDescriptor {
+                    descriptor: descriptor.to_string(),
+                    position
+                }
+            );
         }
     }
 
+    #[test]
+    fn test_parse_array_dimensions() {
+        let descriptor = format!("{}I", "[".repeat(255));
+        assert!(Descriptor::parse_from_field(descriptor).is_ok());
+
+        let descriptor = format!("{}I", "[".repeat(256));
+        assert!(Descriptor::parse_from_field(descriptor).is_err());
+    }
+
     #[test]
     fn test_parse_method_descriptors() {
         let input = "(IDLjava/lang/String;)V";
-        let expected = vec![
-            Descriptor {
-                kind: DescriptorKind::Parameter,
-                ty: FieldType::Base(BaseType::Int),
-            },
-            Descriptor {
-                kind: DescriptorKind::Parameter,
-                ty: FieldType::Base(BaseType::Double),
-            },
-            Descriptor {
-                kind: DescriptorKind::Parameter,
-                ty: FieldType::Object("java/lang/String".to_string()),
-            },
-            Descriptor {
-                kind: DescriptorKind::Return,
-                ty: FieldType::Base(BaseType::Void),
-            },
-        ];
+        let expected = MethodDescriptor {
+            params: vec![
+                FieldType::Base(BaseType::Int),
+                FieldType::Base(BaseType::Double),
+                FieldType::Object("java/lang/String".to_string()),
+            ],
+            ret: ReturnType::Void,
+        };
 
-        let ret = Descriptor::parse_from_method(input.to_string());
+        let ret = MethodDescriptor::parse(input).unwrap();
         assert_eq!(ret, expected);
+        assert_eq!(ret.to_descriptor(), input);
+        assert_eq!(ret.parameter_slots(), 4);
+        assert_eq!(ret.parameter_indices(true), [0, 1, 3]);
+        assert_eq!(ret.parameter_indices(false), [1, 2, 4]);
+
+        let ret = MethodDescriptor::parse("()[J").unwrap();
+        let long = FieldType::Base(BaseType::Long);
+        assert_eq!(ret.ret, ReturnType::Type(FieldType::Array(Box::new(long))));
+        assert_eq!(ret.parameter_slots(), 0);
     }
 }
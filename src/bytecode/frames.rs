@@ -0,0 +1,235 @@
+//! Expansion of the delta-encoded entries of a `StackMapTable` attribute into frames at absolute
+//! bytecode offsets.
+//! <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.4>
+
+use crate::bytecode::attributes::{StackMapFrame, StackMapTableInfo, VerificationTypeInfo};
+use crate::bytecode::flags::MethodAccessFlags;
+use crate::bytecode::pool::ConstantPool;
+use crate::bytecode::{BaseType, BytecodeError, FieldType, Method};
+
+/// A [VerificationTypeInfo] whose class has been resolved to its name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    /// An instance of the class with the given binary name, e.g. `java/lang/String`, or an array
+    /// with the given descriptor, e.g. `[I`.
+    Object(String),
+    /// An object created by the `new` instruction at the given offset, whose constructor has not
+    /// been invoked yet.
+    Uninitialized(u16),
+}
+
+impl VerificationType {
+    /// Resolves the class of an `Object` verification type with the given `pool`.
+    pub fn resolve(
+        info: &VerificationTypeInfo,
+        pool: &ConstantPool,
+    ) -> Result<VerificationType, BytecodeError> {
+        let ty = match info {
+            VerificationTypeInfo::Top => VerificationType::Top,
+            VerificationTypeInfo::Integer => VerificationType::Integer,
+            VerificationTypeInfo::Float => VerificationType::Float,
+            VerificationTypeInfo::Double => VerificationType::Double,
+            VerificationTypeInfo::Long => VerificationType::Long,
+            VerificationTypeInfo::Null => VerificationType::Null,
+            VerificationTypeInfo::UninitializedThis => VerificationType::UninitializedThis,
+            VerificationTypeInfo::Object { class } => {
+                VerificationType::Object(pool.class_name(*class)?)
+            }
+            VerificationTypeInfo::Uninitialized { offset } => {
+                VerificationType::Uninitialized(*offset)
+            }
+        };
+        Ok(ty)
+    }
+
+    /// Returns the verification type of a value of the given field type, e.g. `Integer` for
+    /// `boolean` or `short`.
+    pub fn from_field_type(ty: &FieldType) -> VerificationType {
+        match ty {
+            FieldType::Base(BaseType::Float) => VerificationType::Float,
+            FieldType::Base(BaseType::Double) => VerificationType::Double,
+            FieldType::Base(BaseType::Long) => VerificationType::Long,
+            FieldType::Base(_) => VerificationType::Integer,
+            FieldType::Object(class) => VerificationType::Object(class.clone()),
+            FieldType::Array(_) => VerificationType::Object(ty.to_descriptor()),
+        }
+    }
+}
+
+/// The types of the local variables and of the operand stack at a bytecode offset.
+///
+/// Like in the `StackMapTable` attribute, a `long` or `double` is a single entry of `locals`,
+/// although it occupies two local variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub offset: u32,
+    pub locals: Vec<VerificationType>,
+    pub stack: Vec<VerificationType>,
+}
+
+impl Frame {
+    /// Returns the implicit frame at offset 0 of `method`, which is declared in the class with
+    /// the binary name `class_name`, or `None` if the descriptor of `method` is invalid.
+    pub fn initial(class_name: &str, method: &Method) -> Option<Frame> {
+        let mut locals = vec![];
+        if !method.access_flags.contains(MethodAccessFlags::ACC_STATIC) {
+            if method.name == "<init>" && class_name != "java/lang/Object" {
+                locals.push(VerificationType::UninitializedThis);
+            } else {
+                locals.push(VerificationType::Object(class_name.to_string()));
+            }
+        }
+        let parameters = method
+            .descriptor
+            .as_ref()?
+            .params
+            .iter()
+            .map(VerificationType::from_field_type);
+        locals.extend(parameters);
+
+        Some(Frame {
+            offset: 0,
+            locals,
+            stack: vec![],
+        })
+    }
+}
+
+impl StackMapTableInfo {
+    /// Expands the entries of the table into [Frame]s at absolute bytecode offsets, each with the
+    /// full types of the locals and of the stack. The `initial` frame is the implicit frame of
+    /// the method, see [Frame::initial], and classes are resolved with the `pool`.
+    pub fn frames(
+        &self,
+        initial: &Frame,
+        pool: &ConstantPool,
+    ) -> Result<Vec<Frame>, BytecodeError> {
+        let resolve = |info| VerificationType::resolve(info, pool);
+        let resolve_all = |infos: &Vec<VerificationTypeInfo>| {
+            infos
+                .iter()
+                .map(|info| VerificationType::resolve(info, pool))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let mut frames: Vec<Frame> = Vec::with_capacity(self.entries.len());
+        let mut locals = initial.locals.clone();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let (offset_delta, stack) = match entry {
+                StackMapFrame::SameFrame { frame_type } => (*frame_type as u16, vec![]),
+                StackMapFrame::SameLocals1StackItemFrame { frame_type, stack } => {
+                    (*frame_type as u16 - 64, vec![resolve(stack)?])
+                }
+                StackMapFrame::SameLocals1StackItemFrameExtended {
+                    offset_delta,
+                    stack,
+                    ..
+                } => (*offset_delta, vec![resolve(stack)?]),
+                StackMapFrame::ChopFrame {
+                    frame_type,
+                    offset_delta,
+                } => {
+                    let chopped = 251 - *frame_type as usize;
+                    if chopped > locals.len() {
+                        return Err(BytecodeError::InvalidStackMapFrame(index));
+                    }
+                    locals.truncate(locals.len() - chopped);
+                    (*offset_delta, vec![])
+                }
+                StackMapFrame::SameFrameExtended { offset_delta, .. } => (*offset_delta, vec![]),
+                StackMapFrame::AppendFrame {
+                    offset_delta,
+                    locals: appended,
+                    ..
+                } => {
+                    for local in appended {
+                        locals.push(resolve(local)?);
+                    }
+                    (*offset_delta, vec![])
+                }
+                StackMapFrame::FullFrame {
+                    offset_delta,
+                    locals: full,
+                    stack,
+                    ..
+                } => {
+                    locals = resolve_all(full)?;
+                    (*offset_delta, resolve_all(stack)?)
+                }
+            };
+
+            // The offset of the first frame is its delta, every other frame is at least one byte
+            // behind its predecessor.
+            let offset = match frames.last() {
+                Some(previous) => previous.offset + offset_delta as u32 + 1,
+                None => offset_delta as u32,
+            };
+            frames.push(Frame {
+                offset,
+                locals: locals.clone(),
+                stack,
+            });
+        }
+
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::attributes::{CodeInfo, StackMapTableInfo};
+    use crate::bytecode::frames::{Frame, VerificationType};
+    use crate::bytecode::tests::FRAMES;
+    use crate::bytecode::ClassFile;
+
+    #[test]
+    fn test_frames() {
+        use VerificationType::*;
+
+        let cf = ClassFile::parse(FRAMES).unwrap();
+        let class_name = cf.constant_pool.text_of(cf.this_class).unwrap();
+        let this = || Object("org/example/Frames".to_string());
+        let string = || Object("java/lang/String".to_string());
+        let ints = || Object("[I".to_string());
+
+        let expected = [
+            vec![
+                (14, vec![this(), string()], vec![this()]),
+                (15, vec![this(), string()], vec![this(), string()]),
+            ],
+            vec![
+                (10, vec![ints(), Integer, ints(), Integer, Integer], vec![]),
+                (33, vec![ints(), Integer], vec![]),
+            ],
+            vec![
+                (2, vec![this(), Long, Long], vec![]),
+                (15, vec![this(), Long, Long], vec![]),
+            ],
+            vec![(14, vec![this(), Object("java/lang/Object".into())], vec![])],
+        ];
+
+        for (method, expected) in cf.methods.iter().zip(expected) {
+            let table = method
+                .attribute::<CodeInfo>()
+                .and_then(|code| code.attribute::<StackMapTableInfo>())
+                .unwrap();
+            let initial = Frame::initial(&class_name, method).unwrap();
+            let frames = table.frames(&initial, &cf.constant_pool).unwrap();
+            let frames = frames
+                .into_iter()
+                .map(|frame| (frame.offset, frame.locals, frame.stack))
+                .collect::<Vec<_>>();
+            assert_eq!(frames, expected, "{}", method.name);
+        }
+
+        let constructor = Frame::initial(&class_name, &cf.methods[0]).unwrap();
+        assert_eq!(constructor.locals, [UninitializedThis, string()]);
+    }
+}
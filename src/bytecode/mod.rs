@@ -1,26 +1,78 @@
-use std::collections::HashMap;
-
-use self::attributes::AnyAttribute;
+use self::attributes::{AnyAttribute, Attributes, NamedAttribute};
 use crate::bytecode::flags::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
 use crate::bytecode::pool::{ConstantPool, ConstantPoolIndex};
+use crate::bytecode::reader::parser::ClassParser;
+use crate::error::{Context, Error};
 
 pub mod attributes;
+pub mod borrowed;
+pub mod compaction;
 pub mod descriptors;
 pub mod flags;
+pub mod frames;
+pub mod mutf8;
 pub mod pool;
 pub mod reader;
+pub mod signatures;
+pub mod writer;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
 pub enum BytecodeError {
-    ConstantPoolEntryAlreadyExists,
-    ConstantPoolEntryNotFound,
+    #[error("constant pool entry #{0} already exists")]
+    ConstantPoolEntryAlreadyExists(usize),
+    #[error("constant pool entry #{0} does not exist")]
+    ConstantPoolEntryNotFound(usize),
+    #[error("expected constant pool entry #{index} to be a {expected}")]
+    UnexpectedConstant {
+        index: usize,
+        expected: &'static str,
+    },
+    #[error("long or double constant #{0} exceeds the constant pool")]
+    WideConstantOutOfBounds(usize),
+    #[error("unsupported attribute `{0}`")]
     UnsupportedAttributeName(String),
-    InvalidClassFile,
-    UnexpectedEndOfData,
-    InvalidData,
-    UnsupportedInstruction,
-    InvalidDescriptor,
-    UnsupportedVerificationType,
+    #[error("expected magic number 0xCAFEBABE, found 0x{0:08X}")]
+    InvalidMagicNumber(u32),
+    #[error("invalid access flags 0x{0:04X}")]
+    InvalidAccessFlags(u16),
+    #[error("expected {expected} more bytes, found {found}")]
+    UnexpectedEndOfData { expected: usize, found: usize },
+    #[error("invalid {kind} tag {found}")]
+    InvalidTag { kind: &'static str, found: u8 },
+    #[error("unsupported instruction with opcode 0x{0:02X}")]
+    UnsupportedInstruction(u8),
+    #[error("invalid descriptor `{descriptor}` at position {position}")]
+    InvalidDescriptor { descriptor: String, position: usize },
+    #[error("value {0} is out of range")]
+    ValueOutOfRange(usize),
+    #[error("expected attribute to contain {expected} bytes, but {found} bytes were read")]
+    AttributeLengthMismatch { expected: usize, found: usize },
+    #[error("malformed modified UTF-8 sequence at byte {offset} of the string")]
+    MalformedUtf8 { offset: usize },
+    #[error("{limit} exceeds the limit of {max}")]
+    LimitExceeded { limit: &'static str, max: usize },
+    #[error("invalid signature `{0}`")]
+    InvalidSignature(String),
+    #[error("lookupswitch has a negative number of pairs {0}")]
+    NegativeSwitchPairs(i32),
+    #[error("tableswitch has a high value {high} below its low value {low}")]
+    InvalidSwitchRange { low: i32, high: i32 },
+    #[error("stack map frame {0} removes more locals than are defined")]
+    InvalidStackMapFrame(usize),
+    #[error("failed to read input: {message}")]
+    Io {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+}
+
+impl From<std::io::Error> for BytecodeError {
+    fn from(err: std::io::Error) -> Self {
+        BytecodeError::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -45,30 +97,100 @@ pub struct ClassFile {
     pub methods_count: u16,
     pub methods: Vec<Method>,
     pub attributes_count: u16,
-    pub attributes: HashMap<&'static str, Box<dyn AnyAttribute>>,
+    pub attributes: Attributes,
+}
+
+impl ClassFile {
+    /// Parses the class file contained in `bytes` with a strict [ClassParser] that reads all
+    /// predefined attributes.
+    pub fn parse(bytes: &[u8]) -> Result<ClassFile, Error> {
+        ClassParser::new().parse(bytes)
+    }
+
+    pub fn get_attribute<T: AnyAttribute + 'static>(&self, name: &str) -> Option<&T> {
+        self.attributes.get_attribute(name)
+    }
+
+    pub fn attribute<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Option<&T> {
+        self.attributes.attribute()
+    }
+
+    /// Validates the constant pool, see [ConstantPool::validate], and checks that `this_class`,
+    /// `super_class` and the interfaces reference `CONSTANT_Class_info` entries. Only
+    /// `java/lang/Object` and modules have no superclass.
+    pub fn validate(&self) -> Result<(), Vec<Error>> {
+        let mut errors = self.constant_pool.validate().err().unwrap_or_default();
+        let pool = &self.constant_pool;
+        let mut check = |index: ConstantPoolIndex| {
+            if let Err(kind) = pool.class_name(index) {
+                errors.push(Error::new(kind).within(Context::Class));
+            }
+        };
+
+        check(self.this_class);
+        let is_root = pool.class_name(self.this_class).as_deref() == Ok("java/lang/Object")
+            || self.access_flags.contains(ClassAccessFlags::ACC_MODULE);
+        if self.super_class.index() != 0 || !is_root {
+            check(self.super_class);
+        }
+        for interface in &self.interfaces {
+            check(interface.name_index);
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Field {
+    pub name_index: ConstantPoolIndex,
     pub name: String,
-    pub descriptor: Descriptor,
+    pub descriptor_index: ConstantPoolIndex,
+    /// The parsed descriptor, `None` if it is invalid and the field was read leniently.
+    pub descriptor: Option<Descriptor>,
     pub access_flags: FieldAccessFlags,
-    pub attributes: HashMap<&'static str, Box<dyn AnyAttribute>>,
+    pub attributes: Attributes,
+}
+
+impl Field {
+    pub fn get_attribute<T: AnyAttribute + 'static>(&self, name: &str) -> Option<&T> {
+        self.attributes.get_attribute(name)
+    }
+
+    pub fn attribute<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Option<&T> {
+        self.attributes.attribute()
+    }
 }
 
 #[derive(Debug)]
 pub struct Method {
     pub access_flags: MethodAccessFlags,
+    pub name_index: ConstantPoolIndex,
     pub name: String,
-    pub descriptor: Vec<Descriptor>,
-    pub attributes: HashMap<&'static str, Box<dyn AnyAttribute>>,
+    pub descriptor_index: ConstantPoolIndex,
+    /// The parsed descriptor, `None` if it is invalid and the method was read leniently.
+    pub descriptor: Option<MethodDescriptor>,
+    pub attributes: Attributes,
 }
 
 impl Method {
+    /// Returns the local variable index of each parameter, see
+    /// [MethodDescriptor::parameter_indices], or `None` if the descriptor is invalid.
+    pub fn parameter_indices(&self) -> Option<Vec<u16>> {
+        let is_static = self.access_flags.contains(MethodAccessFlags::ACC_STATIC);
+        let descriptor = self.descriptor.as_ref()?;
+        Some(descriptor.parameter_indices(is_static))
+    }
+
     pub fn get_attribute<T: AnyAttribute + 'static>(&self, name: &str) -> Option<&T> {
-        self.attributes
-            .get(name)
-            .and_then(|attr| attr.as_any_ref().downcast_ref::<T>())
+        self.attributes.get_attribute(name)
+    }
+
+    pub fn attribute<T: NamedAttribute + AnyAttribute + 'static>(&self) -> Option<&T> {
+        self.attributes.attribute()
     }
 }
 
@@ -77,16 +199,10 @@ pub struct Interface {
     pub name_index: ConstantPoolIndex,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum DescriptorKind {
-    Parameter,
-    Return,
-    Type,
-}
-
+/// The type of a field, local variable or record component, e.g. `Ljava/lang/String;`. Method
+/// descriptors are represented by [MethodDescriptor] instead.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Descriptor {
-    pub kind: DescriptorKind,
     pub ty: FieldType,
 }
 
@@ -96,7 +212,42 @@ impl std::fmt::Display for Descriptor {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// The parameter and return types of a method, e.g. `(ILjava/lang/String;)V`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodDescriptor {
+    pub params: Vec<FieldType>,
+    pub ret: ReturnType,
+}
+
+impl std::fmt::Display for MethodDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        for (index, param) in self.params.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", param)?;
+        }
+        write!(f, ") {}", self.ret)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReturnType {
+    Void,
+    Type(FieldType),
+}
+
+impl std::fmt::Display for ReturnType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReturnType::Void => write!(f, "void"),
+            ReturnType::Type(ty) => write!(f, "{}", ty),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum FieldType {
     Base(BaseType),
     Object(String),
@@ -113,7 +264,7 @@ impl std::fmt::Display for FieldType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum BaseType {
     Byte,
     Char,
@@ -123,7 +274,6 @@ pub enum BaseType {
     Long,
     Short,
     Boolean,
-    Void,
 }
 
 impl std::fmt::Display for BaseType {
@@ -137,7 +287,156 @@ impl std::fmt::Display for BaseType {
             BaseType::Long => write!(f, "long"),
             BaseType::Short => write!(f, "short"),
             BaseType::Boolean => write!(f, "boolean"),
-            BaseType::Void => write!(f, "void"),
         }
     }
 }
+
+/// The class files in `examples/testdata` shared by the tests of all modules.
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::attributes::{
+        AnyAttribute, Attributes, CodeInfo, RecordComponent, RecordInfo,
+    };
+    use crate::bytecode::writer::attributes::write_attribute;
+    use crate::bytecode::writer::BufferedWriter;
+    use crate::bytecode::{ClassFile, Method};
+
+    pub const SIMPLE: &[u8] = include_bytes!("../../examples/testdata/org/example/Simple.class");
+    pub const SIMPLE_JSON_SERIALIZABLE: &[u8] =
+        include_bytes!("../../examples/testdata/org/example/Simple$JsonSerializable.class");
+    pub const CONSTANTS: &[u8] =
+        include_bytes!("../../examples/testdata/org/example/Constants.class");
+    pub const STRINGS: &[u8] = include_bytes!("../../examples/testdata/org/example/Strings.class");
+    pub const FRAMES: &[u8] = include_bytes!("../../examples/testdata/org/example/Frames.class");
+    pub const POINT: &[u8] = include_bytes!("../../examples/testdata/org/example/Point.class");
+
+    /// All fixtures, for tests that must hold for every class file.
+    pub const FIXTURES: [&[u8]; 6] = [
+        SIMPLE,
+        SIMPLE_JSON_SERIALIZABLE,
+        CONSTANTS,
+        STRINGS,
+        FRAMES,
+        POINT,
+    ];
+
+    /// The offsets of the structures in a class file, which are worked out from its parsed form
+    /// so that tests can patch a fixture without hard-coding byte offsets.
+    pub struct Layout {
+        cf: ClassFile,
+        /// The offset of the first `field_info`.
+        fields: usize,
+    }
+
+    impl Layout {
+        pub fn of(input: &[u8]) -> Layout {
+            let cf = ClassFile::parse(input).unwrap();
+            // Everything from `fields_count` to the end of the class file, where the size of the
+            // class attributes includes `attributes_count`.
+            let fields = cf.fields.iter().map(|field| member_size(&field.attributes));
+            let methods = cf
+                .methods
+                .iter()
+                .map(|method| member_size(&method.attributes));
+            let members = fields.chain(methods).sum::<usize>();
+            let tail = 4 + members + attributes_size(&cf.attributes);
+            Layout {
+                fields: input.len() - tail + 2,
+                cf,
+            }
+        }
+
+        /// Returns the offset of the `field_info` of the field `name`.
+        pub fn field(&self, name: &str) -> usize {
+            let index = self.cf.fields.iter().position(|f| f.name == name).unwrap();
+            let before = self.cf.fields[..index].iter();
+            self.fields + before.map(|f| member_size(&f.attributes)).sum::<usize>()
+        }
+
+        /// Returns the offset of the `method_info` of the first method `name`.
+        pub fn method(&self, name: &str) -> usize {
+            let index = self.cf.methods.iter().position(|m| m.name == name).unwrap();
+            let before = self.cf.methods[..index].iter();
+            self.methods() + before.map(|m| member_size(&m.attributes)).sum::<usize>()
+        }
+
+        /// Returns the offset of the first attribute `name` of the class.
+        pub fn class_attribute(&self, name: &str) -> usize {
+            let methods = self.cf.methods.iter();
+            let attributes =
+                self.methods() + methods.map(|m| member_size(&m.attributes)).sum::<usize>();
+            attributes + 2 + attribute_offset(&self.cf.attributes, name)
+        }
+
+        /// Returns the offset of the first attribute `name` of the first method `method`.
+        pub fn method_attribute(&self, method: &str, name: &str) -> usize {
+            self.method(method) + 8 + attribute_offset(&self.method_info(method).attributes, name)
+        }
+
+        /// Returns the offset of the first attribute `name` nested in the `Code` attribute of the
+        /// first method `method`.
+        pub fn code_attribute(&self, method: &str, name: &str) -> usize {
+            let code = self.method_info(method).attribute::<CodeInfo>().unwrap();
+            // The header, `max_stack`, `max_locals`, `code_length`, the code, the exception table
+            // and `attributes_count`.
+            let attributes = 6 + 8 + code.code.len() + 2 + 8 * code.exception_table.len() + 2;
+            self.method_attribute(method, "Code")
+                + attributes
+                + attribute_offset(&code.attributes, name)
+        }
+
+        /// Returns the offset of the component `name` in the `Record` attribute of the class.
+        pub fn record_component(&self, name: &str) -> usize {
+            let record = self.cf.attribute::<RecordInfo>().unwrap();
+            let index = record
+                .components
+                .iter()
+                .position(|c| c.name == name)
+                .unwrap();
+            let before = record.components[..index].iter();
+            self.class_attribute("Record") + 8 + before.map(component_size).sum::<usize>()
+        }
+
+        /// Returns the offset of the first attribute `name` of the record component `component`.
+        pub fn record_component_attribute(&self, component: &str, name: &str) -> usize {
+            let record = self.cf.attribute::<RecordInfo>().unwrap();
+            let info = record.components.iter().find(|c| c.name == component);
+            let attributes = &info.unwrap().attributes;
+            self.record_component(component) + 6 + attribute_offset(attributes, name)
+        }
+
+        fn methods(&self) -> usize {
+            let fields = self.cf.fields.iter();
+            self.fields + fields.map(|f| member_size(&f.attributes)).sum::<usize>() + 2
+        }
+
+        fn method_info(&self, name: &str) -> &Method {
+            self.cf.methods.iter().find(|m| m.name == name).unwrap()
+        }
+    }
+
+    fn attribute_size(attribute: &dyn AnyAttribute) -> usize {
+        let mut writer = BufferedWriter::new();
+        write_attribute(&mut writer, attribute).unwrap();
+        writer.size()
+    }
+
+    fn attributes_size(attributes: &Attributes) -> usize {
+        2 + attributes.values().map(attribute_size).sum::<usize>()
+    }
+
+    /// Returns the offset of the first attribute `name` relative to the first of `attributes`.
+    fn attribute_offset(attributes: &Attributes, name: &str) -> usize {
+        let index = attributes.names().position(|n| n == name).unwrap();
+        attributes.values().take(index).map(attribute_size).sum()
+    }
+
+    /// Returns the size of a `field_info` or `method_info` with the given `attributes`.
+    fn member_size(attributes: &Attributes) -> usize {
+        6 + attributes_size(attributes)
+    }
+
+    fn component_size(component: &RecordComponent) -> usize {
+        4 + attributes_size(&component.attributes)
+    }
+}
@@ -0,0 +1,177 @@
+//! Conversion between Rust strings and the modified UTF-8 encoding used by `CONSTANT_Utf8_info`
+//! entries.
+//!
+//! Modified UTF-8 differs from standard UTF-8 in two ways: the null character is encoded using
+//! two bytes (`C0 80`), and supplementary characters are encoded as a surrogate pair where each
+//! surrogate takes three bytes.
+//! <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.4.7>
+
+use std::borrow::Cow;
+
+use crate::bytecode::BytecodeError;
+
+/// Decodes the given modified UTF-8 `bytes` into a [String].
+///
+/// If the bytes are malformed, a [BytecodeError::MalformedUtf8] is returned with the offset of
+/// the first byte of the invalid sequence.
+pub fn decode(bytes: &[u8]) -> Result<String, BytecodeError> {
+    let mut text = String::with_capacity(bytes.len());
+    for_each_char(bytes, |ch| text.push(ch))?;
+    Ok(text)
+}
+
+/// Checks that the given `bytes` are well-formed modified UTF-8 without decoding them.
+pub fn validate(bytes: &[u8]) -> Result<(), BytecodeError> {
+    for_each_char(bytes, |_| {})
+}
+
+/// Calls `f` for every character encoded in the given modified UTF-8 `bytes`.
+fn for_each_char(bytes: &[u8], mut f: impl FnMut(char)) -> Result<(), BytecodeError> {
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let (unit, width) = decode_unit(bytes, offset)?;
+
+        let ch = match unit {
+            0xD800..=0xDBFF => {
+                // A high surrogate has to be followed by a low surrogate.
+                let Ok((low @ 0xDC00..=0xDFFF, low_width)) = decode_unit(bytes, offset + width)
+                else {
+                    return Err(BytecodeError::MalformedUtf8 { offset });
+                };
+                let code_point = 0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00);
+                offset += low_width;
+                char::from_u32(code_point)
+            }
+            0xDC00..=0xDFFF => None,
+            unit => char::from_u32(unit),
+        };
+
+        match ch {
+            Some(ch) => f(ch),
+            None => return Err(BytecodeError::MalformedUtf8 { offset }),
+        }
+        offset += width;
+    }
+
+    Ok(())
+}
+
+/// Decodes the given modified UTF-8 `bytes` like [decode], but borrows them if they are valid
+/// standard UTF-8 as well, which is the case for all text without null and supplementary
+/// characters.
+pub fn decode_borrowed(bytes: &[u8]) -> Result<Cow<'_, str>, BytecodeError> {
+    // Standard UTF-8 rejects the two byte encoding of the null character and surrogates, so only
+    // null bytes and four byte sequences have to be checked for explicitly.
+    if !bytes.iter().any(|&byte| byte == 0x00 || byte >= 0xF0) {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return Ok(Cow::Borrowed(text));
+        }
+    }
+    decode(bytes).map(Cow::Owned)
+}
+
+/// Encodes the given `text` as modified UTF-8.
+pub fn encode(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for unit in text.encode_utf16() {
+        let unit = unit as u32;
+        match unit {
+            0x0001..=0x007F => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+
+    bytes
+}
+
+/// Decodes a single UTF-16 code unit starting at `offset` and returns it together with the number
+/// of bytes it occupies.
+fn decode_unit(bytes: &[u8], offset: usize) -> Result<(u32, usize), BytecodeError> {
+    let malformed = BytecodeError::MalformedUtf8 { offset };
+    let continuation = |idx: usize| match bytes.get(offset + idx) {
+        Some(byte) if byte & 0xC0 == 0x80 => Ok((byte & 0x3F) as u32),
+        _ => Err(malformed.clone()),
+    };
+
+    match bytes.get(offset) {
+        Some(&byte @ 0x01..=0x7F) => Ok((byte as u32, 1)),
+        Some(&byte @ 0xC0..=0xDF) => Ok((((byte & 0x1F) as u32) << 6 | continuation(1)?, 2)),
+        Some(&byte @ 0xE0..=0xEF) => {
+            let unit = ((byte & 0x0F) as u32) << 12 | continuation(1)? << 6 | continuation(2)?;
+            Ok((unit, 3))
+        }
+        _ => Err(malformed),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::borrow::Cow;
+
+    use crate::bytecode::mutf8::{decode, decode_borrowed, encode};
+    use crate::bytecode::BytecodeError;
+
+    #[test]
+    fn test_roundtrip() {
+        let cases: [(&str, &[u8]); 5] = [
+            ("Hello", b"Hello"),
+            ("a\0b", &[0x61, 0xC0, 0x80, 0x62]),
+            ("ß", &[0xC3, 0x9F]),
+            ("€", &[0xE2, 0x82, 0xAC]),
+            ("😀", &[0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]),
+        ];
+
+        for (text, bytes) in cases {
+            assert_eq!(encode(text), bytes);
+            assert_eq!(decode(bytes).unwrap(), text);
+            assert_eq!(decode_borrowed(bytes).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_decode_borrowed() {
+        assert!(matches!(
+            decode_borrowed(b"Hello"),
+            Ok(Cow::Borrowed("Hello"))
+        ));
+        assert!(matches!(
+            decode_borrowed(&[0xC3, 0x9F]),
+            Ok(Cow::Borrowed("ß"))
+        ));
+        assert!(matches!(decode_borrowed(&[0xC0, 0x80]), Ok(Cow::Owned(_))));
+        assert_eq!(
+            decode_borrowed(&[0xF0, 0x9F, 0x98, 0x80]),
+            Err(BytecodeError::MalformedUtf8 { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let cases: [(&[u8], usize); 6] = [
+            // Embedded null byte.
+            (&[0x61, 0x00], 1),
+            // Missing continuation byte.
+            (&[0x61, 0xC3], 1),
+            // Four byte sequences are not allowed.
+            (&[0xF0, 0x9F, 0x98, 0x80], 0),
+            // Lone high surrogate.
+            (&[0xED, 0xA0, 0xBD, 0x61], 0),
+            // Lone low surrogate.
+            (&[0x61, 0xED, 0xB8, 0x80], 1),
+            // Stray continuation byte.
+            (&[0x80], 0),
+        ];
+
+        for (bytes, offset) in cases {
+            assert_eq!(decode(bytes), Err(BytecodeError::MalformedUtf8 { offset }));
+        }
+    }
+}
@@ -1,6 +1,10 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use crate::bytecode::BytecodeError;
+use crate::bytecode::writer::constants::write_constant_pool_entry;
+use crate::bytecode::writer::BufferedWriter;
+use crate::bytecode::{mutf8, BytecodeError, Descriptor, MethodDescriptor};
+use crate::error::{Context, Error};
 
 /// The constant pool index is a 1-based index used to reference items in the [`ConstantPool`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -107,11 +111,79 @@ pub enum ConstantPoolEntry {
     /// by a module.
     /// <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.4.12>
     Package { name_index: ConstantPoolIndex },
+    /// The slot following a `CONSTANT_Long_info` or `CONSTANT_Double_info` entry. It is valid, but
+    /// considered unusable and never appears in the class file.
+    /// <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.4.5>
+    Unusable,
+}
+
+impl ConstantPoolEntry {
+    /// Creates a `CONSTANT_Utf8_info` entry holding the modified UTF-8 encoding of `text`.
+    pub fn utf8(text: &str) -> Result<Self, BytecodeError> {
+        let bytes = mutf8::encode(text);
+        let length =
+            u16::try_from(bytes.len()).map_err(|_| BytecodeError::ValueOutOfRange(bytes.len()))?;
+        Ok(ConstantPoolEntry::Utf8 { length, bytes })
+    }
+
+    /// Returns the [ConstantTag] of the entry, or `None` for an [unusable](ConstantPoolEntry::Unusable)
+    /// slot.
+    pub fn tag(&self) -> Option<ConstantTag> {
+        let tag = match self {
+            ConstantPoolEntry::Class { .. } => ConstantTag::Class,
+            ConstantPoolEntry::FieldRef { .. } => ConstantTag::FieldRef,
+            ConstantPoolEntry::MethodRef { .. } => ConstantTag::MethodRef,
+            ConstantPoolEntry::InterfaceMethodRef { .. } => ConstantTag::InterfaceMethodRef,
+            ConstantPoolEntry::String { .. } => ConstantTag::String,
+            ConstantPoolEntry::Integer { .. } => ConstantTag::Integer,
+            ConstantPoolEntry::Float { .. } => ConstantTag::Float,
+            ConstantPoolEntry::Long { .. } => ConstantTag::Long,
+            ConstantPoolEntry::Double { .. } => ConstantTag::Double,
+            ConstantPoolEntry::NameAndType { .. } => ConstantTag::NameAndType,
+            ConstantPoolEntry::Utf8 { .. } => ConstantTag::Utf8,
+            ConstantPoolEntry::MethodHandle { .. } => ConstantTag::MethodHandle,
+            ConstantPoolEntry::MethodType { .. } => ConstantTag::MethodType,
+            ConstantPoolEntry::Dynamic { .. } => ConstantTag::Dynamic,
+            ConstantPoolEntry::InvokeDynamic { .. } => ConstantTag::InvokeDynamic,
+            ConstantPoolEntry::Module { .. } => ConstantTag::Module,
+            ConstantPoolEntry::Package { .. } => ConstantTag::Package,
+            ConstantPoolEntry::Unusable => return None,
+        };
+        Some(tag)
+    }
+
+    /// Returns `true` if the entry takes up two slots in the [ConstantPool], which is the case for
+    /// `CONSTANT_Long_info` and `CONSTANT_Double_info` entries.
+    pub fn is_wide(&self) -> bool {
+        matches!(
+            self,
+            ConstantPoolEntry::Long { .. } | ConstantPoolEntry::Double { .. }
+        )
+    }
+}
+
+/// Read-only access to a constant pool, which is all that [AttributeFactory]s need for decoding
+/// attributes. It is implemented by the owned [ConstantPool] and by the
+/// [borrowed](crate::bytecode::borrowed::ConstantPool) one, so that attributes of borrowed class
+/// files are decoded without copying the pool.
+///
+/// [AttributeFactory]: crate::bytecode::attributes::AttributeFactory
+pub trait ConstantPoolView: std::fmt::Debug {
+    /// Returns the decoded `CONSTANT_Utf8_info` entry at the given index, see
+    /// [ConstantPool::utf8_at].
+    fn utf8_at(&self, index: ConstantPoolIndex) -> Result<Cow<'_, str>, BytecodeError>;
 }
 
+/// The entries of a class file's constant pool, addressed by their [ConstantPoolIndex].
+///
+/// The entries are stored densely by index, so lookups don't hash and [iter](ConstantPool::iter)
+/// visits them in order.
 #[derive(Debug, Clone, PartialEq)]
 pub struct ConstantPool {
-    entries: HashMap<ConstantPoolIndex, ConstantPoolEntry>,
+    /// The entries by index, where missing indices are `None`. The last slot is always occupied.
+    entries: Vec<Option<ConstantPoolEntry>>,
+    /// The number of occupied slots.
+    len: usize,
 }
 
 impl Default for ConstantPool {
@@ -120,99 +192,1002 @@ impl Default for ConstantPool {
     }
 }
 
+impl ConstantPoolView for ConstantPool {
+    fn utf8_at(&self, index: ConstantPoolIndex) -> Result<Cow<'_, str>, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::Utf8 { bytes, .. } => mutf8::decode_borrowed(bytes),
+            _ => Err(BytecodeError::UnexpectedConstant {
+                index: index.index(),
+                expected: "Utf8 constant",
+            }),
+        }
+    }
+}
+
 impl ConstantPool {
     pub fn new() -> Self {
         Self {
-            entries: HashMap::new(),
+            entries: Vec::new(),
+            len: 0,
         }
     }
 
     /// Insert a new entry into the [ConstantPool] at the given index. If the index is already
     /// present in the [ConstantPool], this function will return an error.
+    ///
+    /// [Wide](ConstantPoolEntry::is_wide) entries also occupy the following index, which is filled
+    /// with [ConstantPoolEntry::Unusable].
     pub fn insert(
         &mut self,
         index: ConstantPoolIndex,
         value: ConstantPoolEntry,
     ) -> Result<(), BytecodeError> {
-        if self.entries.contains_key(&index) {
-            return Err(BytecodeError::ConstantPoolEntryAlreadyExists);
+        let next = ConstantPoolIndex(index.0 + 1);
+        if self.get(index).is_some() || (value.is_wide() && self.get(next).is_some()) {
+            return Err(BytecodeError::ConstantPoolEntryAlreadyExists(index.0));
         }
 
-        self.entries.insert(index, value);
+        let last = if value.is_wide() { next } else { index };
+        if self.entries.len() <= last.0 {
+            self.entries.resize(last.0 + 1, None);
+        }
+        if value.is_wide() {
+            self.entries[next.0] = Some(ConstantPoolEntry::Unusable);
+            self.len += 1;
+        }
+        self.entries[index.0] = Some(value);
+        self.len += 1;
         Ok(())
     }
 
     /// Get a reference to the [entry](ConstantPoolEntry) at the given index in the [ConstantPool].
     /// If the index is not present in the [ConstantPool], this function will return `None`.
     pub fn get(&self, index: ConstantPoolIndex) -> Option<&ConstantPoolEntry> {
-        self.entries.get(&index)
+        self.entries.get(index.0)?.as_ref()
     }
 
     /// Removes the entry at the given index from the [ConstantPool] and returns it. If the index is
     /// not present in the [ConstantPool], this function will return `None`.
+    ///
+    /// Removing a [wide](ConstantPoolEntry::is_wide) entry also frees its unusable slot.
     pub fn remove(&mut self, index: ConstantPoolIndex) -> Option<ConstantPoolEntry> {
-        self.entries.remove(&index)
+        let entry = self.entries.get_mut(index.0)?.take()?;
+        self.len -= 1;
+        if entry.is_wide() {
+            if let Some(slot) = self.entries.get_mut(index.0 + 1) {
+                if slot.take().is_some() {
+                    self.len -= 1;
+                }
+            }
+        }
+        // Trailing free slots are dropped, so that equal pools have equal storage.
+        while let Some(None) = self.entries.last() {
+            self.entries.pop();
+        }
+        Some(entry)
     }
 
-    /// Returns the number of entries in the [ConstantPool].
+    /// Returns an iterator over the entries in the order of their indices, including unusable
+    /// slots.
+    pub fn iter(&self) -> impl Iterator<Item = (ConstantPoolIndex, &ConstantPoolEntry)> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((ConstantPoolIndex(index), entry.as_ref()?)))
+    }
+
+    /// Returns the value of the `CONSTANT_Long_info` entry at the given index. If the index is not
+    /// present or does not refer to a long constant, this function will return `None`.
+    pub fn long_at(&self, index: ConstantPoolIndex) -> Option<i64> {
+        match self.get(index)? {
+            ConstantPoolEntry::Long {
+                high_bytes,
+                low_bytes,
+            } => Some(combine_bytes(*high_bytes, *low_bytes) as i64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the `CONSTANT_Double_info` entry at the given index. If the index is
+    /// not present or does not refer to a double constant, this function will return `None`.
+    pub fn double_at(&self, index: ConstantPoolIndex) -> Option<f64> {
+        match self.get(index)? {
+            ConstantPoolEntry::Double {
+                high_bytes,
+                low_bytes,
+            } => Some(f64::from_bits(combine_bytes(*high_bytes, *low_bytes))),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of slots in the [ConstantPool], including unusable slots.
     pub fn size(&self) -> usize {
-        self.entries.len()
+        self.len
     }
 
     /// Convenience function to check if the [ConstantPool] is empty.
     pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+        self.len == 0
     }
 
-    /// Returns the text representation of the entry at the given index in the [ConstantPool]. If
-    /// the index is not present in the [ConstantPool], this function will return `None`.
-    pub fn text_of(&self, index: ConstantPoolIndex) -> Option<String> {
-        let entry = match self.get(index) {
-            Some(entry) => entry,
-            None => return None,
+    /// Returns the decoded `CONSTANT_Utf8_info` entry at the given index. Unlike
+    /// [text_of](ConstantPool::text_of), this function reports why the text is unavailable: a
+    /// missing entry, an entry of another kind or a malformed modified UTF-8 sequence.
+    pub fn utf8_at(&self, index: ConstantPoolIndex) -> Result<String, BytecodeError> {
+        match self.get(index) {
+            Some(ConstantPoolEntry::Utf8 { bytes, .. }) => mutf8::decode(bytes),
+            Some(_) => Err(BytecodeError::UnexpectedConstant {
+                index: index.index(),
+                expected: "Utf8 constant",
+            }),
+            None => Err(BytecodeError::ConstantPoolEntryNotFound(index.index())),
+        }
+    }
+
+    /// Returns the entry at the given index, or [BytecodeError::ConstantPoolEntryNotFound] if the
+    /// index is not present.
+    fn entry(&self, index: ConstantPoolIndex) -> Result<&ConstantPoolEntry, BytecodeError> {
+        self.get(index)
+            .ok_or(BytecodeError::ConstantPoolEntryNotFound(index.index()))
+    }
+
+    /// Returns the binary name of the `CONSTANT_Class_info` entry at the given index, e.g.
+    /// `java/lang/String`, or the descriptor of an array class, e.g. `[I`.
+    pub fn class_name(&self, index: ConstantPoolIndex) -> Result<String, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::Class { name_index } => self.utf8_at(*name_index),
+            _ => Err(unexpected(index, "Class constant")),
+        }
+    }
+
+    /// Returns the text of the `CONSTANT_String_info` entry at the given index.
+    pub fn string_at(&self, index: ConstantPoolIndex) -> Result<String, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::String { string_index } => self.utf8_at(*string_index),
+            _ => Err(unexpected(index, "String constant")),
+        }
+    }
+
+    /// Returns the name and the descriptor of the `CONSTANT_NameAndType_info` entry at the given
+    /// index.
+    pub fn name_and_type(&self, index: ConstantPoolIndex) -> Result<NameAndType, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::NameAndType {
+                name_index,
+                descriptor_index,
+            } => Ok(NameAndType {
+                name: self.utf8_at(*name_index)?,
+                descriptor: self.utf8_at(*descriptor_index)?,
+            }),
+            _ => Err(unexpected(index, "NameAndType constant")),
+        }
+    }
+
+    /// Resolves the `CONSTANT_Fieldref_info`, `CONSTANT_Methodref_info` or
+    /// `CONSTANT_InterfaceMethodref_info` entry at the given index.
+    pub fn member_ref(&self, index: ConstantPoolIndex) -> Result<MemberRef, BytecodeError> {
+        let (kind, class_index, name_and_type_index) = match self.entry(index)? {
+            ConstantPoolEntry::FieldRef {
+                class_index,
+                name_and_type_index,
+            } => (MemberKind::Field, class_index, name_and_type_index),
+            ConstantPoolEntry::MethodRef {
+                class_index,
+                name_and_type_index,
+            } => (MemberKind::Method, class_index, name_and_type_index),
+            ConstantPoolEntry::InterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            } => (
+                MemberKind::InterfaceMethod,
+                class_index,
+                name_and_type_index,
+            ),
+            _ => {
+                return Err(unexpected(
+                    index,
+                    "Fieldref, Methodref or InterfaceMethodref constant",
+                ))
+            }
         };
+        let NameAndType { name, descriptor } = self.name_and_type(*name_and_type_index)?;
+        Ok(MemberRef {
+            kind,
+            owner: self.class_name(*class_index)?,
+            name,
+            descriptor,
+        })
+    }
+
+    /// Resolves the `CONSTANT_MethodHandle_info` entry at the given index and checks that its
+    /// reference kind matches the kind and the name of the referenced member.
+    /// <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.4.8>
+    pub fn method_handle(&self, index: ConstantPoolIndex) -> Result<MethodHandle, BytecodeError> {
+        let (reference_kind, reference_index) = match self.entry(index)? {
+            ConstantPoolEntry::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => (*reference_kind, *reference_index),
+            _ => return Err(unexpected(index, "MethodHandle constant")),
+        };
+        let kind = ReferenceKind::from_kind(reference_kind).ok_or(BytecodeError::InvalidTag {
+            kind: "reference kind",
+            found: reference_kind,
+        })?;
+        let member = self.member_ref(reference_index)?;
+        let valid = match kind {
+            ReferenceKind::GetField
+            | ReferenceKind::GetStatic
+            | ReferenceKind::PutField
+            | ReferenceKind::PutStatic => member.kind == MemberKind::Field,
+            ReferenceKind::InvokeVirtual | ReferenceKind::NewInvokeSpecial => {
+                member.kind == MemberKind::Method
+            }
+            ReferenceKind::InvokeStatic | ReferenceKind::InvokeSpecial => {
+                member.kind != MemberKind::Field
+            }
+            ReferenceKind::InvokeInterface => member.kind == MemberKind::InterfaceMethod,
+        };
+        if !valid {
+            return Err(unexpected(reference_index, kind.expected_member()));
+        }
+        if !kind.accepts_name(&member.name) {
+            return Err(unexpected(reference_index, kind.expected_name()));
+        }
+        Ok(MethodHandle { kind, member })
+    }
 
+    /// Returns the method descriptor of the `CONSTANT_MethodType_info` entry at the given index.
+    pub fn method_type(&self, index: ConstantPoolIndex) -> Result<String, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::MethodType { descriptor_index } => self.utf8_at(*descriptor_index),
+            _ => Err(unexpected(index, "MethodType constant")),
+        }
+    }
+
+    /// Resolves the `CONSTANT_Dynamic_info` entry at the given index.
+    pub fn dynamic(&self, index: ConstantPoolIndex) -> Result<DynamicRef, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::Dynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => self.dynamic_ref(*bootstrap_method_attr_index, *name_and_type_index),
+            _ => Err(unexpected(index, "Dynamic constant")),
+        }
+    }
+
+    /// Resolves the `CONSTANT_InvokeDynamic_info` entry at the given index.
+    pub fn invoke_dynamic(&self, index: ConstantPoolIndex) -> Result<DynamicRef, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => self.dynamic_ref(*bootstrap_method_attr_index, *name_and_type_index),
+            _ => Err(unexpected(index, "InvokeDynamic constant")),
+        }
+    }
+
+    fn dynamic_ref(
+        &self,
+        bootstrap_method_attr_index: ConstantPoolIndex,
+        name_and_type_index: ConstantPoolIndex,
+    ) -> Result<DynamicRef, BytecodeError> {
+        let NameAndType { name, descriptor } = self.name_and_type(name_and_type_index)?;
+        Ok(DynamicRef {
+            bootstrap_method_attr_index: bootstrap_method_attr_index.index() as u16,
+            name,
+            descriptor,
+        })
+    }
+
+    /// Returns the name of the `CONSTANT_Module_info` entry at the given index.
+    pub fn module_name(&self, index: ConstantPoolIndex) -> Result<String, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::Module { name_index } => self.utf8_at(*name_index),
+            _ => Err(unexpected(index, "Module constant")),
+        }
+    }
+
+    /// Returns the name of the `CONSTANT_Package_info` entry at the given index, e.g.
+    /// `java/lang`.
+    pub fn package_name(&self, index: ConstantPoolIndex) -> Result<String, BytecodeError> {
+        match self.entry(index)? {
+            ConstantPoolEntry::Package { name_index } => self.utf8_at(*name_index),
+            _ => Err(unexpected(index, "Package constant")),
+        }
+    }
+
+    /// Resolves the loadable entry at the given index, which may be pushed onto the operand
+    /// stack by `ldc` or be a static argument of a bootstrap method.
+    /// <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.4-310>
+    pub fn loadable_constant(&self, index: ConstantPoolIndex) -> Result<Constant, BytecodeError> {
+        let constant = match self.entry(index)? {
+            ConstantPoolEntry::Integer { bytes } => Constant::Integer(*bytes),
+            ConstantPoolEntry::Float { bytes } => Constant::Float(*bytes),
+            ConstantPoolEntry::Long {
+                high_bytes,
+                low_bytes,
+            } => Constant::Long(combine_bytes(*high_bytes, *low_bytes) as i64),
+            ConstantPoolEntry::Double {
+                high_bytes,
+                low_bytes,
+            } => Constant::Double(f64::from_bits(combine_bytes(*high_bytes, *low_bytes))),
+            ConstantPoolEntry::String { .. } => Constant::String(self.string_at(index)?),
+            ConstantPoolEntry::Class { .. } => Constant::Class(self.class_name(index)?),
+            ConstantPoolEntry::MethodHandle { .. } => {
+                Constant::MethodHandle(self.method_handle(index)?)
+            }
+            ConstantPoolEntry::MethodType { .. } => Constant::MethodType(self.method_type(index)?),
+            ConstantPoolEntry::Dynamic { .. } => Constant::Dynamic(self.dynamic(index)?),
+            _ => return Err(unexpected(index, "loadable constant")),
+        };
+        Ok(constant)
+    }
+
+    /// Checks that every entry references entries of the kinds required by JVMS 4.4, that
+    /// descriptors are well-formed and that method handles have a valid reference kind with a
+    /// compatible target. Every problem is reported within the [Context::ConstantPoolEntry] that
+    /// contains the reference.
+    /// <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.4>
+    pub fn validate(&self) -> Result<(), Vec<Error>> {
+        let mut errors = vec![];
+        for (index, entry) in self.iter() {
+            for kind in self
+                .validate_entry(index, entry)
+                .into_iter()
+                .filter_map(Result::err)
+            {
+                errors.push(Error::new(kind).within(Context::ConstantPoolEntry(index.index())));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Returns the result of checking each reference of the `entry` at `index`.
+    fn validate_entry(
+        &self,
+        index: ConstantPoolIndex,
+        entry: &ConstantPoolEntry,
+    ) -> Vec<Result<(), BytecodeError>> {
         match entry {
-            ConstantPoolEntry::Utf8 { bytes, .. } => {
-                let str = match String::from_utf8(bytes.clone()) {
-                    Ok(str) => str,
-                    Err(_) => return None,
-                };
-                Some(str)
+            ConstantPoolEntry::Class { name_index } => vec![self.expect_utf8(*name_index)],
+            ConstantPoolEntry::FieldRef {
+                class_index,
+                name_and_type_index,
+            } => vec![
+                self.expect(*class_index, ConstantTag::Class, "Class constant"),
+                self.expect_name_and_type(*name_and_type_index, false),
+            ],
+            ConstantPoolEntry::MethodRef {
+                class_index,
+                name_and_type_index,
+            }
+            | ConstantPoolEntry::InterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            } => vec![
+                self.expect(*class_index, ConstantTag::Class, "Class constant"),
+                self.expect_name_and_type(*name_and_type_index, true),
+            ],
+            ConstantPoolEntry::String { string_index } => vec![self.expect_utf8(*string_index)],
+            ConstantPoolEntry::NameAndType {
+                name_index,
+                descriptor_index,
+            } => vec![
+                self.expect_utf8(*name_index),
+                self.utf8_at(*descriptor_index).and_then(|descriptor| {
+                    match descriptor.starts_with('(') {
+                        true => MethodDescriptor::parse(&descriptor).map(|_| ()),
+                        false => Descriptor::parse_from_field(descriptor).map(|_| ()),
+                    }
+                }),
+            ],
+            ConstantPoolEntry::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => vec![self.expect_handle_target(*reference_kind, *reference_index)],
+            ConstantPoolEntry::MethodType { descriptor_index } => vec![self
+                .utf8_at(*descriptor_index)
+                .and_then(|descriptor| MethodDescriptor::parse(&descriptor).map(|_| ()))],
+            ConstantPoolEntry::Dynamic {
+                name_and_type_index,
+                ..
+            } => vec![self.expect_name_and_type(*name_and_type_index, false)],
+            ConstantPoolEntry::InvokeDynamic {
+                name_and_type_index,
+                ..
+            } => vec![self.expect_name_and_type(*name_and_type_index, true)],
+            ConstantPoolEntry::Module { name_index }
+            | ConstantPoolEntry::Package { name_index } => {
+                vec![self.expect_utf8(*name_index)]
+            }
+            ConstantPoolEntry::Unusable => {
+                let previous = index.index().checked_sub(1).map(ConstantPoolIndex);
+                match previous.and_then(|previous| self.get(previous)) {
+                    Some(entry) if entry.is_wide() => vec![],
+                    _ => vec![Err(BytecodeError::InvalidTag {
+                        kind: "constant pool",
+                        found: 0,
+                    })],
+                }
+            }
+            ConstantPoolEntry::Integer { .. }
+            | ConstantPoolEntry::Float { .. }
+            | ConstantPoolEntry::Long { .. }
+            | ConstantPoolEntry::Double { .. }
+            | ConstantPoolEntry::Utf8 { .. } => vec![],
+        }
+    }
+
+    /// Checks that the entry at `index` exists and has the given `tag`.
+    fn expect(
+        &self,
+        index: ConstantPoolIndex,
+        tag: ConstantTag,
+        expected: &'static str,
+    ) -> Result<(), BytecodeError> {
+        match self.entry(index)?.tag() {
+            Some(found) if found == tag => Ok(()),
+            _ => Err(unexpected(index, expected)),
+        }
+    }
+
+    fn expect_utf8(&self, index: ConstantPoolIndex) -> Result<(), BytecodeError> {
+        self.expect(index, ConstantTag::Utf8, "Utf8 constant")
+    }
+
+    /// Checks that the entry at `index` is a `CONSTANT_NameAndType_info` entry with a method
+    /// descriptor if `is_method` is set, or a field descriptor otherwise. Malformed descriptors
+    /// are reported by the entry itself.
+    fn expect_name_and_type(
+        &self,
+        index: ConstantPoolIndex,
+        is_method: bool,
+    ) -> Result<(), BytecodeError> {
+        let expected = match is_method {
+            true => "NameAndType constant with a method descriptor",
+            false => "NameAndType constant with a field descriptor",
+        };
+        match self.entry(index)? {
+            ConstantPoolEntry::NameAndType {
+                descriptor_index, ..
+            } => match self.utf8_at(*descriptor_index) {
+                Ok(descriptor) if descriptor.starts_with('(') != is_method => {
+                    Err(unexpected(index, expected))
+                }
+                _ => Ok(()),
+            },
+            _ => Err(unexpected(index, expected)),
+        }
+    }
+
+    /// Checks that `reference_kind` is valid and that the entry at `index` is a member reference
+    /// of the kind and with the name it requires.
+    fn expect_handle_target(
+        &self,
+        reference_kind: u8,
+        index: ConstantPoolIndex,
+    ) -> Result<(), BytecodeError> {
+        let kind = ReferenceKind::from_kind(reference_kind).ok_or(BytecodeError::InvalidTag {
+            kind: "reference kind",
+            found: reference_kind,
+        })?;
+        let valid = matches!(
+            (kind, self.entry(index)?),
+            (
+                ReferenceKind::GetField
+                    | ReferenceKind::GetStatic
+                    | ReferenceKind::PutField
+                    | ReferenceKind::PutStatic,
+                ConstantPoolEntry::FieldRef { .. },
+            ) | (
+                ReferenceKind::InvokeVirtual | ReferenceKind::NewInvokeSpecial,
+                ConstantPoolEntry::MethodRef { .. },
+            ) | (
+                ReferenceKind::InvokeStatic | ReferenceKind::InvokeSpecial,
+                ConstantPoolEntry::MethodRef { .. } | ConstantPoolEntry::InterfaceMethodRef { .. },
+            ) | (
+                ReferenceKind::InvokeInterface,
+                ConstantPoolEntry::InterfaceMethodRef { .. }
+            )
+        );
+        if !valid {
+            return Err(unexpected(index, kind.expected_member()));
+        }
+        // A malformed member reference is reported by the referenced entry itself.
+        match self.member_ref(index) {
+            Ok(member) if !kind.accepts_name(&member.name) => {
+                Err(unexpected(index, kind.expected_name()))
             }
-            ConstantPoolEntry::String { string_index } => self.text_of(*string_index),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the text representation of the entry at the given index in the [ConstantPool]. If
+    /// the index is not present in the [ConstantPool], this function will return `None`.
+    pub fn text_of(&self, index: ConstantPoolIndex) -> Option<String> {
+        self.text_at(index, 0)
+    }
+
+    /// Returns the text representation of the entry at the given index, which is referenced from
+    /// an entry at the given `depth`. Well-formed entries refer to Utf8 constants within two
+    /// steps, so deeper references indicate a cycle in a malformed pool and yield `None`.
+    fn text_at(&self, index: ConstantPoolIndex, depth: usize) -> Option<String> {
+        if depth > 2 {
+            return None;
+        }
+        let text_of = |index: ConstantPoolIndex| self.text_at(index, depth + 1);
+        match self.get(index)? {
+            ConstantPoolEntry::Utf8 { bytes, .. } => mutf8::decode(bytes).ok(),
+            ConstantPoolEntry::String { string_index } => text_of(*string_index),
             ConstantPoolEntry::Integer { bytes } => Some(bytes.to_string()),
             ConstantPoolEntry::Float { bytes } => Some(bytes.to_string()),
+            ConstantPoolEntry::Long { .. } => Some(self.long_at(index)?.to_string()),
+            ConstantPoolEntry::Double { .. } => Some(self.double_at(index)?.to_string()),
             ConstantPoolEntry::MethodRef {
                 class_index,
                 name_and_type_index,
             } => Some(format!(
                 "{}.{}",
-                self.text_of(*class_index)?,
-                self.text_of(*name_and_type_index)?
+                text_of(*class_index)?,
+                text_of(*name_and_type_index)?
             )),
             ConstantPoolEntry::InterfaceMethodRef {
                 class_index,
                 name_and_type_index,
             } => Some(format!(
                 "{}.{}",
-                self.text_of(*class_index)?,
-                self.text_of(*name_and_type_index)?
+                text_of(*class_index)?,
+                text_of(*name_and_type_index)?
             )),
             ConstantPoolEntry::NameAndType {
                 name_index,
                 descriptor_index,
             } => Some(format!(
                 "{}: {}",
-                self.text_of(*name_index)?,
-                self.text_of(*descriptor_index)?
+                text_of(*name_index)?,
+                text_of(*descriptor_index)?
             )),
-            ConstantPoolEntry::Class { name_index } => self.text_of(*name_index),
+            ConstantPoolEntry::Class { name_index } => text_of(*name_index),
             _ => None,
         }
     }
 }
 
+/// The largest index of a constant pool, as `constant_pool_count` is stored as `u16` and is one
+/// greater than the largest index.
+const MAX_INDEX: usize = u16::MAX as usize - 1;
+
+/// Builds a [ConstantPool] by appending entries at the next free index. Every entry is interned,
+/// so adding an entry that is already present returns its existing index.
+///
+/// ```
+/// use rjvm::bytecode::pool::ConstantPoolBuilder;
+///
+/// let mut builder = ConstantPoolBuilder::new();
+/// let object = builder.class("java/lang/Object").unwrap();
+/// let init = builder.method_ref("java/lang/Object", "<init>", "()V").unwrap();
+/// assert_eq!(builder.class("java/lang/Object").unwrap(), object);
+/// assert_eq!(builder.method_ref("java/lang/Object", "<init>", "()V").unwrap(), init);
+///
+/// let pool = builder.build();
+/// assert_eq!(pool.class_name(object).unwrap(), "java/lang/Object");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConstantPoolBuilder {
+    pool: ConstantPool,
+    /// The indices of the entries by their encoding in the class file, which distinguishes
+    /// floating-point constants by their bits.
+    interned: HashMap<Vec<u8>, ConstantPoolIndex>,
+    next: usize,
+}
+
+impl Default for ConstantPoolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConstantPoolBuilder {
+    pub fn new() -> Self {
+        Self {
+            pool: ConstantPool::new(),
+            interned: HashMap::new(),
+            next: 1,
+        }
+    }
+
+    /// Creates a builder that appends to an existing `pool`, e.g. to patch a parsed class file.
+    /// The entries of the pool keep their indices and are reused when added again.
+    pub fn from_pool(pool: ConstantPool) -> Result<Self, BytecodeError> {
+        let mut interned = HashMap::new();
+        let mut next = 1;
+        for (index, entry) in pool.iter() {
+            if entry.tag().is_some() {
+                interned.entry(encode(entry)?).or_insert(index);
+            }
+            next = index.index() + 1;
+        }
+        Ok(Self {
+            pool,
+            interned,
+            next,
+        })
+    }
+
+    /// Returns the pool that has been built so far.
+    pub fn pool(&self) -> &ConstantPool {
+        &self.pool
+    }
+
+    /// Returns the `constant_pool_count` of the pool, which is one greater than the largest index.
+    pub fn count(&self) -> u16 {
+        self.next as u16
+    }
+
+    pub fn build(self) -> ConstantPool {
+        self.pool
+    }
+
+    /// Returns the index of `entry`, which is appended to the pool if it is not present yet.
+    /// Fails with [BytecodeError::LimitExceeded] if the entry does not fit into the pool.
+    pub fn add(&mut self, entry: ConstantPoolEntry) -> Result<ConstantPoolIndex, BytecodeError> {
+        // Unusable slots are implied by wide entries and cannot be added on their own.
+        if entry.tag().is_none() {
+            return Err(BytecodeError::InvalidTag {
+                kind: "constant pool",
+                found: 0,
+            });
+        }
+        let key = encode(&entry)?;
+        if let Some(index) = self.interned.get(&key) {
+            return Ok(*index);
+        }
+
+        let slots = if entry.is_wide() { 2 } else { 1 };
+        if self.next + slots - 1 > MAX_INDEX {
+            return Err(BytecodeError::LimitExceeded {
+                limit: "constant pool count",
+                max: MAX_INDEX + 1,
+            });
+        }
+        let index = ConstantPoolIndex(self.next);
+        self.pool.insert(index, entry)?;
+        self.interned.insert(key, index);
+        self.next += slots;
+        Ok(index)
+    }
+
+    pub fn utf8(&mut self, text: &str) -> Result<ConstantPoolIndex, BytecodeError> {
+        self.add(ConstantPoolEntry::utf8(text)?)
+    }
+
+    pub fn integer(&mut self, value: i32) -> Result<ConstantPoolIndex, BytecodeError> {
+        self.add(ConstantPoolEntry::Integer { bytes: value })
+    }
+
+    pub fn float(&mut self, value: f32) -> Result<ConstantPoolIndex, BytecodeError> {
+        self.add(ConstantPoolEntry::Float { bytes: value })
+    }
+
+    pub fn long(&mut self, value: i64) -> Result<ConstantPoolIndex, BytecodeError> {
+        let (high_bytes, low_bytes) = split_bytes(value as u64);
+        self.add(ConstantPoolEntry::Long {
+            high_bytes,
+            low_bytes,
+        })
+    }
+
+    pub fn double(&mut self, value: f64) -> Result<ConstantPoolIndex, BytecodeError> {
+        let (high_bytes, low_bytes) = split_bytes(value.to_bits());
+        self.add(ConstantPoolEntry::Double {
+            high_bytes,
+            low_bytes,
+        })
+    }
+
+    /// Adds a class with the given binary name, e.g. `java/lang/Object`.
+    pub fn class(&mut self, name: &str) -> Result<ConstantPoolIndex, BytecodeError> {
+        let name_index = self.utf8(name)?;
+        self.add(ConstantPoolEntry::Class { name_index })
+    }
+
+    pub fn string(&mut self, text: &str) -> Result<ConstantPoolIndex, BytecodeError> {
+        let string_index = self.utf8(text)?;
+        self.add(ConstantPoolEntry::String { string_index })
+    }
+
+    pub fn name_and_type(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndex, BytecodeError> {
+        let name_index = self.utf8(name)?;
+        let descriptor_index = self.utf8(descriptor)?;
+        self.add(ConstantPoolEntry::NameAndType {
+            name_index,
+            descriptor_index,
+        })
+    }
+
+    pub fn field_ref(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndex, BytecodeError> {
+        self.member_ref(MemberKind::Field, owner, name, descriptor)
+    }
+
+    pub fn method_ref(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndex, BytecodeError> {
+        self.member_ref(MemberKind::Method, owner, name, descriptor)
+    }
+
+    pub fn interface_method_ref(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndex, BytecodeError> {
+        self.member_ref(MemberKind::InterfaceMethod, owner, name, descriptor)
+    }
+
+    fn member_ref(
+        &mut self,
+        kind: MemberKind,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndex, BytecodeError> {
+        let class_index = self.class(owner)?;
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        let entry = match kind {
+            MemberKind::Field => ConstantPoolEntry::FieldRef {
+                class_index,
+                name_and_type_index,
+            },
+            MemberKind::Method => ConstantPoolEntry::MethodRef {
+                class_index,
+                name_and_type_index,
+            },
+            MemberKind::InterfaceMethod => ConstantPoolEntry::InterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            },
+        };
+        self.add(entry)
+    }
+
+    pub fn method_handle(
+        &mut self,
+        kind: ReferenceKind,
+        member: &MemberRef,
+    ) -> Result<ConstantPoolIndex, BytecodeError> {
+        let reference_index =
+            self.member_ref(member.kind, &member.owner, &member.name, &member.descriptor)?;
+        self.add(ConstantPoolEntry::MethodHandle {
+            reference_kind: kind.kind(),
+            reference_index,
+        })
+    }
+
+    pub fn method_type(&mut self, descriptor: &str) -> Result<ConstantPoolIndex, BytecodeError> {
+        let descriptor_index = self.utf8(descriptor)?;
+        self.add(ConstantPoolEntry::MethodType { descriptor_index })
+    }
+
+    pub fn dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndex, BytecodeError> {
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        self.add(ConstantPoolEntry::Dynamic {
+            bootstrap_method_attr_index: bootstrap_method_attr_index.into(),
+            name_and_type_index,
+        })
+    }
+
+    pub fn invoke_dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndex, BytecodeError> {
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        self.add(ConstantPoolEntry::InvokeDynamic {
+            bootstrap_method_attr_index: bootstrap_method_attr_index.into(),
+            name_and_type_index,
+        })
+    }
+
+    pub fn module(&mut self, name: &str) -> Result<ConstantPoolIndex, BytecodeError> {
+        let name_index = self.utf8(name)?;
+        self.add(ConstantPoolEntry::Module { name_index })
+    }
+
+    pub fn package(&mut self, name: &str) -> Result<ConstantPoolIndex, BytecodeError> {
+        let name_index = self.utf8(name)?;
+        self.add(ConstantPoolEntry::Package { name_index })
+    }
+}
+
+/// Returns the encoding of `entry` in the class file, which identifies equal entries.
+fn encode(entry: &ConstantPoolEntry) -> Result<Vec<u8>, BytecodeError> {
+    let mut writer = BufferedWriter::new();
+    write_constant_pool_entry(&mut writer, entry)?;
+    Ok(writer.into_inner())
+}
+
+fn combine_bytes(high_bytes: u32, low_bytes: u32) -> u64 {
+    ((high_bytes as u64) << 32) | low_bytes as u64
+}
+
+fn split_bytes(value: u64) -> (u32, u32) {
+    ((value >> 32) as u32, value as u32)
+}
+
+fn unexpected(index: ConstantPoolIndex, expected: &'static str) -> BytecodeError {
+    BytecodeError::UnexpectedConstant {
+        index: index.index(),
+        expected,
+    }
+}
+
+/// A resolved `CONSTANT_NameAndType_info` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NameAndType {
+    pub name: String,
+    pub descriptor: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemberKind {
+    Field,
+    Method,
+    InterfaceMethod,
+}
+
+/// A resolved `CONSTANT_Fieldref_info`, `CONSTANT_Methodref_info` or
+/// `CONSTANT_InterfaceMethodref_info` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemberRef {
+    pub kind: MemberKind,
+    /// The binary name of the class or interface that declares the member.
+    pub owner: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+/// The kind of a method handle, which characterizes its bytecode behavior.
+/// <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-5.html#jvms-5.4.3.5>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    GetField,
+    GetStatic,
+    PutField,
+    PutStatic,
+    InvokeVirtual,
+    InvokeStatic,
+    InvokeSpecial,
+    NewInvokeSpecial,
+    InvokeInterface,
+}
+
+impl ReferenceKind {
+    pub fn from_kind(kind: u8) -> Option<ReferenceKind> {
+        match kind {
+            1 => Some(ReferenceKind::GetField),
+            2 => Some(ReferenceKind::GetStatic),
+            3 => Some(ReferenceKind::PutField),
+            4 => Some(ReferenceKind::PutStatic),
+            5 => Some(ReferenceKind::InvokeVirtual),
+            6 => Some(ReferenceKind::InvokeStatic),
+            7 => Some(ReferenceKind::InvokeSpecial),
+            8 => Some(ReferenceKind::NewInvokeSpecial),
+            9 => Some(ReferenceKind::InvokeInterface),
+            _ => None,
+        }
+    }
+
+    /// Returns the numeric kind as it is stored in a class file.
+    pub fn kind(&self) -> u8 {
+        match self {
+            ReferenceKind::GetField => 1,
+            ReferenceKind::GetStatic => 2,
+            ReferenceKind::PutField => 3,
+            ReferenceKind::PutStatic => 4,
+            ReferenceKind::InvokeVirtual => 5,
+            ReferenceKind::InvokeStatic => 6,
+            ReferenceKind::InvokeSpecial => 7,
+            ReferenceKind::NewInvokeSpecial => 8,
+            ReferenceKind::InvokeInterface => 9,
+        }
+    }
+
+    /// Describes the constant that a method handle of this kind must reference.
+    fn expected_member(&self) -> &'static str {
+        match self {
+            ReferenceKind::GetField
+            | ReferenceKind::GetStatic
+            | ReferenceKind::PutField
+            | ReferenceKind::PutStatic => "Fieldref constant",
+            ReferenceKind::InvokeVirtual | ReferenceKind::NewInvokeSpecial => "Methodref constant",
+            ReferenceKind::InvokeStatic | ReferenceKind::InvokeSpecial => {
+                "Methodref or InterfaceMethodref constant"
+            }
+            ReferenceKind::InvokeInterface => "InterfaceMethodref constant",
+        }
+    }
+
+    /// Returns `true` if a method handle of this kind may reference a member with the given
+    /// `name`: `REF_newInvokeSpecial` must reference a constructor, the other method kinds must
+    /// not reference a constructor or class initializer.
+    fn accepts_name(&self, name: &str) -> bool {
+        match self {
+            ReferenceKind::GetField
+            | ReferenceKind::GetStatic
+            | ReferenceKind::PutField
+            | ReferenceKind::PutStatic => true,
+            ReferenceKind::NewInvokeSpecial => name == "<init>",
+            ReferenceKind::InvokeVirtual
+            | ReferenceKind::InvokeStatic
+            | ReferenceKind::InvokeSpecial
+            | ReferenceKind::InvokeInterface => name != "<init>" && name != "<clinit>",
+        }
+    }
+
+    /// Describes the member that a method handle of this kind must reference by name, see
+    /// [accepts_name](ReferenceKind::accepts_name).
+    fn expected_name(&self) -> &'static str {
+        match self {
+            ReferenceKind::NewInvokeSpecial => "member reference to `<init>`",
+            _ => "member reference to a method other than `<init>` or `<clinit>`",
+        }
+    }
+}
+
+/// A resolved `CONSTANT_MethodHandle_info` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodHandle {
+    pub kind: ReferenceKind,
+    pub member: MemberRef,
+}
+
+/// A resolved `CONSTANT_Dynamic_info` or `CONSTANT_InvokeDynamic_info` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DynamicRef {
+    /// The index into the `bootstrap_methods` of the `BootstrapMethods` attribute.
+    pub bootstrap_method_attr_index: u16,
+    pub name: String,
+    pub descriptor: String,
+}
+
+/// A resolved loadable constant, see [ConstantPool::loadable_constant].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(String),
+    /// The binary name of a class, e.g. `java/lang/String`.
+    Class(String),
+    MethodHandle(MethodHandle),
+    /// The method descriptor of a method type, e.g. `(I)V`.
+    MethodType(String),
+    Dynamic(DynamicRef),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConstantTag {
     /// The `CONSTANT_Class_info` constnat is used to represent a class or an interface.
@@ -312,11 +1287,41 @@ impl ConstantTag {
             _ => None,
         }
     }
+
+    /// Returns the numeric tag of the constant as it is stored in a class file.
+    pub fn tag(&self) -> u8 {
+        match self {
+            ConstantTag::Utf8 => 1,
+            ConstantTag::Integer => 3,
+            ConstantTag::Float => 4,
+            ConstantTag::Long => 5,
+            ConstantTag::Double => 6,
+            ConstantTag::Class => 7,
+            ConstantTag::String => 8,
+            ConstantTag::FieldRef => 9,
+            ConstantTag::MethodRef => 10,
+            ConstantTag::InterfaceMethodRef => 11,
+            ConstantTag::NameAndType => 12,
+            ConstantTag::MethodHandle => 15,
+            ConstantTag::MethodType => 16,
+            ConstantTag::Dynamic => 17,
+            ConstantTag::InvokeDynamic => 18,
+            ConstantTag::Module => 19,
+            ConstantTag::Package => 20,
+        }
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use crate::bytecode::pool::ConstantPoolIndex;
+    use crate::bytecode::attributes::ConstantValueInfo;
+    use crate::bytecode::pool::{
+        Constant, ConstantPool, ConstantPoolBuilder, ConstantPoolEntry, ConstantPoolIndex,
+        DynamicRef, MemberKind, MemberRef, ReferenceKind,
+    };
+    use crate::bytecode::tests::{CONSTANTS, POINT, SIMPLE, STRINGS};
+    use crate::bytecode::{BytecodeError, ClassFile};
+    use crate::error::Context;
 
     #[test]
     fn constant_pool_index_from_impl() {
@@ -325,4 +1330,355 @@ pub mod tests {
         let cpi = ConstantPoolIndex::from(42usize);
         assert_eq!(cpi, ConstantPoolIndex(42));
     }
+
+    #[test]
+    fn wide_entries_occupy_two_slots() {
+        let mut pool = ConstantPool::new();
+        let long = ConstantPoolEntry::Long {
+            high_bytes: 0xFFFF_FFFF,
+            low_bytes: 0xFFFF_FFFE,
+        };
+        pool.insert(1usize.into(), long.clone()).unwrap();
+        assert_eq!(pool.size(), 2);
+        assert_eq!(pool.get(2usize.into()), Some(&ConstantPoolEntry::Unusable));
+        assert_eq!(pool.long_at(1usize.into()), Some(-2));
+        assert_eq!(pool.long_at(2usize.into()), None);
+        assert_eq!(
+            pool.insert(2usize.into(), ConstantPoolEntry::Integer { bytes: 1 }),
+            Err(BytecodeError::ConstantPoolEntryAlreadyExists(2))
+        );
+        assert_eq!(
+            pool.insert(0usize.into(), long.clone()),
+            Err(BytecodeError::ConstantPoolEntryAlreadyExists(0))
+        );
+
+        assert_eq!(pool.remove(1usize.into()), Some(long));
+        assert!(pool.is_empty());
+        assert_eq!(pool, ConstantPool::new());
+    }
+
+    #[test]
+    fn iterate_entries_in_order() {
+        let mut pool = ConstantPool::new();
+        let integer = ConstantPoolEntry::Integer { bytes: 7 };
+        let double = ConstantPoolEntry::Double {
+            high_bytes: 0,
+            low_bytes: 1,
+        };
+        pool.insert(5usize.into(), integer.clone()).unwrap();
+        pool.insert(1usize.into(), double.clone()).unwrap();
+        assert_eq!(pool.get(4usize.into()), None);
+        assert_eq!(
+            pool.iter().collect::<Vec<_>>(),
+            [
+                (1usize.into(), &double),
+                (2usize.into(), &ConstantPoolEntry::Unusable),
+                (5usize.into(), &integer),
+            ]
+        );
+
+        pool.remove(1usize.into());
+        assert_eq!(pool.size(), 1);
+        assert_eq!(
+            pool.iter()
+                .map(|(index, _)| index.index())
+                .collect::<Vec<_>>(),
+            [5]
+        );
+    }
+
+    #[test]
+    fn read_long_and_double_constants() {
+        let cf = ClassFile::parse(CONSTANTS).unwrap();
+        let pool = &cf.constant_pool;
+
+        assert_eq!(pool.size(), 44);
+        assert_eq!(pool.long_at(9usize.into()), Some(9876543210));
+        assert_eq!(pool.get(10usize.into()), Some(&ConstantPoolEntry::Unusable));
+        assert_eq!(pool.double_at(11usize.into()), Some(std::f64::consts::E));
+        assert_eq!(
+            pool.text_of(cf.this_class).unwrap(),
+            "org/example/Constants"
+        );
+
+        let values = cf
+            .fields
+            .iter()
+            .map(|field| {
+                let info = field.attribute::<ConstantValueInfo>().unwrap();
+                pool.text_of(info.constantvalue_index).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                i64::MAX.to_string(),
+                "-1234567890123".to_string(),
+                "3.141592653589793".to_string(),
+                (-0.5e-300f64).to_string(),
+                "123456789".to_string(),
+                "constants".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_modified_utf8_constants() {
+        let pool = ClassFile::parse(STRINGS).unwrap().constant_pool;
+
+        assert_eq!(pool.text_of(16usize.into()).unwrap(), "before\0after");
+        assert_eq!(pool.text_of(19usize.into()).unwrap(), "smile 😀");
+        assert_eq!(pool.utf8_at(23usize.into()).unwrap(), "Grüße");
+        assert_eq!(pool.utf8_at(37usize.into()).unwrap(), "Hello, \u{1} ❤");
+        assert_eq!(
+            pool.utf8_at(36usize.into()),
+            Err(BytecodeError::UnexpectedConstant {
+                index: 36,
+                expected: "Utf8 constant"
+            })
+        );
+
+        let entry = ConstantPoolEntry::utf8("smile 😀").unwrap();
+        assert_eq!(pool.get(20usize.into()), Some(&entry));
+    }
+
+    #[test]
+    fn resolve_typed_constants() {
+        let pool = ClassFile::parse(POINT).unwrap().constant_pool;
+        let x = MemberRef {
+            kind: MemberKind::Field,
+            owner: "org/example/Point".to_string(),
+            name: "x".to_string(),
+            descriptor: "I".to_string(),
+        };
+
+        assert_eq!(pool.class_name(8usize.into()).unwrap(), "org/example/Point");
+        assert_eq!(pool.member_ref(7usize.into()).unwrap(), x);
+        assert_eq!(
+            pool.member_ref(1usize.into()).unwrap().kind,
+            MemberKind::Method
+        );
+        assert_eq!(
+            pool.invoke_dynamic(29usize.into()).unwrap(),
+            DynamicRef {
+                bootstrap_method_attr_index: 0,
+                name: "hashCode".to_string(),
+                descriptor: "(Lorg/example/Point;)I".to_string(),
+            }
+        );
+
+        let Constant::MethodHandle(handle) = pool.loadable_constant(72usize.into()).unwrap() else {
+            panic!("expected a method handle");
+        };
+        assert_eq!(handle.kind, ReferenceKind::GetField);
+        assert_eq!(handle.member, x);
+        let handle = pool.method_handle(63usize.into()).unwrap();
+        assert_eq!(handle.kind, ReferenceKind::InvokeStatic);
+        assert_eq!(handle.member.name, "bootstrap");
+        assert_eq!(
+            pool.loadable_constant(70usize.into()),
+            Ok(Constant::String("x;y;label;tags".to_string()))
+        );
+
+        assert_eq!(
+            pool.class_name(7usize.into()),
+            Err(BytecodeError::UnexpectedConstant {
+                index: 7,
+                expected: "Class constant"
+            })
+        );
+        assert_eq!(
+            pool.loadable_constant(25usize.into()),
+            Err(BytecodeError::UnexpectedConstant {
+                index: 25,
+                expected: "loadable constant"
+            })
+        );
+        assert_eq!(
+            pool.member_ref(500usize.into()),
+            Err(BytecodeError::ConstantPoolEntryNotFound(500))
+        );
+
+        // A method handle that invokes a field is rejected.
+        let mut pool = pool;
+        pool.remove(72usize.into());
+        let entry = ConstantPoolEntry::MethodHandle {
+            reference_kind: 5,
+            reference_index: 7usize.into(),
+        };
+        pool.insert(72usize.into(), entry).unwrap();
+        assert_eq!(
+            pool.method_handle(72usize.into()),
+            Err(BytecodeError::UnexpectedConstant {
+                index: 7,
+                expected: "Methodref constant"
+            })
+        );
+    }
+
+    #[test]
+    fn build_interned_constants() {
+        let mut builder = ConstantPoolBuilder::new();
+        let long = builder.long(-2).unwrap();
+        let string = builder.string("text").unwrap();
+        assert_eq!(long, 1usize.into());
+        // The utf8 entry of the string follows the unusable slot of the long.
+        assert_eq!(string, 4usize.into());
+        assert_eq!(builder.long(-2).unwrap(), long);
+        assert_eq!(builder.utf8("text").unwrap(), 3usize.into());
+        assert_ne!(builder.double(0.0).unwrap(), builder.double(-0.0).unwrap());
+        assert_eq!(builder.count(), 9);
+
+        let pool = builder.build();
+        assert_eq!(pool.long_at(long), Some(-2));
+        assert_eq!(pool.get(2usize.into()), Some(&ConstantPoolEntry::Unusable));
+        assert_eq!(pool.string_at(string).unwrap(), "text");
+
+        let pool = ClassFile::parse(POINT).unwrap().constant_pool;
+        let size = pool.size();
+        let mut builder = ConstantPoolBuilder::from_pool(pool).unwrap();
+        let x = builder.field_ref("org/example/Point", "x", "I").unwrap();
+        assert_eq!(x, 7usize.into());
+        assert_eq!(builder.pool().size(), size);
+        let z = builder.field_ref("org/example/Point", "z", "I").unwrap();
+        assert_eq!(builder.pool().member_ref(z).unwrap().name, "z");
+        assert_eq!(builder.pool().size(), size + 3);
+
+        let mut builder = ConstantPoolBuilder::new();
+        for value in 0..65533 {
+            builder.integer(value).unwrap();
+        }
+        assert_eq!(
+            builder.long(0),
+            Err(BytecodeError::LimitExceeded {
+                limit: "constant pool count",
+                max: 65535
+            })
+        );
+        builder.integer(65533).unwrap();
+        assert_eq!(builder.count(), 65535);
+        assert!(builder.integer(65534).is_err());
+    }
+
+    #[test]
+    fn validate_references() {
+        let fixtures: [&[u8]; 3] = [SIMPLE, CONSTANTS, POINT];
+        for input in fixtures {
+            assert_eq!(ClassFile::parse(input).unwrap().validate(), Ok(()));
+        }
+
+        let mut cf = ClassFile::parse(fixtures[2]).unwrap();
+        let unexpected = |index, expected| BytecodeError::UnexpectedConstant { index, expected };
+        let mut replace = |index: usize, entry| {
+            cf.constant_pool.remove(index.into()).unwrap();
+            cf.constant_pool.insert(index.into(), entry).unwrap();
+        };
+        // The field reference `x` points at its name and type instead of its class.
+        replace(
+            7,
+            ConstantPoolEntry::FieldRef {
+                class_index: 9usize.into(),
+                name_and_type_index: 9usize.into(),
+            },
+        );
+        // The name and type of `y` has the descriptor `y`.
+        replace(
+            14,
+            ConstantPoolEntry::NameAndType {
+                name_index: 15usize.into(),
+                descriptor_index: 15usize.into(),
+            },
+        );
+        replace(
+            72,
+            ConstantPoolEntry::MethodHandle {
+                reference_kind: 10,
+                reference_index: 7usize.into(),
+            },
+        );
+        replace(
+            73,
+            ConstantPoolEntry::MethodHandle {
+                reference_kind: 5,
+                reference_index: 13usize.into(),
+            },
+        );
+        // `REF_invokeStatic` of `Record.<init>` and `REF_newInvokeSpecial` of
+        // `ObjectMethods.bootstrap`.
+        replace(
+            74,
+            ConstantPoolEntry::MethodHandle {
+                reference_kind: 6,
+                reference_index: 1usize.into(),
+            },
+        );
+        replace(
+            75,
+            ConstantPoolEntry::MethodHandle {
+                reference_kind: 8,
+                reference_index: 64usize.into(),
+            },
+        );
+        replace(
+            70,
+            ConstantPoolEntry::String {
+                string_index: 500usize.into(),
+            },
+        );
+        cf.super_class = 4usize.into();
+        assert_eq!(
+            cf.constant_pool.method_handle(75usize.into()),
+            Err(unexpected(64, "member reference to `<init>`"))
+        );
+
+        let errors = cf
+            .validate()
+            .unwrap_err()
+            .into_iter()
+            .map(|err| (err.context()[0].clone(), err.kind().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                (
+                    Context::ConstantPoolEntry(7),
+                    unexpected(9, "Class constant")
+                ),
+                (
+                    Context::ConstantPoolEntry(14),
+                    BytecodeError::InvalidDescriptor {
+                        descriptor: "y".to_string(),
+                        position: 0
+                    }
+                ),
+                (
+                    Context::ConstantPoolEntry(70),
+                    BytecodeError::ConstantPoolEntryNotFound(500)
+                ),
+                (
+                    Context::ConstantPoolEntry(72),
+                    BytecodeError::InvalidTag {
+                        kind: "reference kind",
+                        found: 10
+                    }
+                ),
+                (
+                    Context::ConstantPoolEntry(73),
+                    unexpected(13, "Methodref constant")
+                ),
+                (
+                    Context::ConstantPoolEntry(74),
+                    unexpected(
+                        1,
+                        "member reference to a method other than `<init>` or `<clinit>`"
+                    )
+                ),
+                (
+                    Context::ConstantPoolEntry(75),
+                    unexpected(64, "member reference to `<init>`")
+                ),
+                (Context::Class, unexpected(4, "Class constant")),
+            ]
+        );
+    }
 }
@@ -1,51 +1,171 @@
 use crate::bytecode::attributes::{
-    Annotation, AnnotationDefaultInfo, AnyAttribute, AttributeFactory, BootstrapMethod,
+    Annotation, AnnotationDefaultInfo, AnyAttribute, AttributeFactory, Attributes, BootstrapMethod,
     BootstrapMethodsInfo, CodeInfo, ConstantValueInfo, Container, DeprecatedInfo, ElementValue,
     ElementValuePair, EnclosingMethodInfo, ExceptionTableEntry, ExceptionsInfo, Exports,
     InnerClass, InnerClassesInfo, LineNumberTableEntry, LineNumberTableInfo,
     LocalVarTargetTableEntry, LocalVariableTableEntry, LocalVariableTableInfo,
     LocalVariableTypeTableEntry, LocalVariableTypeTableInfo, MethodParameter, MethodParametersInfo,
-    ModuleInfo, ModuleMainClassInfo, ModulePackagesInfo, NestHostInfo, NestMembersInfo, Opens,
-    ParameterAnnotation, PermittedSubtypesInfo, Provides, RecordInfo, Requires,
-    RuntimeInvisibleAnnotationsInfo, RuntimeInvisibleParameterAnnotationsInfo,
-    RuntimeInvisibleTypeAnnotationsInfo, RuntimeVisibleAnnotationsInfo,
-    RuntimeVisibleParameterAnnotationsInfo, RuntimeVisibleTypeAnnotationsInfo, SignatureInfo,
-    SourceDebugExtensionInfo, SourceFileInfo, StackMapFrame, StackMapTableInfo, SyntheticInfo,
-    TypeAnnotation, TypeAnnotationTargetInfo, TypeAnnotationTargetInfoType, TypePath,
-    TypePathEntry, VerificationTypeInfo,
+    ModuleInfo, ModuleMainClassInfo, ModulePackagesInfo, NamedAttributeFactory, NestHostInfo,
+    NestMembersInfo, Opens, ParameterAnnotation, PermittedSubtypesInfo, Provides, RawAttribute,
+    RecordComponent, RecordInfo, Requires, RuntimeInvisibleAnnotationsInfo,
+    RuntimeInvisibleParameterAnnotationsInfo, RuntimeInvisibleTypeAnnotationsInfo,
+    RuntimeVisibleAnnotationsInfo, RuntimeVisibleParameterAnnotationsInfo,
+    RuntimeVisibleTypeAnnotationsInfo, SignatureInfo, SourceDebugExtensionInfo, SourceFileInfo,
+    StackMapFrame, StackMapTableInfo, SyntheticInfo, TypeAnnotation, TypeAnnotationTargetInfo,
+    TypeAnnotationTargetInfoType, TypePath, TypePathEntry, UnknownAttributePolicy,
+    VerificationTypeInfo,
 };
 use crate::bytecode::flags::InnerClassAccessFlags;
-use crate::bytecode::pool::{ConstantPool, ConstantPoolIndex};
+use crate::bytecode::pool::{ConstantPoolIndex, ConstantPoolView};
+use crate::bytecode::reader::options::ParseOptions;
 use crate::bytecode::reader::BufferedReader;
-use crate::bytecode::BytecodeError;
-
+use crate::bytecode::{BytecodeError, Descriptor};
+use crate::error::{Context, Error};
+
+/// Reads the next attribute using the [AttributeFactory] registered for its name in the
+/// `container`. Attributes without a registered factory are handled according to the container's
+/// [UnknownAttributePolicy]; `None` is returned if such an attribute has been skipped.
+///
+/// The factory reads the attribute from a reader that is bounded to its `attribute_length`, so it
+/// can neither read past the end of the attribute nor leave any of its bytes unread.
 pub fn read_attribute(
     reader: &mut BufferedReader,
-    cp: &mut ConstantPool,
+    cp: &dyn ConstantPoolView,
+    container: &Container,
+) -> Result<Option<Box<dyn AnyAttribute>>, Error> {
+    reader.nested(|reader| {
+        let mut attribute_reader = named_attribute_reader(reader, cp)?;
+        read_bounded_attribute(&mut attribute_reader, cp, container)
+    })
+}
+
+/// Reads the next attribute like [read_attribute], but records a malformed attribute as a
+/// diagnostic and skips it if the `options` are lenient. `None` is returned if the attribute has
+/// been skipped.
+pub fn read_attribute_with(
+    reader: &mut BufferedReader,
+    cp: &dyn ConstantPoolView,
+    container: &Container,
+    options: &mut ParseOptions,
+) -> Result<Option<Box<dyn AnyAttribute>>, Error> {
+    reader.nested(|reader| {
+        // If the bounds of the attribute are unknown, there is no way to continue with the
+        // structure following it.
+        let mut attribute_reader = named_attribute_reader(reader, cp)?;
+        match read_bounded_attribute_with(&mut attribute_reader, cp, container, options) {
+            Ok(attribute) => Ok(attribute),
+            Err(err) => {
+                options.recover(err)?;
+                Ok(None)
+            }
+        }
+    })
+}
+
+/// Reads `attributes_count` attributes with [read_attribute_with] and keeps them in their original
+/// order, each under its name in the constant pool.
+pub(crate) fn read_attributes_with(
+    reader: &mut BufferedReader,
+    cp: &dyn ConstantPoolView,
     container: &Container,
-) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    options: &mut ParseOptions,
+    attributes_count: u16,
+) -> Result<Attributes, Error> {
+    let capacity =
+        reader.capacity_for::<(String, Box<dyn AnyAttribute>)>(attributes_count as usize)?;
+    let mut attributes = Attributes::with_capacity(capacity);
+    for _ in 0..attributes_count {
+        let name_index = reader.peek_bytes::<u16>()?;
+        if let Some(attribute) = read_attribute_with(reader, cp, container, options)? {
+            // The name has already been resolved for reading the attribute.
+            let name = cp.utf8_at(name_index.into()).map_err(Error::new)?;
+            attributes.push(name, attribute);
+        }
+    }
+    Ok(attributes)
+}
+
+/// Takes the next attribute from the `reader` and returns a reader that is bounded to it,
+/// including its `attribute_name_index` and `attribute_length`.
+pub(crate) fn attribute_reader<'a>(
+    reader: &mut BufferedReader<'a>,
+) -> Result<BufferedReader<'a>, Error> {
+    let mut header = reader.clone();
+    let _attribute_name_index = header.take::<u16>()?;
+    let attribute_length = header.take::<u32>()?;
+    reader.sub_reader(6 + attribute_length as usize)
+}
+
+/// Takes the next attribute like [attribute_reader], but reports a truncated attribute within
+/// its name if that can be resolved.
+fn named_attribute_reader<'a>(
+    reader: &mut BufferedReader<'a>,
+    cp: &dyn ConstantPoolView,
+) -> Result<BufferedReader<'a>, Error> {
     let attribute_name_index = reader.peek_bytes::<u16>()?;
-    let Some(name) = cp.text_of(attribute_name_index.into()) else {
-        return Err(BytecodeError::InvalidData);
-    };
+    attribute_reader(reader).map_err(|err| match cp.utf8_at(attribute_name_index.into()) {
+        Ok(name) => err.within(Context::Attribute(name.into_owned())),
+        Err(_) => err,
+    })
+}
 
-    let Some(attr) = container.get_by_name(&name) else {
-        return Err(BytecodeError::UnsupportedAttributeName(name));
+pub(crate) fn read_bounded_attribute(
+    reader: &mut BufferedReader,
+    cp: &dyn ConstantPoolView,
+    container: &Container,
+) -> Result<Option<Box<dyn AnyAttribute>>, Error> {
+    read_bounded_attribute_with(reader, cp, container, &mut ParseOptions::default())
+}
+
+/// Reads a bounded attribute like [read_bounded_attribute], passing the `options` on to the
+/// factory so that nested attributes are read with them as well.
+pub(crate) fn read_bounded_attribute_with(
+    reader: &mut BufferedReader,
+    cp: &dyn ConstantPoolView,
+    container: &Container,
+    options: &mut ParseOptions,
+) -> Result<Option<Box<dyn AnyAttribute>>, Error> {
+    let attribute_name_index = reader.peek_bytes::<u16>()?;
+    let name = cp
+        .utf8_at(attribute_name_index.into())
+        .map_err(|kind| Error::new(kind).at(reader.offset()))?
+        .into_owned();
+
+    let start = options.diagnostics().len();
+    let attribute = match container.get_by_name(&name) {
+        Some(attr) => attr.make_with(reader, cp, container, options),
+        None => match container.unknown_attribute_policy() {
+            UnknownAttributePolicy::Error => {
+                let kind = BytecodeError::UnsupportedAttributeName(name);
+                return Err(Error::new(kind).at(reader.offset()));
+            }
+            UnknownAttributePolicy::Skip => return Ok(None),
+            UnknownAttributePolicy::KeepRaw => RawAttributeFactory.make(reader, cp, container),
+        },
     };
+    let attribute = attribute.map_err(|err| err.within(Context::Attribute(name.clone())))?;
+    options.within(start, Context::Attribute(name.clone()));
+
+    if reader.has_remaining_data() {
+        let kind = BytecodeError::AttributeLengthMismatch {
+            expected: reader.size() - 6,
+            found: reader.position().saturating_sub(6),
+        };
+        return Err(Error::new(kind)
+            .at(reader.offset())
+            .within(Context::Attribute(name)));
+    }
 
-    attr.make(reader, cp, container)
+    Ok(Some(attribute))
 }
 
-fn read_annotation(
-    reader: &mut BufferedReader,
-    pool: &mut ConstantPool,
-) -> Result<Annotation, BytecodeError> {
+pub(crate) fn read_annotation(reader: &mut BufferedReader) -> Result<Annotation, Error> {
     let type_index = reader.take::<u16>()?;
     let num_element_value_pairs = reader.take::<u16>()?;
-    let mut element_value_pairs = Vec::with_capacity(num_element_value_pairs as usize);
+    let mut element_value_pairs = reader.allocate(num_element_value_pairs as usize)?;
     for _ in 0..num_element_value_pairs {
         let element_name_index = reader.take::<u16>()?;
-        let element_value = read_elementvalue(reader, pool)?;
+        let element_value = read_elementvalue(reader)?;
         element_value_pairs.push(ElementValuePair {
             element_name_index: ConstantPoolIndex::new(element_name_index),
             value: element_value,
@@ -61,17 +181,17 @@ fn read_annotation(
 
 fn read_typeannotation(
     reader: &mut BufferedReader,
-    cp: &mut ConstantPool,
-) -> Result<TypeAnnotation, BytecodeError> {
+    cp: &dyn ConstantPoolView,
+) -> Result<TypeAnnotation, Error> {
     let target_type = reader.take::<u8>()?;
-    let target_info = read_typeannotationtarget_info(reader, cp)?;
+    let target_info = read_typeannotationtarget_info(reader, cp, target_type)?;
     let target_path = read_typepath(reader, cp)?;
     let type_index = reader.take::<u16>()?;
     let num_element_value_pairs = reader.take::<u16>()?;
-    let mut element_value_pairs = Vec::with_capacity(num_element_value_pairs as usize);
+    let mut element_value_pairs = reader.allocate(num_element_value_pairs as usize)?;
     for _ in 0..num_element_value_pairs {
         let element_name_index = reader.take::<u16>()?;
-        let element_value = read_elementvalue(reader, cp)?;
+        let element_value = read_elementvalue(reader)?;
         element_value_pairs.push(ElementValuePair {
             element_name_index: ConstantPoolIndex::new(element_name_index),
             value: element_value,
@@ -90,12 +210,12 @@ fn read_typeannotation(
 
 fn read_typeannotationtarget_info(
     reader: &mut BufferedReader,
-    _cp: &mut ConstantPool,
-) -> Result<TypeAnnotationTargetInfo, BytecodeError> {
-    let target_type = reader.take::<u8>()?;
+    _cp: &dyn ConstantPoolView,
+    target_type: u8,
+) -> Result<TypeAnnotationTargetInfo, Error> {
     let target_info = match target_type {
         0x00 | 0x01 => {
-            let offset = reader.take::<u16>()?;
+            let offset = reader.take::<u8>()?;
             TypeAnnotationTargetInfoType::TypeParameter {
                 type_parameter_index: ConstantPoolIndex::new(offset),
             }
@@ -107,7 +227,7 @@ fn read_typeannotationtarget_info(
             }
         }
         0x11 | 0x12 => {
-            let offset = reader.take::<u16>()?;
+            let offset = reader.take::<u8>()?;
             let type_argument_index = reader.take::<u8>()?;
             TypeAnnotationTargetInfoType::TypeParameterBound {
                 type_parameter_index: ConstantPoolIndex::new(offset),
@@ -116,7 +236,7 @@ fn read_typeannotationtarget_info(
         }
         0x13..=0x15 => TypeAnnotationTargetInfoType::Empty,
         0x16 => {
-            let parameter_index = reader.take::<u16>()?;
+            let parameter_index = reader.take::<u8>()?;
             TypeAnnotationTargetInfoType::FormalParameter {
                 formal_parameter_index: ConstantPoolIndex::new(parameter_index),
             }
@@ -129,7 +249,7 @@ fn read_typeannotationtarget_info(
         }
         0x40 | 0x41 => {
             let table_length = reader.take::<u16>()?;
-            let mut table = Vec::with_capacity(table_length as usize);
+            let mut table = reader.allocate(table_length as usize)?;
             for _ in 0..table_length {
                 let start_pc = reader.take::<u16>()?;
                 let length = reader.take::<u16>()?;
@@ -160,28 +280,37 @@ fn read_typeannotationtarget_info(
                 type_argument_index: ConstantPoolIndex::new(type_argument_index),
             }
         }
-        _ => return Err(BytecodeError::InvalidData),
+        _ => {
+            return Err(reader.error(BytecodeError::InvalidTag {
+                kind: "type annotation target",
+                found: target_type,
+            }))
+        }
     };
 
     Ok(TypeAnnotationTargetInfo { target_info })
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ConstantValueAttributeFactory;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct CodeAttributeFactory;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct LineNumberTableAttributeFactory;
 
+impl NamedAttributeFactory for ConstantValueAttributeFactory {
+    type Attribute = ConstantValueInfo;
+}
+
 impl AttributeFactory for ConstantValueAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let constantvalue_index = reader.take::<u16>()?;
@@ -194,39 +323,54 @@ impl AttributeFactory for ConstantValueAttributeFactory {
     }
 }
 
+impl NamedAttributeFactory for CodeAttributeFactory {
+    type Attribute = CodeInfo;
+}
+
 impl AttributeFactory for CodeAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        pool: &dyn ConstantPoolView,
         container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
+        self.make_with(reader, pool, container, &mut ParseOptions::default())
+    }
+
+    fn make_with(
+        &self,
+        reader: &mut BufferedReader,
+        pool: &dyn ConstantPoolView,
+        container: &Container,
+        options: &mut ParseOptions,
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let max_stack = reader.take::<u16>()?;
         let max_locals = reader.take::<u16>()?;
         let code_length = reader.take::<u32>()?;
+        let max_code_length = reader.limits().max_code_length;
+        if code_length as usize > max_code_length {
+            return Err(reader.error(BytecodeError::LimitExceeded {
+                limit: "code length",
+                max: max_code_length,
+            }));
+        }
         let code = reader.take_bytes(code_length as usize)?;
+        reader.reserve(code.len())?;
         let exception_table_length = reader.take::<u16>()?;
-        let mut exception_table = Vec::with_capacity(exception_table_length as usize);
-        for _ in 0..exception_table_length {
-            let start_pc = reader.take::<u16>()?;
-            let end_pc = reader.take::<u16>()?;
-            let handler_pc = reader.take::<u16>()?;
-            let catch_type = reader.take::<u16>()?;
-            exception_table.push(ExceptionTableEntry {
-                start_pc,
-                end_pc,
-                handler_pc,
-                catch_type: ConstantPoolIndex::new(catch_type),
-            });
+        let mut exception_table = reader.allocate(exception_table_length as usize)?;
+        for index in 0..exception_table_length as usize {
+            let entry = read_exception_table_entry(reader).map_err(|err| {
+                err.within(Context::Entry {
+                    table: "exception table",
+                    index,
+                })
+            })?;
+            exception_table.push(entry);
         }
         let attributes_count = reader.take::<u16>()?;
-        let mut attributes = Vec::with_capacity(attributes_count as usize);
-        for _ in 0..attributes_count {
-            let attribute = read_attribute(reader, pool, container)?;
-            attributes.push(attribute);
-        }
+        let attributes = read_attributes_with(reader, pool, container, options, attributes_count)?;
 
         Ok(Box::new(CodeInfo {
             attribute_name_index: ConstantPoolIndex::new(attribute_name_index),
@@ -243,194 +387,47 @@ impl AttributeFactory for CodeAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+pub(crate) fn read_exception_table_entry(
+    reader: &mut BufferedReader,
+) -> Result<ExceptionTableEntry, Error> {
+    let start_pc = reader.take::<u16>()?;
+    let end_pc = reader.take::<u16>()?;
+    let handler_pc = reader.take::<u16>()?;
+    let catch_type = reader.take::<u16>()?;
+
+    Ok(ExceptionTableEntry {
+        start_pc,
+        end_pc,
+        handler_pc,
+        catch_type: ConstantPoolIndex::new(catch_type),
+    })
+}
+
+#[derive(Debug, Default)]
 pub struct StackMapTableAttributeFactory;
 
+impl NamedAttributeFactory for StackMapTableAttributeFactory {
+    type Attribute = StackMapTableInfo;
+}
+
 impl AttributeFactory for StackMapTableAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let number_of_entries = reader.take::<u16>()?;
-        let frame_type = reader.take::<u8>()?;
-        let mut entries = Vec::with_capacity(number_of_entries as usize);
-        for _ in 0..number_of_entries {
-            let entry = match frame_type {
-                0..=63 => StackMapFrame::SameFrame { frame_type },
-                64..=127 => {
-                    let verification_type = reader.take::<u8>()?;
-                    let verification_type = match verification_type {
-                        0 => VerificationTypeInfo::Top,
-                        1 => VerificationTypeInfo::Integer,
-                        2 => VerificationTypeInfo::Float,
-                        3 => VerificationTypeInfo::Double,
-                        4 => VerificationTypeInfo::Long,
-                        5 => VerificationTypeInfo::Null,
-                        6 => VerificationTypeInfo::UninitializedThis,
-                        7 => {
-                            let class = reader.take::<u16>()?;
-                            VerificationTypeInfo::Object {
-                                class: class.into(),
-                            }
-                        }
-                        8 => {
-                            let offset = reader.take::<u16>()?;
-                            VerificationTypeInfo::Uninitialized { offset }
-                        }
-                        _ => return Err(BytecodeError::UnsupportedVerificationType),
-                    };
-                    StackMapFrame::SameLocals1StackItemFrame {
-                        frame_type,
-                        stack: verification_type,
-                    }
-                }
-                247 => {
-                    let offset_delta = reader.take::<u16>()?;
-                    let verification_type = reader.take::<u8>()?;
-                    let verification_type = match verification_type {
-                        0 => VerificationTypeInfo::Top,
-                        1 => VerificationTypeInfo::Integer,
-                        2 => VerificationTypeInfo::Float,
-                        3 => VerificationTypeInfo::Double,
-                        4 => VerificationTypeInfo::Long,
-                        5 => VerificationTypeInfo::Null,
-                        6 => VerificationTypeInfo::UninitializedThis,
-                        7 => {
-                            let class = reader.take::<u16>()?;
-                            VerificationTypeInfo::Object {
-                                class: class.into(),
-                            }
-                        }
-                        8 => {
-                            let offset = reader.take::<u16>()?;
-                            VerificationTypeInfo::Uninitialized { offset }
-                        }
-                        _ => return Err(BytecodeError::UnsupportedVerificationType),
-                    };
-
-                    StackMapFrame::SameLocals1StackItemFrameExtended {
-                        frame_type,
-                        offset_delta,
-                        stack: verification_type,
-                    }
-                }
-                248..=250 => {
-                    let offset_delta = reader.take::<u16>()?;
-                    StackMapFrame::ChopFrame {
-                        frame_type,
-                        offset_delta,
-                    }
-                }
-                251 => {
-                    let offset_delta = reader.take::<u16>()?;
-                    StackMapFrame::SameFrameExtended {
-                        frame_type,
-                        offset_delta,
-                    }
-                }
-                252..=254 => {
-                    let offset_delta = reader.take::<u16>()?;
-                    let mut locals = Vec::with_capacity(frame_type as usize - 251);
-                    for _ in 0..frame_type - 251 {
-                        let verification_type = reader.take::<u8>()?;
-                        let verification_type = match verification_type {
-                            0 => VerificationTypeInfo::Top,
-                            1 => VerificationTypeInfo::Integer,
-                            2 => VerificationTypeInfo::Float,
-                            3 => VerificationTypeInfo::Double,
-                            4 => VerificationTypeInfo::Long,
-                            5 => VerificationTypeInfo::Null,
-                            6 => VerificationTypeInfo::UninitializedThis,
-                            7 => {
-                                let class = reader.take::<u16>()?;
-                                VerificationTypeInfo::Object {
-                                    class: class.into(),
-                                }
-                            }
-                            8 => {
-                                let offset = reader.take::<u16>()?;
-                                VerificationTypeInfo::Uninitialized { offset }
-                            }
-                            _ => return Err(BytecodeError::UnsupportedVerificationType),
-                        };
-                        locals.push(verification_type);
-                    }
-                    StackMapFrame::AppendFrame {
-                        frame_type,
-                        offset_delta,
-                        locals,
-                    }
-                }
-                255 => {
-                    let offset_delta = reader.take::<u16>()?;
-                    let number_of_locals = reader.take::<u16>()?;
-                    let mut locals = Vec::with_capacity(number_of_locals as usize);
-                    for _ in 0..number_of_locals {
-                        let verification_type = reader.take::<u8>()?;
-                        let verification_type = match verification_type {
-                            0 => VerificationTypeInfo::Top,
-                            1 => VerificationTypeInfo::Integer,
-                            2 => VerificationTypeInfo::Float,
-                            3 => VerificationTypeInfo::Double,
-                            4 => VerificationTypeInfo::Long,
-                            5 => VerificationTypeInfo::Null,
-                            6 => VerificationTypeInfo::UninitializedThis,
-                            7 => {
-                                let class = reader.take::<u16>()?;
-                                VerificationTypeInfo::Object {
-                                    class: class.into(),
-                                }
-                            }
-                            8 => {
-                                let offset = reader.take::<u16>()?;
-                                VerificationTypeInfo::Uninitialized { offset }
-                            }
-                            _ => return Err(BytecodeError::UnsupportedVerificationType),
-                        };
-                        locals.push(verification_type);
-                    }
-                    let number_of_stack_items = reader.take::<u16>()?;
-                    let mut stack = Vec::with_capacity(number_of_stack_items as usize);
-                    for _ in 0..number_of_stack_items {
-                        let verification_type = reader.take::<u8>()?;
-                        let verification_type = match verification_type {
-                            0 => VerificationTypeInfo::Top,
-                            1 => VerificationTypeInfo::Integer,
-                            2 => VerificationTypeInfo::Float,
-                            3 => VerificationTypeInfo::Double,
-                            4 => VerificationTypeInfo::Long,
-                            5 => VerificationTypeInfo::Null,
-                            6 => VerificationTypeInfo::UninitializedThis,
-                            7 => {
-                                let class = reader.take::<u16>()?;
-                                VerificationTypeInfo::Object {
-                                    class: class.into(),
-                                }
-                            }
-                            8 => {
-                                let offset = reader.take::<u16>()?;
-                                VerificationTypeInfo::Uninitialized { offset }
-                            }
-                            _ => return Err(BytecodeError::UnsupportedVerificationType),
-                        };
-                        stack.push(verification_type);
-                    }
-                    StackMapFrame::FullFrame {
-                        frame_type,
-                        number_of_locals,
-                        number_of_stack_items,
-                        offset_delta,
-                        locals,
-                        stack,
-                    }
-                }
-                _ => return Err(BytecodeError::InvalidData),
-            };
-
+        let mut entries = reader.allocate(number_of_entries as usize)?;
+        for index in 0..number_of_entries as usize {
+            let entry = read_stackmapframe(reader).map_err(|err| {
+                err.within(Context::Entry {
+                    table: "stack map table",
+                    index,
+                })
+            })?;
             entries.push(entry);
         }
 
@@ -443,20 +440,122 @@ impl AttributeFactory for StackMapTableAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+fn read_stackmapframe(reader: &mut BufferedReader) -> Result<StackMapFrame, Error> {
+    let frame_type = reader.take::<u8>()?;
+    let frame = match frame_type {
+        0..=63 => StackMapFrame::SameFrame { frame_type },
+        64..=127 => StackMapFrame::SameLocals1StackItemFrame {
+            frame_type,
+            stack: read_verification_type(reader)?,
+        },
+        247 => StackMapFrame::SameLocals1StackItemFrameExtended {
+            frame_type,
+            offset_delta: reader.take::<u16>()?,
+            stack: read_verification_type(reader)?,
+        },
+        248..=250 => StackMapFrame::ChopFrame {
+            frame_type,
+            offset_delta: reader.take::<u16>()?,
+        },
+        251 => StackMapFrame::SameFrameExtended {
+            frame_type,
+            offset_delta: reader.take::<u16>()?,
+        },
+        252..=254 => {
+            let offset_delta = reader.take::<u16>()?;
+            let locals = read_verification_types(reader, frame_type as u16 - 251)?;
+            StackMapFrame::AppendFrame {
+                frame_type,
+                offset_delta,
+                locals,
+            }
+        }
+        255 => {
+            let offset_delta = reader.take::<u16>()?;
+            let number_of_locals = reader.take::<u16>()?;
+            let locals = read_verification_types(reader, number_of_locals)?;
+            let number_of_stack_items = reader.take::<u16>()?;
+            let stack = read_verification_types(reader, number_of_stack_items)?;
+            StackMapFrame::FullFrame {
+                frame_type,
+                offset_delta,
+                number_of_locals,
+                locals,
+                number_of_stack_items,
+                stack,
+            }
+        }
+        _ => {
+            return Err(reader.error(BytecodeError::InvalidTag {
+                kind: "stack map frame",
+                found: frame_type,
+            }))
+        }
+    };
+
+    Ok(frame)
+}
+
+fn read_verification_types(
+    reader: &mut BufferedReader,
+    count: u16,
+) -> Result<Vec<VerificationTypeInfo>, Error> {
+    let mut types = reader.allocate(count as usize)?;
+    for _ in 0..count {
+        types.push(read_verification_type(reader)?);
+    }
+    Ok(types)
+}
+
+fn read_verification_type(reader: &mut BufferedReader) -> Result<VerificationTypeInfo, Error> {
+    let tag = reader.take::<u8>()?;
+    let verification_type = match tag {
+        0 => VerificationTypeInfo::Top,
+        1 => VerificationTypeInfo::Integer,
+        2 => VerificationTypeInfo::Float,
+        3 => VerificationTypeInfo::Double,
+        4 => VerificationTypeInfo::Long,
+        5 => VerificationTypeInfo::Null,
+        6 => VerificationTypeInfo::UninitializedThis,
+        7 => {
+            let class = reader.take::<u16>()?;
+            VerificationTypeInfo::Object {
+                class: class.into(),
+            }
+        }
+        8 => {
+            let offset = reader.take::<u16>()?;
+            VerificationTypeInfo::Uninitialized { offset }
+        }
+        _ => {
+            return Err(reader.error(BytecodeError::InvalidTag {
+                kind: "verification type",
+                found: tag,
+            }))
+        }
+    };
+
+    Ok(verification_type)
+}
+
+#[derive(Debug, Default)]
 pub struct ExceptionsAttributeFactory;
 
+impl NamedAttributeFactory for ExceptionsAttributeFactory {
+    type Attribute = ExceptionsInfo;
+}
+
 impl AttributeFactory for ExceptionsAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let number_of_exceptions = reader.take::<u16>()?;
-        let mut exception_index_table = Vec::with_capacity(number_of_exceptions as usize);
+        let mut exception_index_table = reader.allocate(number_of_exceptions as usize)?;
         for _ in 0..number_of_exceptions {
             let exception_index = reader.take::<u16>()?;
             exception_index_table.push(ConstantPoolIndex::new(exception_index));
@@ -471,20 +570,24 @@ impl AttributeFactory for ExceptionsAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct InnerClassesAttributeFactory;
 
+impl NamedAttributeFactory for InnerClassesAttributeFactory {
+    type Attribute = InnerClassesInfo;
+}
+
 impl AttributeFactory for InnerClassesAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let number_of_classes = reader.take::<u16>()?;
-        let mut classes = Vec::with_capacity(number_of_classes as usize);
+        let mut classes = reader.allocate(number_of_classes as usize)?;
         for _ in 0..number_of_classes {
             let inner_class_info_index = reader.take::<u16>()?;
             let outer_class_info_index = reader.take::<u16>()?;
@@ -493,7 +596,9 @@ impl AttributeFactory for InnerClassesAttributeFactory {
             let Some(inner_class_access_flags) =
                 InnerClassAccessFlags::from_bits(inner_class_access_flags)
             else {
-                return Err(BytecodeError::InvalidData);
+                return Err(
+                    reader.error(BytecodeError::InvalidAccessFlags(inner_class_access_flags))
+                );
             };
             classes.push(InnerClass {
                 inner_class_info_index: ConstantPoolIndex::new(inner_class_info_index),
@@ -512,16 +617,20 @@ impl AttributeFactory for InnerClassesAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct EnclosingMethodAttributeFactory;
 
+impl NamedAttributeFactory for EnclosingMethodAttributeFactory {
+    type Attribute = EnclosingMethodInfo;
+}
+
 impl AttributeFactory for EnclosingMethodAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let class_index = reader.take::<u16>()?;
@@ -536,16 +645,20 @@ impl AttributeFactory for EnclosingMethodAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct SyntheticAttributeFactory;
 
+impl NamedAttributeFactory for SyntheticAttributeFactory {
+    type Attribute = SyntheticInfo;
+}
+
 impl AttributeFactory for SyntheticAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
 
@@ -556,16 +669,20 @@ impl AttributeFactory for SyntheticAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct SignatureAttributeFactory;
 
+impl NamedAttributeFactory for SignatureAttributeFactory {
+    type Attribute = SignatureInfo;
+}
+
 impl AttributeFactory for SignatureAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let signature_index = reader.take::<u16>()?;
@@ -578,16 +695,20 @@ impl AttributeFactory for SignatureAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct SourceFileAttributeFactory;
 
+impl NamedAttributeFactory for SourceFileAttributeFactory {
+    type Attribute = SourceFileInfo;
+}
+
 impl AttributeFactory for SourceFileAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let sourcefile_index = reader.take::<u16>()?;
@@ -600,39 +721,48 @@ impl AttributeFactory for SourceFileAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct SourceDebugExtensionAttributeFactory;
 
+impl NamedAttributeFactory for SourceDebugExtensionAttributeFactory {
+    type Attribute = SourceDebugExtensionInfo;
+}
+
 impl AttributeFactory for SourceDebugExtensionAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
-        let debug_extension = reader.take::<Vec<u8>>()?;
+        let debug_extension = reader.take_bytes(attribute_length as usize)?;
+        reader.reserve(debug_extension.len())?;
 
         Ok(Box::new(SourceDebugExtensionInfo {
             attribute_name_index: ConstantPoolIndex::new(attribute_name_index),
             attribute_length,
-            debug_extension,
+            debug_extension: debug_extension.to_vec(),
         }))
     }
 }
 
+impl NamedAttributeFactory for LineNumberTableAttributeFactory {
+    type Attribute = LineNumberTableInfo;
+}
+
 impl AttributeFactory for LineNumberTableAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let line_number_table_length = reader.take::<u16>()?;
-        let mut line_number_table = Vec::with_capacity(line_number_table_length as usize);
+        let mut line_number_table = reader.allocate(line_number_table_length as usize)?;
         for _ in 0..line_number_table_length {
             let start_pc = reader.take::<u16>()?;
             let line_number = reader.take::<u16>()?;
@@ -651,20 +781,24 @@ impl AttributeFactory for LineNumberTableAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct LocalVariableTableAttributeFactory;
 
+impl NamedAttributeFactory for LocalVariableTableAttributeFactory {
+    type Attribute = LocalVariableTableInfo;
+}
+
 impl AttributeFactory for LocalVariableTableAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let local_variable_table_length = reader.take::<u16>()?;
-        let mut local_variable_table = Vec::with_capacity(local_variable_table_length as usize);
+        let mut local_variable_table = reader.allocate(local_variable_table_length as usize)?;
         for _ in 0..local_variable_table_length {
             let start_pc = reader.take::<u16>()?;
             let length = reader.take::<u16>()?;
@@ -689,21 +823,25 @@ impl AttributeFactory for LocalVariableTableAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct LocalVariableTypeTableAttributeFactory;
 
+impl NamedAttributeFactory for LocalVariableTypeTableAttributeFactory {
+    type Attribute = LocalVariableTypeTableInfo;
+}
+
 impl AttributeFactory for LocalVariableTypeTableAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let local_variable_type_table_length = reader.take::<u16>()?;
         let mut local_variable_type_table =
-            Vec::with_capacity(local_variable_type_table_length as usize);
+            reader.allocate(local_variable_type_table_length as usize)?;
         for _ in 0..local_variable_type_table_length {
             let start_pc = reader.take::<u16>()?;
             let length = reader.take::<u16>()?;
@@ -728,16 +866,20 @@ impl AttributeFactory for LocalVariableTypeTableAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct DeprecatedAttributeFactory;
 
+impl NamedAttributeFactory for DeprecatedAttributeFactory {
+    type Attribute = DeprecatedInfo;
+}
+
 impl AttributeFactory for DeprecatedAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
 
@@ -748,22 +890,31 @@ impl AttributeFactory for DeprecatedAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RuntimeVisibleAnnotationsAttributeFactory;
 
+impl NamedAttributeFactory for RuntimeVisibleAnnotationsAttributeFactory {
+    type Attribute = RuntimeVisibleAnnotationsInfo;
+}
+
 impl AttributeFactory for RuntimeVisibleAnnotationsAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let num_annotations = reader.take::<u16>()?;
-        let mut annotations = Vec::with_capacity(num_annotations as usize);
-        for _ in 0..num_annotations {
-            let annotation = read_annotation(reader, pool)?;
+        let mut annotations = reader.allocate(num_annotations as usize)?;
+        for index in 0..num_annotations as usize {
+            let annotation = read_annotation(reader).map_err(|err| {
+                err.within(Context::Entry {
+                    table: "annotation",
+                    index,
+                })
+            })?;
             annotations.push(annotation);
         }
 
@@ -776,22 +927,31 @@ impl AttributeFactory for RuntimeVisibleAnnotationsAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RuntimeInvisibleAnnotationsAttributeFactory;
 
+impl NamedAttributeFactory for RuntimeInvisibleAnnotationsAttributeFactory {
+    type Attribute = RuntimeInvisibleAnnotationsInfo;
+}
+
 impl AttributeFactory for RuntimeInvisibleAnnotationsAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let num_annotations = reader.take::<u16>()?;
-        let mut annotations = Vec::with_capacity(num_annotations as usize);
-        for _ in 0..num_annotations {
-            let annotation = read_annotation(reader, pool)?;
+        let mut annotations = reader.allocate(num_annotations as usize)?;
+        for index in 0..num_annotations as usize {
+            let annotation = read_annotation(reader).map_err(|err| {
+                err.within(Context::Entry {
+                    table: "annotation",
+                    index,
+                })
+            })?;
             annotations.push(annotation);
         }
 
@@ -804,25 +964,29 @@ impl AttributeFactory for RuntimeInvisibleAnnotationsAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RuntimeVisibleParameterAnnotationsAttributeFactory;
 
+impl NamedAttributeFactory for RuntimeVisibleParameterAnnotationsAttributeFactory {
+    type Attribute = RuntimeVisibleParameterAnnotationsInfo;
+}
+
 impl AttributeFactory for RuntimeVisibleParameterAnnotationsAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let num_parameters = reader.take::<u8>()?;
-        let mut annotations = Vec::with_capacity(num_parameters as usize);
+        let mut annotations = reader.allocate(num_parameters as usize)?;
         for _ in 0..num_parameters {
             let num_annotations = reader.take::<u16>()?;
-            let mut parameter_annotations = Vec::with_capacity(num_annotations as usize);
+            let mut parameter_annotations = reader.allocate(num_annotations as usize)?;
             for _ in 0..num_annotations {
-                let annotation = read_annotation(reader, pool)?;
+                let annotation = read_annotation(reader)?;
                 parameter_annotations.push(annotation);
             }
             annotations.push(ParameterAnnotation {
@@ -840,25 +1004,29 @@ impl AttributeFactory for RuntimeVisibleParameterAnnotationsAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RuntimeInvisibleParameterAnnotationsAttributeFactory;
 
+impl NamedAttributeFactory for RuntimeInvisibleParameterAnnotationsAttributeFactory {
+    type Attribute = RuntimeInvisibleParameterAnnotationsInfo;
+}
+
 impl AttributeFactory for RuntimeInvisibleParameterAnnotationsAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let num_parameters = reader.take::<u8>()?;
-        let mut annotations = Vec::with_capacity(num_parameters as usize);
+        let mut annotations = reader.allocate(num_parameters as usize)?;
         for _ in 0..num_parameters {
             let num_annotations = reader.take::<u16>()?;
-            let mut parameter_annotations = Vec::with_capacity(num_annotations as usize);
+            let mut parameter_annotations = reader.allocate(num_annotations as usize)?;
             for _ in 0..num_annotations {
-                let annotation = read_annotation(reader, pool)?;
+                let annotation = read_annotation(reader)?;
                 parameter_annotations.push(annotation);
             }
             annotations.push(ParameterAnnotation {
@@ -876,20 +1044,24 @@ impl AttributeFactory for RuntimeInvisibleParameterAnnotationsAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RuntimeVisibleTypeAnnotationsAttributeFactory;
 
+impl NamedAttributeFactory for RuntimeVisibleTypeAnnotationsAttributeFactory {
+    type Attribute = RuntimeVisibleTypeAnnotationsInfo;
+}
+
 impl AttributeFactory for RuntimeVisibleTypeAnnotationsAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let num_annotations = reader.take::<u16>()?;
-        let mut annotations = Vec::with_capacity(num_annotations as usize);
+        let mut annotations = reader.allocate(num_annotations as usize)?;
         for _ in 0..num_annotations {
             let annotation = read_typeannotation(reader, pool)?;
             annotations.push(annotation);
@@ -904,20 +1076,24 @@ impl AttributeFactory for RuntimeVisibleTypeAnnotationsAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RuntimeInvisibleTypeAnnotationsAttributeFactory;
 
+impl NamedAttributeFactory for RuntimeInvisibleTypeAnnotationsAttributeFactory {
+    type Attribute = RuntimeInvisibleTypeAnnotationsInfo;
+}
+
 impl AttributeFactory for RuntimeInvisibleTypeAnnotationsAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let num_annotations = reader.take::<u16>()?;
-        let mut annotations = Vec::with_capacity(num_annotations as usize);
+        let mut annotations = reader.allocate(num_annotations as usize)?;
         for _ in 0..num_annotations {
             let annotation = read_typeannotation(reader, pool)?;
             annotations.push(annotation);
@@ -932,24 +1108,28 @@ impl AttributeFactory for RuntimeInvisibleTypeAnnotationsAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct BootstrapMethodsAttributeFactory;
 
+impl NamedAttributeFactory for BootstrapMethodsAttributeFactory {
+    type Attribute = BootstrapMethodsInfo;
+}
+
 impl AttributeFactory for BootstrapMethodsAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let num_bootstrap_methods = reader.take::<u16>()?;
-        let mut bootstrap_methods = Vec::with_capacity(num_bootstrap_methods as usize);
+        let mut bootstrap_methods = reader.allocate(num_bootstrap_methods as usize)?;
         for _ in 0..num_bootstrap_methods {
             let bootstrap_method_ref = reader.take::<u16>()?;
             let num_bootstrap_arguments = reader.take::<u16>()?;
-            let mut bootstrap_arguments = Vec::with_capacity(num_bootstrap_arguments as usize);
+            let mut bootstrap_arguments = reader.allocate(num_bootstrap_arguments as usize)?;
             for _ in 0..num_bootstrap_arguments {
                 let bootstrap_argument = reader.take::<u16>()?;
                 bootstrap_arguments.push(ConstantPoolIndex::new(bootstrap_argument));
@@ -970,19 +1150,23 @@ impl AttributeFactory for BootstrapMethodsAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct AnnotationDefaultAttributeFactory;
 
+impl NamedAttributeFactory for AnnotationDefaultAttributeFactory {
+    type Attribute = AnnotationDefaultInfo;
+}
+
 impl AttributeFactory for AnnotationDefaultAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
-        let default_value = read_elementvalue(reader, pool)?;
+        let default_value = read_elementvalue(reader)?;
 
         Ok(Box::new(AnnotationDefaultInfo {
             attribute_name_index: ConstantPoolIndex::new(attribute_name_index),
@@ -992,20 +1176,24 @@ impl AttributeFactory for AnnotationDefaultAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct MethodParametersAttributeFactory;
 
+impl NamedAttributeFactory for MethodParametersAttributeFactory {
+    type Attribute = MethodParametersInfo;
+}
+
 impl AttributeFactory for MethodParametersAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let parameters_count = reader.take::<u8>()?;
-        let mut parameters = Vec::with_capacity(parameters_count as usize);
+        let mut parameters = reader.allocate(parameters_count as usize)?;
         for _ in 0..parameters_count {
             let name_index = reader.take::<u16>()?;
             let access_flags = reader.take::<u16>()?;
@@ -1024,23 +1212,27 @@ impl AttributeFactory for MethodParametersAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ModuleAttributeFactory;
 
+impl NamedAttributeFactory for ModuleAttributeFactory {
+    type Attribute = ModuleInfo;
+}
+
 impl AttributeFactory for ModuleAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let name_index = reader.take::<u16>()?;
         let access_flags = reader.take::<u16>()?;
         let version_index = reader.take::<u16>()?;
         let requires_count = reader.take::<u16>()?;
-        let mut requires = Vec::with_capacity(requires_count as usize);
+        let mut requires = reader.allocate(requires_count as usize)?;
         for _ in 0..requires_count {
             let requires_index = reader.take::<u16>()?;
             let requires_flags = reader.take::<u16>()?;
@@ -1053,12 +1245,12 @@ impl AttributeFactory for ModuleAttributeFactory {
         }
 
         let exports_count = reader.take::<u16>()?;
-        let mut exports = Vec::with_capacity(exports_count as usize);
+        let mut exports = reader.allocate(exports_count as usize)?;
         for _ in 0..exports_count {
             let exports_index = reader.take::<u16>()?;
             let exports_flags = reader.take::<u16>()?;
             let exports_to_count = reader.take::<u16>()?;
-            let mut indices = Vec::with_capacity(exports_to_count as usize);
+            let mut indices = reader.allocate(exports_to_count as usize)?;
             for _ in 0..exports_to_count {
                 let result = reader.take::<u16>()?;
                 indices.push(ConstantPoolIndex::new(result));
@@ -1072,12 +1264,12 @@ impl AttributeFactory for ModuleAttributeFactory {
         }
 
         let opens_count = reader.take::<u16>()?;
-        let mut opens = Vec::with_capacity(opens_count as usize);
+        let mut opens = reader.allocate(opens_count as usize)?;
         for _ in 0..opens_count {
             let opens_index = reader.take::<u16>()?;
             let opens_flags = reader.take::<u16>()?;
             let opens_to_count = reader.take::<u16>()?;
-            let mut indices = Vec::with_capacity(opens_to_count as usize);
+            let mut indices = reader.allocate(opens_to_count as usize)?;
             for _ in 0..opens_to_count {
                 let result = reader.take::<u16>()?;
                 indices.push(ConstantPoolIndex::new(result));
@@ -1091,18 +1283,18 @@ impl AttributeFactory for ModuleAttributeFactory {
         }
 
         let uses_count = reader.take::<u16>()?;
-        let mut uses_index = Vec::with_capacity(uses_count as usize);
+        let mut uses_index = reader.allocate(uses_count as usize)?;
         for _ in 0..uses_count {
             let result = reader.take::<u16>()?;
             uses_index.push(ConstantPoolIndex::new(result));
         }
 
         let provides_count = reader.take::<u16>()?;
-        let mut provides = Vec::with_capacity(provides_count as usize);
+        let mut provides = reader.allocate(provides_count as usize)?;
         for _ in 0..provides_count {
             let provides_index = reader.take::<u16>()?;
             let provides_with_count = reader.take::<u16>()?;
-            let mut indices = Vec::with_capacity(provides_with_count as usize);
+            let mut indices = reader.allocate(provides_with_count as usize)?;
             for _ in 0..provides_with_count {
                 let result = reader.take::<u16>()?;
                 indices.push(ConstantPoolIndex::new(result));
@@ -1134,20 +1326,24 @@ impl AttributeFactory for ModuleAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ModulePackagesAttributeFactory;
 
+impl NamedAttributeFactory for ModulePackagesAttributeFactory {
+    type Attribute = ModulePackagesInfo;
+}
+
 impl AttributeFactory for ModulePackagesAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let package_count = reader.take::<u16>()?;
-        let mut package_index = Vec::with_capacity(package_count as usize);
+        let mut package_index = reader.allocate(package_count as usize)?;
         for _ in 0..package_count {
             let result = reader.take::<u16>()?;
             package_index.push(ConstantPoolIndex::new(result));
@@ -1162,16 +1358,20 @@ impl AttributeFactory for ModulePackagesAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ModuleMainClassAttributeFactory;
 
+impl NamedAttributeFactory for ModuleMainClassAttributeFactory {
+    type Attribute = ModuleMainClassInfo;
+}
+
 impl AttributeFactory for ModuleMainClassAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let main_class_index = reader.take::<u16>()?;
@@ -1184,16 +1384,20 @@ impl AttributeFactory for ModuleMainClassAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct NestHostAttributeFactory;
 
+impl NamedAttributeFactory for NestHostAttributeFactory {
+    type Attribute = NestHostInfo;
+}
+
 impl AttributeFactory for NestHostAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let host_class_index = reader.take::<u16>()?;
@@ -1206,20 +1410,24 @@ impl AttributeFactory for NestHostAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct NestMembersAttributeFactory;
 
+impl NamedAttributeFactory for NestMembersAttributeFactory {
+    type Attribute = NestMembersInfo;
+}
+
 impl AttributeFactory for NestMembersAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        _pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let number_of_classes = reader.take::<u16>()?;
-        let mut classes = Vec::with_capacity(number_of_classes as usize);
+        let mut classes = reader.allocate(number_of_classes as usize)?;
         for _ in 0..number_of_classes {
             let idx = reader.take::<u16>()?;
             classes.push(ConstantPoolIndex::new(idx));
@@ -1234,54 +1442,116 @@ impl AttributeFactory for NestMembersAttributeFactory {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RecordAttributeFactory;
 
+impl NamedAttributeFactory for RecordAttributeFactory {
+    type Attribute = RecordInfo;
+}
+
 impl AttributeFactory for RecordAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        pool: &dyn ConstantPoolView,
+        container: &Container,
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
+        self.make_with(reader, pool, container, &mut ParseOptions::default())
+    }
+
+    fn make_with(
+        &self,
+        reader: &mut BufferedReader,
+        pool: &dyn ConstantPoolView,
         container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+        options: &mut ParseOptions,
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let component_count = reader.take::<u16>()?;
-        let mut attributes = Vec::with_capacity(component_count as usize);
-        for _ in 0..component_count {
-            let attr = read_attribute(reader, pool, container)?;
-            attributes.push(attr);
+        let mut components = reader.allocate(component_count as usize)?;
+        for index in 0..component_count as usize {
+            let context = || Context::Entry {
+                table: "record component",
+                index,
+            };
+            let start = options.diagnostics().len();
+            let component = read_record_component(reader, pool, container, options)
+                .map_err(|err| err.within(context()))?;
+            options.within(start, context());
+            components.push(component);
         }
 
         Ok(Box::new(RecordInfo {
             attribute_name_index: ConstantPoolIndex::new(attribute_name_index),
             attribute_length,
             component_count,
-            components: attributes,
+            components,
         }))
     }
 }
 
-#[derive(Debug)]
+fn read_record_component(
+    reader: &mut BufferedReader,
+    pool: &dyn ConstantPoolView,
+    container: &Container,
+    options: &mut ParseOptions,
+) -> Result<RecordComponent, Error> {
+    let name_index = reader.take::<u16>()?;
+    let name = pool
+        .utf8_at(name_index.into())
+        .map_err(|kind| reader.error(kind))?
+        .into_owned();
+    reader.reserve(name.len())?;
+
+    let descriptor_index = reader.take::<u16>()?;
+    let descriptor = pool
+        .utf8_at(descriptor_index.into())
+        .map_err(|kind| reader.error(kind))?
+        .into_owned();
+    reader.reserve(descriptor.len())?;
+    let descriptor = match Descriptor::parse_from_field(descriptor) {
+        Ok(descriptor) => Some(descriptor.ty),
+        Err(kind) => {
+            options.recover(reader.error(kind))?;
+            None
+        }
+    };
+
+    let attributes_count = reader.take::<u16>()?;
+    let attributes = read_attributes_with(reader, pool, container, options, attributes_count)?;
+
+    Ok(RecordComponent {
+        name_index: ConstantPoolIndex::new(name_index),
+        name,
+        descriptor_index: ConstantPoolIndex::new(descriptor_index),
+        descriptor,
+        attributes_count,
+        attributes,
+    })
+}
+
+#[derive(Debug, Default)]
 pub struct PermittedSubtypesAttributeFactory;
 
+impl NamedAttributeFactory for PermittedSubtypesAttributeFactory {
+    type Attribute = PermittedSubtypesInfo;
+}
+
 impl AttributeFactory for PermittedSubtypesAttributeFactory {
     fn make(
         &self,
         reader: &mut BufferedReader,
-        pool: &mut ConstantPool,
+        _pool: &dyn ConstantPoolView,
         _container: &Container,
-    ) -> Result<Box<dyn AnyAttribute>, BytecodeError> {
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
         let attribute_name_index = reader.take::<u16>()?;
         let attribute_length = reader.take::<u32>()?;
         let number_of_classes = reader.take::<u16>()?;
-        let mut classes = Vec::with_capacity(number_of_classes as usize);
+        let mut classes = reader.allocate(number_of_classes as usize)?;
         for _ in 0..number_of_classes {
             let class_index = reader.take::<u16>()?;
-            let Some(class) = pool.text_of(class_index.into()) else {
-                return Err(BytecodeError::InvalidData);
-            };
-            classes.push(class);
+            classes.push(ConstantPoolIndex::new(class_index));
         }
 
         Ok(Box::new(PermittedSubtypesInfo {
@@ -1293,12 +1563,43 @@ impl AttributeFactory for PermittedSubtypesAttributeFactory {
     }
 }
 
+/// Reads any attribute as [RawAttribute] without interpreting its payload. This factory is used
+/// for unknown attributes if the [Container] is configured with [UnknownAttributePolicy::KeepRaw],
+/// but it can also be registered explicitly for attributes that should be kept as they are.
+#[derive(Debug)]
+pub struct RawAttributeFactory;
+
+impl AttributeFactory for RawAttributeFactory {
+    fn make(
+        &self,
+        reader: &mut BufferedReader,
+        pool: &dyn ConstantPoolView,
+        _container: &Container,
+    ) -> Result<Box<dyn AnyAttribute>, Error> {
+        let attribute_name_index = reader.take::<u16>()?;
+        let attribute_length = reader.take::<u32>()?;
+        let name = pool
+            .utf8_at(attribute_name_index.into())
+            .map_err(|kind| reader.error(kind))?
+            .into_owned();
+        let bytes = reader.take_bytes(attribute_length as usize)?;
+        reader.reserve(bytes.len())?;
+
+        Ok(Box::new(RawAttribute {
+            attribute_name_index: ConstantPoolIndex::new(attribute_name_index),
+            attribute_length,
+            name,
+            bytes: bytes.to_vec(),
+        }))
+    }
+}
+
 fn read_typepath(
     reader: &mut BufferedReader,
-    _cp: &mut ConstantPool,
-) -> Result<TypePath, BytecodeError> {
+    _cp: &dyn ConstantPoolView,
+) -> Result<TypePath, Error> {
     let path_length = reader.take::<u8>()?;
-    let mut path = Vec::with_capacity(path_length as usize);
+    let mut path = reader.allocate(path_length as usize)?;
     for _ in 0..path_length {
         let type_path_kind = reader.take::<u8>()?;
         let type_argument_index = reader.take::<u8>()?;
@@ -1311,16 +1612,21 @@ fn read_typepath(
     Ok(TypePath { path_length, path })
 }
 
-#[allow(clippy::only_used_in_recursion)]
-fn read_elementvalue(
-    reader: &mut BufferedReader,
-    cp: &mut ConstantPool,
-) -> Result<ElementValue, BytecodeError> {
+/// Reads an element value one nesting level deeper than the enclosing structure, as element values
+/// can contain annotations and arrays of element values themselves.
+fn read_elementvalue(reader: &mut BufferedReader) -> Result<ElementValue, Error> {
+    reader.nested(read_nested_elementvalue)
+}
+
+fn read_nested_elementvalue(reader: &mut BufferedReader) -> Result<ElementValue, Error> {
     let tag = reader.take::<u8>()?;
     let value = match tag {
         b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' => {
             let const_value_index = reader.take::<u16>()?;
-            ElementValue::ConstValueIndex(ConstantPoolIndex::new(const_value_index))
+            ElementValue::ConstValueIndex {
+                tag,
+                const_value_index: ConstantPoolIndex::new(const_value_index),
+            }
         }
         b'e' => {
             let type_name_index = reader.take::<u16>()?;
@@ -1337,10 +1643,10 @@ fn read_elementvalue(
         b'@' => {
             let type_index = reader.take::<u16>()?;
             let num_element_value_pairs = reader.take::<u16>()?;
-            let mut element_value_pairs = Vec::with_capacity(num_element_value_pairs as usize);
+            let mut element_value_pairs = reader.allocate(num_element_value_pairs as usize)?;
             for _ in 0..num_element_value_pairs {
                 let element_name_index = reader.take::<u16>()?;
-                let element_value = read_elementvalue(reader, cp)?;
+                let element_value = read_elementvalue(reader)?;
                 element_value_pairs.push(ElementValuePair {
                     element_name_index: ConstantPoolIndex::new(element_name_index),
                     value: element_value,
@@ -1354,15 +1660,374 @@ fn read_elementvalue(
         }
         b'[' => {
             let num_values = reader.take::<u16>()?;
-            let mut values = Vec::with_capacity(num_values as usize);
+            let mut values = reader.allocate(num_values as usize)?;
             for _ in 0..num_values {
-                let value = read_elementvalue(reader, cp)?;
+                let value = read_elementvalue(reader)?;
                 values.push(value);
             }
             ElementValue::Array { num_values, values }
         }
-        _ => return Err(BytecodeError::InvalidData),
+        _ => {
+            return Err(reader.error(BytecodeError::InvalidTag {
+                kind: "element value",
+                found: tag,
+            }))
+        }
     };
 
     Ok(value)
 }
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::attributes::{
+        AnyAttribute, AttributeFactory, CodeInfo, Container, ElementValue, PermittedSubtypesInfo,
+        RawAttribute, RecordInfo, RuntimeVisibleAnnotationsInfo, RuntimeVisibleTypeAnnotationsInfo,
+        SignatureInfo, SourceDebugExtensionInfo, SourceFileInfo, TypeAnnotationTargetInfoType,
+        UnknownAttributePolicy,
+    };
+    use crate::bytecode::pool::{ConstantPool, ConstantPoolIndex, ConstantPoolView};
+    use crate::bytecode::reader::attributes::{
+        PermittedSubtypesAttributeFactory, RuntimeVisibleAnnotationsAttributeFactory,
+        RuntimeVisibleTypeAnnotationsAttributeFactory, SourceDebugExtensionAttributeFactory,
+    };
+    use crate::bytecode::reader::containers::{read_classfile, read_classfile_with};
+    use crate::bytecode::reader::options::ParseOptions;
+    use crate::bytecode::reader::BufferedReader;
+    use crate::bytecode::tests::{Layout, POINT, SIMPLE};
+    use crate::bytecode::writer::attributes::write_attribute;
+    use crate::bytecode::writer::BufferedWriter;
+    use crate::bytecode::{BaseType, BytecodeError, ClassFile, FieldType};
+    use crate::error::{Context, Error};
+
+    /// A broken `SourceFile` factory, which reads the given number of bytes after the attribute
+    /// header instead of the two bytes of `sourcefile_index`.
+    #[derive(Debug)]
+    struct SourceFileFactory(usize);
+
+    impl AttributeFactory for SourceFileFactory {
+        fn make(
+            &self,
+            reader: &mut BufferedReader,
+            _pool: &dyn ConstantPoolView,
+            _container: &Container,
+        ) -> Result<Box<dyn AnyAttribute>, Error> {
+            let attribute_name_index = reader.take::<u16>()?;
+            let attribute_length = reader.take::<u32>()?;
+            reader.take_bytes(self.0)?;
+
+            Ok(Box::new(SourceFileInfo {
+                attribute_name_index: ConstantPoolIndex::new(attribute_name_index),
+                attribute_length,
+                sourcefile_index: ConstantPoolIndex::new(0usize),
+            }))
+        }
+    }
+
+    fn container(consumed: usize) -> Container {
+        let mut container = Container::standard();
+        container.register_as("SourceFile", SourceFileFactory(consumed));
+        container
+    }
+
+    #[test]
+    fn test_attribute_length_is_enforced() {
+        let mut reader = BufferedReader::new(SIMPLE);
+        let err = read_classfile(&mut reader, &container(0)).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &BytecodeError::AttributeLengthMismatch {
+                expected: 2,
+                found: 0
+            }
+        );
+        assert_eq!(
+            err.context(),
+            [Context::Class, Context::Attribute("SourceFile".to_string())]
+        );
+
+        let mut reader = BufferedReader::new(SIMPLE);
+        let err = read_classfile(&mut reader, &container(4)).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &BytecodeError::UnexpectedEndOfData {
+                expected: 4,
+                found: 2
+            }
+        );
+        assert_eq!(
+            err.context(),
+            [Context::Class, Context::Attribute("SourceFile".to_string())]
+        );
+
+        // In lenient mode, the broken attribute is skipped and reading continues after it.
+        let mut reader = BufferedReader::new(SIMPLE);
+        let mut options = ParseOptions::lenient();
+        let cf = read_classfile_with(&mut reader, &container(0), &mut options).unwrap();
+        assert!(!cf.attributes.contains("SourceFile"));
+        assert!(cf.attributes.contains("NestMembers"));
+        assert!(cf.attributes.contains("InnerClasses"));
+        assert_eq!(options.diagnostics().len(), 1);
+        assert!(!reader.has_remaining_data());
+    }
+
+    #[test]
+    fn test_lenient_nested_attributes() {
+        let mut input = SIMPLE.to_vec();
+        // The `LineNumberTable` in the `Code` of `<init>` claims a second entry it does not have.
+        let table = Layout::of(SIMPLE).code_attribute("<init>", "LineNumberTable");
+        input[table + 6..table + 8].copy_from_slice(&[0x00, 0x02]);
+
+        let mut reader = BufferedReader::new(&input);
+        let err = read_classfile(&mut reader, &Container::standard()).unwrap_err();
+        assert_eq!(
+            err.context(),
+            [
+                Context::Class,
+                Context::Method("<init>".to_string()),
+                Context::Attribute("Code".to_string()),
+                Context::Attribute("LineNumberTable".to_string())
+            ]
+        );
+
+        // In lenient mode, only the nested attribute is skipped and the code is kept.
+        let mut reader = BufferedReader::new(&input);
+        let mut options = ParseOptions::lenient();
+        let cf = read_classfile_with(&mut reader, &Container::standard(), &mut options).unwrap();
+        let code = cf.methods[0].attribute::<CodeInfo>().unwrap();
+        assert!(!code.attributes.contains("LineNumberTable"));
+        assert_eq!(code.code.len(), 5);
+        let diagnostics = options.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].error(), &err);
+    }
+
+    #[test]
+    fn test_unknown_attribute_policy() {
+        let mut container = Container::new();
+
+        let mut reader = BufferedReader::new(SIMPLE);
+        let ret = read_classfile(&mut reader, &container);
+        let err = ret.unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &BytecodeError::UnsupportedAttributeName("Code".to_string())
+        );
+        assert_eq!(
+            err.to_string(),
+            "unsupported attribute `Code` at offset 649 in class > method `<init>`"
+        );
+
+        container.set_unknown_attribute_policy(UnknownAttributePolicy::Skip);
+        let mut reader = BufferedReader::new(SIMPLE);
+        let cf = read_classfile(&mut reader, &container).unwrap();
+        assert_eq!(cf.methods.len(), 4);
+        assert!(cf.methods.iter().all(|m| m.attributes.is_empty()));
+        assert!(cf.attributes.is_empty());
+
+        container.set_unknown_attribute_policy(UnknownAttributePolicy::KeepRaw);
+        let mut reader = BufferedReader::new(SIMPLE);
+        let cf = read_classfile(&mut reader, &container).unwrap();
+        let code = cf.methods[0]
+            .attributes
+            .values()
+            .next()
+            .and_then(|attr| attr.as_any_ref().downcast_ref::<RawAttribute>())
+            .unwrap();
+        assert_eq!(code.name, "Code");
+        assert_eq!(code.bytes.len(), code.attribute_length as usize);
+
+        let mut writer = BufferedWriter::new();
+        write_attribute(&mut writer, code).unwrap();
+        assert_eq!(writer.size(), code.bytes.len() + 6);
+        assert_eq!(&writer.as_bytes()[6..], code.bytes.as_slice());
+    }
+
+    #[test]
+    fn test_record_components() {
+        let cf = ClassFile::parse(POINT).unwrap();
+        let cp = &cf.constant_pool;
+        let record = cf.attribute::<RecordInfo>().unwrap();
+
+        let components = record
+            .components
+            .iter()
+            .map(|component| (component.name.as_str(), component.descriptor.as_ref()))
+            .collect::<Vec<_>>();
+        let object = |name: &str| FieldType::Object(name.to_string());
+        assert_eq!(
+            components,
+            [
+                ("x", Some(&FieldType::Base(BaseType::Int))),
+                ("y", Some(&FieldType::Base(BaseType::Long))),
+                ("label", Some(&object("java/lang/Object"))),
+                ("tags", Some(&object("java/util/List"))),
+            ]
+        );
+
+        let annotations = record.components[1]
+            .attribute::<RuntimeVisibleAnnotationsInfo>()
+            .unwrap();
+        let annotation = cp.text_of(annotations.annotations[0].type_index).unwrap();
+        assert_eq!(annotation, "Lorg/example/Point$Unit;");
+
+        let signature = record.components[3].attribute::<SignatureInfo>().unwrap();
+        let signature = cp.text_of(signature.signature_index).unwrap();
+        assert_eq!(signature, "Ljava/util/List<Ljava/lang/String;>;");
+    }
+
+    #[test]
+    fn test_lenient_record_components() {
+        let mut input = POINT.to_vec();
+        // The descriptor of the component `y` refers to its name and its annotation claims an
+        // element value pair it does not have.
+        let layout = Layout::of(POINT);
+        let y = layout.record_component("y");
+        input.copy_within(y..y + 2, y + 2);
+        // The attribute header, `num_annotations` and `type_index` precede the number of pairs.
+        let annotation = layout.record_component_attribute("y", "RuntimeVisibleAnnotations");
+        input[annotation + 10..annotation + 12].copy_from_slice(&[0x00, 0x01]);
+
+        let mut reader = BufferedReader::new(&input);
+        let mut options = ParseOptions::lenient();
+        let cf = read_classfile_with(&mut reader, &Container::standard(), &mut options).unwrap();
+        let record = cf.attribute::<RecordInfo>().unwrap();
+        let y = &record.components[1];
+        assert_eq!(y.descriptor, None);
+        assert!(y.attributes.is_empty());
+        assert_eq!(record.components[2].attributes.len(), 1);
+
+        let diagnostics = options.take_diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        let context = [
+            Context::Class,
+            Context::Attribute("Record".to_string()),
+            Context::Entry {
+                table: "record component",
+                index: 1,
+            },
+        ];
+        assert_eq!(
+            diagnostics[0].error().kind(),
+            &BytecodeError::InvalidDescriptor {
+                descriptor: "y".to_string(),
+                position: 0
+            }
+        );
+        assert_eq!(diagnostics[0].error().context(), context);
+        let mut annotation = context.to_vec();
+        annotation.push(Context::Attribute("RuntimeVisibleAnnotations".to_string()));
+        assert!(diagnostics[1].error().context().starts_with(&annotation));
+    }
+
+    /// Reads an attribute with the given `body` with `factory` and checks that the whole
+    /// attribute has been consumed.
+    fn make(factory: impl AttributeFactory, body: &[u8]) -> Box<dyn AnyAttribute> {
+        let mut bytes = vec![0, 1];
+        bytes.extend((body.len() as u32).to_be_bytes());
+        bytes.extend(body);
+        let mut reader = BufferedReader::new(&bytes);
+        let attribute = factory
+            .make(&mut reader, &ConstantPool::new(), &Container::new())
+            .unwrap();
+        assert_eq!(reader.position(), bytes.len());
+        attribute
+    }
+
+    #[test]
+    fn test_type_annotation_targets() {
+        let annotation = |target: &[u8]| [target, &[0, 0, 9, 0, 0]].concat();
+        let body = [
+            &[0, 3][..],
+            // type_parameter_target with `type_parameter_index` 1
+            &annotation(&[0x00, 1]),
+            // type_parameter_bound_target with `type_parameter_index` 2 and `bound_index` 3
+            &annotation(&[0x11, 2, 3]),
+            // formal_parameter_target with `formal_parameter_index` 4
+            &annotation(&[0x16, 4]),
+        ]
+        .concat();
+        let attribute = make(RuntimeVisibleTypeAnnotationsAttributeFactory, &body);
+        let attribute = attribute
+            .as_any_ref()
+            .downcast_ref::<RuntimeVisibleTypeAnnotationsInfo>()
+            .unwrap();
+
+        let targets = attribute
+            .annotations
+            .iter()
+            .map(|annotation| {
+                assert_eq!(annotation.type_index, ConstantPoolIndex::new(9u16));
+                (annotation.target_type, &annotation.target_info.target_info)
+            })
+            .collect::<Vec<_>>();
+        let index = |index: u16| ConstantPoolIndex::new(index);
+        assert_eq!(
+            targets,
+            [
+                (
+                    0x00,
+                    &TypeAnnotationTargetInfoType::TypeParameter {
+                        type_parameter_index: index(1)
+                    }
+                ),
+                (
+                    0x11,
+                    &TypeAnnotationTargetInfoType::TypeParameterBound {
+                        type_parameter_index: index(2),
+                        bound_index: index(3)
+                    }
+                ),
+                (
+                    0x16,
+                    &TypeAnnotationTargetInfoType::FormalParameter {
+                        formal_parameter_index: index(4)
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_source_debug_extension() {
+        let attribute = make(SourceDebugExtensionAttributeFactory, b"SMAP");
+        let attribute = attribute
+            .as_any_ref()
+            .downcast_ref::<SourceDebugExtensionInfo>()
+            .unwrap();
+        assert_eq!(attribute.debug_extension, b"SMAP");
+    }
+
+    #[test]
+    fn test_permitted_subtypes() {
+        // The classes are kept as indices, even if they are missing from the pool.
+        let body = [0, 2, 0, 7, 0, 9];
+        let attribute = make(PermittedSubtypesAttributeFactory, &body);
+        let attribute = attribute
+            .as_any_ref()
+            .downcast_ref::<PermittedSubtypesInfo>()
+            .unwrap();
+        assert_eq!(
+            attribute.classes,
+            [ConstantPoolIndex::new(7u16), ConstantPoolIndex::new(9u16)]
+        );
+    }
+
+    #[test]
+    fn test_element_value_tag() {
+        // One annotation of type #1 with the element #2 = int constant #3.
+        let body = [0, 1, 0, 1, 0, 1, 0, 2, b'I', 0, 3];
+        let attribute = make(RuntimeVisibleAnnotationsAttributeFactory, &body);
+        let attribute = attribute
+            .as_any_ref()
+            .downcast_ref::<RuntimeVisibleAnnotationsInfo>()
+            .unwrap();
+        assert_eq!(
+            attribute.annotations[0].element_value_pairs[0].value,
+            ElementValue::ConstValueIndex {
+                tag: b'I',
+                const_value_index: ConstantPoolIndex::new(3u16),
+            }
+        );
+    }
+}
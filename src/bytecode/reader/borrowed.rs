@@ -0,0 +1,805 @@
+use std::borrow::Cow;
+use std::sync::{Arc, OnceLock};
+
+use crate::bytecode::attributes::{Annotation, AnyAttribute, Attributes, Container};
+use crate::bytecode::borrowed::{
+    Annotations, Attribute, ClassFile, Code, Constant, ConstantPool, Field, Method, Shared,
+};
+use crate::bytecode::flags::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
+use crate::bytecode::pool::{self, ConstantPoolIndex, ConstantTag};
+use crate::bytecode::reader::attributes::{
+    attribute_reader, read_annotation, read_bounded_attribute_with, read_exception_table_entry,
+};
+use crate::bytecode::reader::constants::{read_constant_pool_entry, read_utf8_bytes};
+use crate::bytecode::reader::options::{Diagnostic, ParseMode, ParseOptions};
+use crate::bytecode::reader::BufferedReader;
+use crate::bytecode::{
+    self, BytecodeError, ClassFileVersion, Descriptor, Interface, MethodDescriptor,
+};
+use crate::error::{Context, Error};
+
+/// Reads a class file into its [borrowed](crate::bytecode::borrowed) representation. The
+/// attributes are not decoded, but only located, and the `container` is used when they are
+/// accessed. In lenient mode, the diagnostics are collected in the class file, see
+/// [ClassFile::diagnostics].
+pub fn read_borrowed_classfile<'a>(
+    reader: &mut BufferedReader<'a>,
+    container: &'a Container,
+    mode: ParseMode,
+) -> Result<ClassFile<'a>, Error> {
+    let mut options = ParseOptions::new(mode);
+    let cf = read_borrowed_classfile_contents(reader, container, &mut options)
+        .map_err(|err| err.within(Context::Class))?;
+    options.within(0, Context::Class);
+    cf.shared.record(options.take_diagnostics());
+    Ok(cf)
+}
+
+fn read_borrowed_classfile_contents<'a>(
+    reader: &mut BufferedReader<'a>,
+    container: &'a Container,
+    options: &mut ParseOptions,
+) -> Result<ClassFile<'a>, Error> {
+    let magic_number = reader.take::<u32>()?;
+    if magic_number != 0xCAFEBABE {
+        return Err(reader.error(BytecodeError::InvalidMagicNumber(magic_number)));
+    }
+
+    let minor = reader.take::<u16>()?;
+    let major = reader.take::<u16>()?;
+
+    let constant_pool_count = reader.take::<u16>()?;
+    let max_constant_pool_size = reader.limits().max_constant_pool_size;
+    if constant_pool_count.saturating_sub(1) as usize > max_constant_pool_size {
+        return Err(reader.error(BytecodeError::LimitExceeded {
+            limit: "constant pool size",
+            max: max_constant_pool_size,
+        }));
+    }
+    let mut constant_pool = ConstantPool::new();
+    let mut idx = 1;
+    while idx < constant_pool_count as usize {
+        let constant =
+            read_constant(reader).map_err(|err| err.within(Context::ConstantPoolEntry(idx)))?;
+        let wide = matches!(&constant, Constant::Entry(entry) if entry.is_wide());
+        let width = if wide { 2 } else { 1 };
+        if idx + width > constant_pool_count as usize {
+            // A long or double constant can't occupy the last slot of the pool.
+            return Err(reader.error(BytecodeError::WideConstantOutOfBounds(idx)));
+        }
+        constant_pool.push(idx, constant);
+        if wide {
+            constant_pool.push(idx + 1, Constant::Entry(pool::ConstantPoolEntry::Unusable));
+        }
+        idx += width;
+    }
+    let shared = Arc::new(Shared::new(container, constant_pool, options.mode()));
+
+    let access_flags = reader.take::<u16>()?;
+    let access_flags = match ClassAccessFlags::from_bits(access_flags) {
+        Some(flags) => flags,
+        None => {
+            options.recover(reader.error(BytecodeError::InvalidAccessFlags(access_flags)))?;
+            ClassAccessFlags::from_bits_retain(access_flags)
+        }
+    };
+
+    let this_class = ConstantPoolIndex::new(reader.take::<u16>()?);
+    let super_class = ConstantPoolIndex::new(reader.take::<u16>()?);
+
+    let interfaces_count = reader.take::<u16>()?;
+    let mut interfaces = reader.allocate(interfaces_count as usize)?;
+    for _ in 0..interfaces_count {
+        let name_index = reader.take::<u16>()?;
+        interfaces.push(Interface {
+            name_index: ConstantPoolIndex::new(name_index),
+        });
+    }
+
+    let fields_count = reader.take::<u16>()?;
+    let mut fields = reader.allocate(fields_count as usize)?;
+    for _ in 0..fields_count {
+        let (access_flags, member) = read_member(reader, &shared, Context::Field)?;
+        let access_flags = match FieldAccessFlags::from_bits(access_flags) {
+            Some(flags) => flags,
+            None => {
+                let err = Error::new(BytecodeError::InvalidAccessFlags(access_flags))
+                    .at(member.offset)
+                    .within(Context::Field(member.name.to_string()));
+                options.recover(err)?;
+                FieldAccessFlags::from_bits_retain(access_flags)
+            }
+        };
+        fields.push(Field {
+            access_flags,
+            name_index: member.name_index,
+            name: member.name,
+            descriptor_index: member.descriptor_index,
+            descriptor: member.descriptor,
+            attributes: member.attributes,
+        });
+    }
+
+    let methods_count = reader.take::<u16>()?;
+    let mut methods = reader.allocate(methods_count as usize)?;
+    for _ in 0..methods_count {
+        let (access_flags, member) = read_member(reader, &shared, Context::Method)?;
+        let access_flags = match MethodAccessFlags::from_bits(access_flags) {
+            Some(flags) => flags,
+            None => {
+                let err = Error::new(BytecodeError::InvalidAccessFlags(access_flags))
+                    .at(member.offset)
+                    .within(Context::Method(member.name.to_string()));
+                options.recover(err)?;
+                MethodAccessFlags::from_bits_retain(access_flags)
+            }
+        };
+        methods.push(Method {
+            access_flags,
+            name_index: member.name_index,
+            name: member.name,
+            descriptor_index: member.descriptor_index,
+            descriptor: member.descriptor,
+            attributes: member.attributes,
+            code: OnceLock::new(),
+        });
+    }
+
+    let attributes = read_attributes(reader, &shared)?;
+
+    Ok(ClassFile {
+        version: ClassFileVersion { minor, major },
+        access_flags,
+        this_class,
+        super_class,
+        interfaces,
+        fields,
+        methods,
+        attributes,
+        shared,
+    })
+}
+
+/// Reads a constant pool entry, borrowing the bytes of `CONSTANT_Utf8_info` entries.
+fn read_constant<'a>(reader: &mut BufferedReader<'a>) -> Result<Constant<'a>, Error> {
+    if ConstantTag::from_tag(reader.peek_bytes::<u8>()?) == Some(ConstantTag::Utf8) {
+        reader.take::<u8>()?;
+        return Ok(Constant::Utf8(read_utf8_bytes(reader)?));
+    }
+    let entry = read_constant_pool_entry(reader, &mut pool::ConstantPool::new())?;
+    Ok(Constant::Entry(entry))
+}
+
+/// The parts that fields and methods have in common.
+struct Member<'a> {
+    /// The offset of the member's access flags.
+    offset: usize,
+    name_index: ConstantPoolIndex,
+    name: Cow<'a, str>,
+    descriptor_index: ConstantPoolIndex,
+    descriptor: Cow<'a, str>,
+    attributes: Vec<Attribute<'a>>,
+}
+
+/// Reads a field or method and returns its unvalidated access flags together with the remaining
+/// parts. Errors in its attributes are reported within the `context` created from its name.
+fn read_member<'a>(
+    reader: &mut BufferedReader<'a>,
+    shared: &Arc<Shared<'a>>,
+    context: fn(String) -> Context,
+) -> Result<(u16, Member<'a>), Error> {
+    let cp = &shared.constant_pool;
+    let offset = reader.offset();
+    let access_flags = reader.take::<u16>()?;
+    let name_index = reader.take::<u16>()?;
+    let name = cp
+        .utf8_at(name_index.into())
+        .map_err(|kind| reader.error(kind))?;
+    let descriptor_index = reader.take::<u16>()?;
+    let descriptor = cp
+        .utf8_at(descriptor_index.into())
+        .map_err(|kind| reader.error(kind))?;
+    let attributes =
+        read_attributes(reader, shared).map_err(|err| err.within(context(name.to_string())))?;
+
+    let member = Member {
+        offset,
+        name_index: ConstantPoolIndex::new(name_index),
+        name,
+        descriptor_index: ConstantPoolIndex::new(descriptor_index),
+        descriptor,
+        attributes,
+    };
+    Ok((access_flags, member))
+}
+
+/// Reads an `attributes_count` followed by as many attributes without decoding them.
+fn read_attributes<'a>(
+    reader: &mut BufferedReader<'a>,
+    shared: &Arc<Shared<'a>>,
+) -> Result<Vec<Attribute<'a>>, Error> {
+    let cp = &shared.constant_pool;
+    let attributes_count = reader.take::<u16>()?;
+    let mut attributes = reader.allocate(attributes_count as usize)?;
+    for _ in 0..attributes_count {
+        let attribute = reader.nested(|reader| {
+            let name_index = reader.peek_bytes::<u16>()?;
+            let name = cp
+                .utf8_at(name_index.into())
+                .map_err(|kind| Error::new(kind).at(reader.offset()))?;
+            let attribute_reader = attribute_reader(reader)
+                .map_err(|err| err.within(Context::Attribute(name.to_string())))?;
+            let mut header = attribute_reader.clone();
+            header.take::<u16>()?;
+            let attribute_length = header.take::<u32>()?;
+            let info = header.take_bytes(attribute_length as usize)?;
+
+            Ok(Attribute {
+                name_index: ConstantPoolIndex::new(name_index),
+                name,
+                info,
+                reader: attribute_reader,
+                shared: Arc::clone(shared),
+                decoded: OnceLock::new(),
+            })
+        })?;
+        attributes.push(attribute);
+    }
+
+    Ok(attributes)
+}
+
+/// A lazily decoded attribute, or `None` if it has been skipped, together with the diagnostics
+/// recorded while decoding it.
+type Decoded<T> = (Option<T>, Vec<Diagnostic>);
+
+/// Reads the `Code` attribute of a method, see [Method::code]. In lenient mode, a malformed `Code` attribute is skipped, and the returned diagnostics explain
+/// why.
+pub(crate) fn decode_code<'a>(attribute: &Attribute<'a>) -> Result<Decoded<Code<'a>>, Error> {
+    let mut options = ParseOptions::new(attribute.shared.mode);
+    let code = match read_code(&mut attribute.reader.clone(), &attribute.shared) {
+        Ok(code) => Some(code),
+        Err(err) => {
+            options.recover(err)?;
+            None
+        }
+    };
+    Ok((code, options.take_diagnostics()))
+}
+
+/// Reads the `Code` attribute the `reader` is bounded to, borrowing its code and the bytes of its
+/// attributes.
+fn read_code<'a>(
+    reader: &mut BufferedReader<'a>,
+    shared: &Arc<Shared<'a>>,
+) -> Result<Code<'a>, Error> {
+    read_code_contents(reader, shared).map_err(|err| err.within(Context::Attribute("Code".into())))
+}
+
+fn read_code_contents<'a>(
+    reader: &mut BufferedReader<'a>,
+    shared: &Arc<Shared<'a>>,
+) -> Result<Code<'a>, Error> {
+    let _attribute_name_index = reader.take::<u16>()?;
+    let _attribute_length = reader.take::<u32>()?;
+    let max_stack = reader.take::<u16>()?;
+    let max_locals = reader.take::<u16>()?;
+    let code_length = reader.take::<u32>()?;
+    let max_code_length = reader.limits().max_code_length;
+    if code_length as usize > max_code_length {
+        return Err(reader.error(BytecodeError::LimitExceeded {
+            limit: "code length",
+            max: max_code_length,
+        }));
+    }
+    let code = reader.take_bytes(code_length as usize)?;
+    let exception_table_length = reader.take::<u16>()?;
+    let mut exception_table = reader.allocate(exception_table_length as usize)?;
+    for index in 0..exception_table_length as usize {
+        let entry = read_exception_table_entry(reader).map_err(|err| {
+            err.within(Context::Entry {
+                table: "exception table",
+                index,
+            })
+        })?;
+        exception_table.push(entry);
+    }
+    let attributes = read_attributes(reader, shared)?;
+
+    if reader.has_remaining_data() {
+        let kind = BytecodeError::AttributeLengthMismatch {
+            expected: reader.size() - 6,
+            found: reader.position() - 6,
+        };
+        return Err(Error::new(kind).at(reader.offset()));
+    }
+
+    Ok(Code {
+        max_stack,
+        max_locals,
+        code,
+        exception_table,
+        attributes,
+    })
+}
+
+/// Decodes the attribute with the factories registered in the container of its class file, see
+/// [Attribute::decode]. In lenient mode, an attribute that can't be decoded is skipped, and the
+/// returned diagnostics explain why.
+pub(crate) fn decode_attribute(
+    attribute: &Attribute,
+) -> Result<Decoded<Box<dyn AnyAttribute>>, Error> {
+    let shared = &attribute.shared;
+    let mut options = ParseOptions::new(shared.mode);
+    let decoded = match read_bounded_attribute_with(
+        &mut attribute.reader.clone(),
+        &shared.constant_pool,
+        shared.container,
+        &mut options,
+    ) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            options.recover(err)?;
+            None
+        }
+    };
+    Ok((decoded, options.take_diagnostics()))
+}
+
+/// Locates the annotations of a `RuntimeVisibleAnnotations` or `RuntimeInvisibleAnnotations`
+/// attribute, see [Attribute::annotations].
+pub(crate) fn read_annotations<'a>(attribute: &Attribute<'a>) -> Result<Annotations<'a>, Error> {
+    let mut reader = attribute.reader.clone();
+    let _attribute_name_index = reader.take::<u16>()?;
+    let _attribute_length = reader.take::<u32>()?;
+    let num_annotations = reader
+        .take::<u16>()
+        .map_err(|err| err.within(Context::Attribute(attribute.name.to_string())))?;
+    Ok(Annotations {
+        num_annotations,
+        name: attribute.name.clone(),
+        reader,
+        index: 0,
+    })
+}
+
+/// Decodes the next annotation from the bytes of the class file, see [Annotations].
+pub(crate) fn next_annotation(annotations: &mut Annotations) -> Option<Result<Annotation, Error>> {
+    let index = annotations.index;
+    if index >= annotations.num_annotations as usize {
+        return None;
+    }
+    annotations.index += 1;
+    let annotation = read_annotation(&mut annotations.reader).map_err(|err| {
+        // The annotations following a malformed one can't be located.
+        annotations.index = annotations.num_annotations as usize;
+        err.within(Context::Entry {
+            table: "annotation",
+            index,
+        })
+        .within(Context::Attribute(annotations.name.to_string()))
+    });
+    Some(annotation)
+}
+
+/// Converts the borrowed class file into an owned one, see [ClassFile::into_owned]. In lenient
+/// mode, the diagnostics of the conversion are collected in the class file.
+pub(crate) fn to_owned_classfile(cf: ClassFile) -> Result<bytecode::ClassFile, Error> {
+    let shared = Arc::clone(&cf.shared);
+    let mut options = ParseOptions::new(shared.mode);
+    let owned =
+        to_owned_classfile_contents(cf, &mut options).map_err(|err| err.within(Context::Class))?;
+    options.within(0, Context::Class);
+    shared.record(options.take_diagnostics());
+    Ok(owned)
+}
+
+fn to_owned_classfile_contents(
+    cf: ClassFile,
+    options: &mut ParseOptions,
+) -> Result<bytecode::ClassFile, Error> {
+    let constant_pool = cf.shared.constant_pool.clone().into_owned();
+    let constant_pool_count = (constant_pool.size() + 1) as u16;
+
+    let mut fields = Vec::with_capacity(cf.fields.len());
+    for field in cf.fields {
+        let context = || Context::Field(field.name.to_string());
+        let descriptor = match Descriptor::parse_from_field(field.descriptor.to_string()) {
+            Ok(descriptor) => Some(descriptor),
+            Err(kind) => {
+                options.recover(Error::new(kind).within(context()))?;
+                None
+            }
+        };
+        let attributes =
+            to_owned_attributes(field.attributes).map_err(|err| err.within(context()))?;
+        fields.push(bytecode::Field {
+            name_index: field.name_index,
+            name: field.name.into_owned(),
+            descriptor_index: field.descriptor_index,
+            descriptor,
+            access_flags: field.access_flags,
+            attributes,
+        });
+    }
+
+    let mut methods = Vec::with_capacity(cf.methods.len());
+    for method in cf.methods {
+        let context = || Context::Method(method.name.to_string());
+        let descriptor = match MethodDescriptor::parse(&method.descriptor) {
+            Ok(descriptor) => Some(descriptor),
+            Err(kind) => {
+                options.recover(Error::new(kind).within(context()))?;
+                None
+            }
+        };
+        let attributes =
+            to_owned_attributes(method.attributes).map_err(|err| err.within(context()))?;
+        methods.push(bytecode::Method {
+            access_flags: method.access_flags,
+            name_index: method.name_index,
+            descriptor,
+            name: method.name.into_owned(),
+            descriptor_index: method.descriptor_index,
+            attributes,
+        });
+    }
+
+    let attributes_count = cf.attributes.len() as u16;
+    let attributes = to_owned_attributes(cf.attributes)?;
+
+    Ok(bytecode::ClassFile {
+        magic_number: 0xCAFEBABE,
+        version: cf.version,
+        constant_pool_count,
+        constant_pool,
+        access_flags: cf.access_flags,
+        this_class: cf.this_class,
+        super_class: cf.super_class,
+        interfaces_count: cf.interfaces.len() as u16,
+        interfaces: cf.interfaces,
+        fields_count: fields.len() as u16,
+        fields,
+        methods_count: methods.len() as u16,
+        methods,
+        attributes_count,
+        attributes,
+    })
+}
+
+/// Decodes the given attributes, unless they have already been decoded on access.
+fn to_owned_attributes(attributes: Vec<Attribute>) -> Result<Attributes, Error> {
+    let mut owned = Attributes::with_capacity(attributes.len());
+    for attribute in attributes {
+        attribute.decode()?;
+        if let Some(decoded) = attribute.decoded.into_inner().flatten() {
+            owned.push(attribute.name, decoded);
+        }
+    }
+    Ok(owned)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::borrow::Cow;
+    use std::ops::Range;
+
+    use crate::bytecode::attributes::{
+        CodeInfo, RuntimeInvisibleAnnotationsInfo, SourceDebugExtensionInfo, StackMapTableInfo,
+    };
+    use crate::bytecode::flags::FieldAccessFlags;
+    use crate::bytecode::pool::{ConstantPoolBuilder, ConstantPoolView};
+    use crate::bytecode::reader::options::ParseMode;
+    use crate::bytecode::reader::parser::ClassParser;
+    use crate::bytecode::tests::{Layout, FIXTURES, SIMPLE};
+    use crate::bytecode::writer::containers::write_classfile;
+    use crate::bytecode::writer::BufferedWriter;
+    use crate::bytecode::{BytecodeError, ClassFile};
+    use crate::error::Context;
+
+    fn contains(input: &[u8], bytes: &[u8]) -> bool {
+        let Range { start, end } = input.as_ptr_range();
+        let range = bytes.as_ptr_range();
+        start <= range.start && range.end <= end
+    }
+
+    #[test]
+    fn test_borrowed_classfile() {
+        let parser = ClassParser::new();
+        for input in FIXTURES {
+            let cf = parser.parse_borrowed(input).unwrap();
+            let owned = ClassFile::parse(input).unwrap();
+            assert_eq!(cf.constant_pool().size(), owned.constant_pool.size());
+            // Factories resolve names in the borrowed pool without copying them.
+            let pool: &dyn ConstantPoolView = cf.constant_pool();
+            for method in &cf.methods {
+                let name = pool.utf8_at(method.name_index).unwrap();
+                assert!(matches!(name, Cow::Borrowed(name) if contains(input, name.as_bytes())));
+            }
+
+            for (method, owned_method) in cf.methods.iter().zip(&owned.methods) {
+                assert!(matches!(method.name, Cow::Borrowed(_)));
+                assert_eq!(method.name, owned_method.name);
+                let code = method.code().unwrap();
+                let owned_code = owned_method.attribute::<CodeInfo>();
+                match (code, owned_code) {
+                    (Some(code), Some(owned_code)) => {
+                        assert!(contains(input, code.code));
+                        assert_eq!(code.code, owned_code.code);
+                        assert_eq!(code.attributes.len(), owned_code.attributes.len());
+                    }
+                    (code, owned_code) => assert!(code.is_none() && owned_code.is_none()),
+                }
+            }
+
+            let converted = cf.into_owned().unwrap();
+            assert_eq!(converted.constant_pool, owned.constant_pool);
+            assert_eq!(converted.constant_pool_count, owned.constant_pool_count);
+            assert_eq!(converted.fields.len(), owned.fields.len());
+            for (method, owned_method) in converted.methods.iter().zip(&owned.methods) {
+                assert_eq!(method.descriptor, owned_method.descriptor);
+                let names = method.attributes.names().collect::<Vec<_>>();
+                let owned_names = owned_method.attributes.names().collect::<Vec<_>>();
+                assert_eq!(names, owned_names);
+            }
+            assert_eq!(converted.attributes.len(), owned.attributes.len());
+        }
+    }
+
+    #[test]
+    fn test_lazy_decoding() {
+        let parser = ClassParser::new();
+        for input in FIXTURES {
+            let cf = parser.parse_borrowed(input).unwrap();
+            let owned = ClassFile::parse(input).unwrap();
+
+            for (method, owned_method) in cf.methods.iter().zip(&owned.methods) {
+                let Some(code) = method.code().unwrap() else {
+                    continue;
+                };
+                // The `Code` attribute is read once and cached afterwards.
+                let cached = method.code().unwrap().unwrap();
+                assert!(std::ptr::eq(code, cached));
+
+                let frames = code.get::<StackMapTableInfo>().unwrap();
+                let owned_frames = owned_method
+                    .attribute::<CodeInfo>()
+                    .and_then(|code| code.attribute::<StackMapTableInfo>());
+                assert_eq!(
+                    frames.map(|frames| frames.entries.len()),
+                    owned_frames.map(|frames| frames.entries.len())
+                );
+
+                let decoded = method.get::<CodeInfo>().unwrap().unwrap();
+                assert_eq!(decoded.code, code.code);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lenient_borrowed_classfile() {
+        let layout = Layout::of(SIMPLE);
+        let age = layout.field("age");
+        let main = layout.method("main");
+        let mut input = SIMPLE.to_vec();
+        input[age..age + 2].copy_from_slice(&[0xFF, 0xFF]);
+        // The descriptor of the method `main` refers to its name.
+        input.copy_within(main + 2..main + 4, main + 4);
+
+        let err = ClassParser::new().parse_borrowed(&input).unwrap_err();
+        assert_eq!(err.kind(), &BytecodeError::InvalidAccessFlags(0xFFFF));
+
+        let parser = ClassParser::new().mode(ParseMode::Lenient);
+        let cf = parser.parse_borrowed(&input).unwrap();
+        assert_eq!(
+            cf.fields[1].access_flags,
+            FieldAccessFlags::from_bits_retain(0xFFFF)
+        );
+        let diagnostics = cf.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        let error = diagnostics[0].error();
+        assert_eq!(error.kind(), &BytecodeError::InvalidAccessFlags(0xFFFF));
+        assert_eq!(error.offset(), Some(age));
+        assert_eq!(
+            error.context(),
+            [Context::Class, Context::Field("age".to_string())]
+        );
+
+        let (owned, diagnostics) = cf.into_owned_with_diagnostics().unwrap();
+        assert_eq!(owned.methods[1].descriptor, None);
+        assert_eq!(diagnostics.len(), 2);
+        let error = diagnostics[1].error();
+        assert_eq!(
+            error.kind(),
+            &BytecodeError::InvalidDescriptor {
+                descriptor: "main".to_string(),
+                position: 0
+            }
+        );
+        assert_eq!(
+            error.context(),
+            [Context::Class, Context::Method("main".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_lenient_decoding() {
+        let layout = Layout::of(SIMPLE);
+        // The attribute header, `num_annotations`, `type_index`, `num_element_value_pairs` and
+        // `element_name_index` precede the tag.
+        let tag = layout.method_attribute("something", "RuntimeInvisibleAnnotations") + 14;
+        // The attribute header, `max_stack` and `max_locals` precede the `code_length`.
+        let code_length = layout.method_attribute("main", "Code") + 10;
+        let mut input = SIMPLE.to_vec();
+        input[tag] = b'x';
+        input[code_length..code_length + 4].copy_from_slice(&[0xFF; 4]);
+
+        let parser = ClassParser::new();
+        let cf = parser.parse_borrowed(&input).unwrap();
+        let something = &cf.methods[3];
+        let annotations = something.attribute("RuntimeInvisibleAnnotations").unwrap();
+        assert!(annotations.decode().is_err());
+        assert!(cf.methods[1].code().is_err());
+
+        let parser = ClassParser::new().mode(ParseMode::Lenient);
+        let cf = parser.parse_borrowed(&input).unwrap();
+        let something = &cf.methods[3];
+        let annotations = something.attribute("RuntimeInvisibleAnnotations").unwrap();
+        assert!(annotations.decode().unwrap().is_none());
+        // The attribute is decoded only once, so the problem is recorded only once.
+        assert!(annotations.decode().unwrap().is_none());
+        assert!(cf.methods[1].code().unwrap().is_none());
+        assert!(something.code().unwrap().is_some());
+
+        let diagnostics = cf.diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        let error = diagnostics[0].error();
+        assert_eq!(
+            error.kind(),
+            &BytecodeError::InvalidTag {
+                kind: "element value",
+                found: b'x'
+            }
+        );
+        assert_eq!(error.offset(), Some(tag));
+        assert_eq!(
+            error.context()[0],
+            Context::Attribute("RuntimeInvisibleAnnotations".to_string())
+        );
+        assert_eq!(
+            diagnostics[1].error().kind(),
+            &BytecodeError::LimitExceeded {
+                limit: "code length",
+                max: 65535
+            }
+        );
+
+        let (owned, diagnostics) = cf.into_owned_with_diagnostics().unwrap();
+        assert!(!owned.methods[1].attributes.contains("Code"));
+        assert!(!owned.methods[3]
+            .attributes
+            .contains("RuntimeInvisibleAnnotations"));
+        // Converting decodes the `Code` attribute of `main` once more with its factory.
+        assert_eq!(diagnostics.len(), 3);
+    }
+
+    #[test]
+    fn test_borrowed_annotations() {
+        let parser = ClassParser::new();
+        let cf = parser.parse_borrowed(SIMPLE).unwrap();
+        let owned = ClassFile::parse(SIMPLE).unwrap();
+        let something = &cf.methods[3];
+        let annotations = something
+            .annotations("RuntimeInvisibleAnnotations")
+            .unwrap()
+            .unwrap();
+        let owned_annotations = owned.methods[3]
+            .attribute::<RuntimeInvisibleAnnotationsInfo>()
+            .unwrap();
+        assert_eq!(
+            annotations.num_annotations,
+            owned_annotations.num_annotations
+        );
+        let annotations = annotations.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(annotations, owned_annotations.annotations);
+        assert!(something
+            .annotations("RuntimeVisibleAnnotations")
+            .unwrap()
+            .is_none());
+        assert!(something
+            .attribute("Code")
+            .unwrap()
+            .annotations()
+            .unwrap()
+            .is_none());
+
+        // Malformed annotations are reported while iterating, even in lenient mode.
+        let layout = Layout::of(SIMPLE);
+        let tag = layout.method_attribute("something", "RuntimeInvisibleAnnotations") + 14;
+        let mut input = SIMPLE.to_vec();
+        input[tag] = b'x';
+        let parser = ClassParser::new().mode(ParseMode::Lenient);
+        let cf = parser.parse_borrowed(&input).unwrap();
+        let mut annotations = cf.methods[3]
+            .annotations("RuntimeInvisibleAnnotations")
+            .unwrap()
+            .unwrap();
+        let err = annotations.next().unwrap().unwrap_err();
+        assert_eq!(err.offset(), Some(tag));
+        assert_eq!(
+            err.context(),
+            [
+                Context::Attribute("RuntimeInvisibleAnnotations".to_string()),
+                Context::Entry {
+                    table: "annotation",
+                    index: 0
+                }
+            ]
+        );
+        assert!(annotations.next().is_none());
+    }
+
+    #[test]
+    fn test_source_debug_extension() {
+        let mut owned = ClassFile::parse(SIMPLE).unwrap();
+        let mut builder = ConstantPoolBuilder::from_pool(owned.constant_pool.clone()).unwrap();
+        let attribute_name_index = builder.utf8("SourceDebugExtension").unwrap();
+        owned.constant_pool = builder.build();
+        let debug_extension = b"SMAP\nSimple.java\nJava\n*E\n".to_vec();
+        owned.attributes.push(
+            "SourceDebugExtension",
+            Box::new(SourceDebugExtensionInfo {
+                attribute_name_index,
+                attribute_length: debug_extension.len() as u32,
+                debug_extension: debug_extension.clone(),
+            }),
+        );
+        let mut writer = BufferedWriter::new();
+        write_classfile(&mut writer, &owned).unwrap();
+        let input = writer.as_bytes();
+
+        let parser = ClassParser::new();
+        let cf = parser.parse_borrowed(input).unwrap();
+        let extension = cf.source_debug_extension().unwrap();
+        assert_eq!(extension.debug_extension, debug_extension);
+        assert!(contains(input, extension.debug_extension));
+        let source_file = cf.attribute("SourceFile").unwrap();
+        assert_eq!(source_file.source_debug_extension(), None);
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<crate::bytecode::borrowed::ClassFile<'static>>();
+
+        let parser = ClassParser::new();
+        for input in FIXTURES {
+            let cf = parser.parse_borrowed(input).unwrap();
+            let owned = ClassFile::parse(input).unwrap();
+            let sizes = std::thread::scope(|scope| {
+                let cf = &cf;
+                let handles = cf
+                    .methods
+                    .iter()
+                    .map(|method| {
+                        scope.spawn(move || method.code().unwrap().map(|code| code.code.len()))
+                    })
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect::<Vec<_>>()
+            });
+            let owned_sizes = owned
+                .methods
+                .iter()
+                .map(|method| method.attribute::<CodeInfo>().map(|code| code.code.len()))
+                .collect::<Vec<_>>();
+            assert_eq!(sizes, owned_sizes);
+
+            // The class file itself can be moved to another thread as well.
+            let converted = std::thread::scope(|scope| scope.spawn(move || cf.into_owned()).join());
+            assert!(converted.unwrap().is_ok());
+        }
+    }
+}
@@ -1,14 +1,18 @@
 use crate::bytecode::pool::{ConstantPool, ConstantPoolEntry, ConstantPoolIndex, ConstantTag};
 use crate::bytecode::reader::BufferedReader;
-use crate::bytecode::BytecodeError;
+use crate::bytecode::{mutf8, BytecodeError};
+use crate::error::Error;
 
 pub fn read_constant_pool_entry(
     reader: &mut BufferedReader,
     _cp: &mut ConstantPool,
-) -> Result<ConstantPoolEntry, BytecodeError> {
+) -> Result<ConstantPoolEntry, Error> {
     let tag = reader.take::<u8>()?;
     let Some(tag) = ConstantTag::from_tag(tag) else {
-        return Err(BytecodeError::InvalidData);
+        return Err(reader.error(BytecodeError::InvalidTag {
+            kind: "constant",
+            found: tag,
+        }));
     };
 
     let entry = match tag {
@@ -59,7 +63,7 @@ pub fn read_constant_pool_entry(
             ConstantPoolEntry::Integer { bytes }
         }
         ConstantTag::Float => {
-            let bytes = reader.take::<f32>().expect("msg");
+            let bytes = reader.take::<f32>()?;
 
             ConstantPoolEntry::Float { bytes }
         }
@@ -91,11 +95,11 @@ pub fn read_constant_pool_entry(
             }
         }
         ConstantTag::Utf8 => {
-            let length = reader.take::<u16>()?;
-            let bytes = reader.take_bytes(length as usize)?;
+            let bytes = read_utf8_bytes(reader)?;
+            reader.reserve(bytes.len())?;
 
             ConstantPoolEntry::Utf8 {
-                length,
+                length: bytes.len() as u16,
                 bytes: bytes.to_vec(),
             }
         }
@@ -151,3 +155,17 @@ pub fn read_constant_pool_entry(
 
     Ok(entry)
 }
+
+/// Reads the `length` and `bytes` of a `CONSTANT_Utf8_info` entry and returns the bytes after
+/// validating that they are well-formed modified UTF-8.
+pub(crate) fn read_utf8_bytes<'a>(reader: &mut BufferedReader<'a>) -> Result<&'a [u8], Error> {
+    let length = reader.take::<u16>()?;
+    let start = reader.offset();
+    let bytes = reader.take_bytes(length as usize)?;
+    if let Err(kind @ BytecodeError::MalformedUtf8 { offset }) = mutf8::validate(bytes) {
+        // Report the offset of the malformed sequence within the class file.
+        return Err(Error::new(kind).at(start + offset));
+    }
+
+    Ok(bytes)
+}
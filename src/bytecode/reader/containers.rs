@@ -1,25 +1,46 @@
-use std::collections::HashMap;
-
 use crate::bytecode::attributes::Container;
 use crate::bytecode::flags::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags};
 use crate::bytecode::pool::{ConstantPool, ConstantPoolIndex};
-use crate::bytecode::reader::attributes::read_attribute;
+use crate::bytecode::reader::attributes::read_attributes_with;
 use crate::bytecode::reader::constants::read_constant_pool_entry;
+use crate::bytecode::reader::options::ParseOptions;
 use crate::bytecode::reader::BufferedReader;
 use crate::bytecode::{
-    BaseType, BytecodeError, ClassFile, ClassFileVersion, Descriptor, DescriptorKind, Field,
-    FieldType, Interface, Method,
+    BytecodeError, ClassFile, ClassFileVersion, Descriptor, Field, Interface, Method,
+    MethodDescriptor,
 };
+use crate::error::{Context, Error};
 
+/// Reads a class file, including its constant pool, which is owned by the returned [ClassFile].
 pub fn read_classfile(
     reader: &mut BufferedReader,
-    cp: &mut ConstantPool,
     container: &Container,
-) -> Result<ClassFile, BytecodeError> {
+) -> Result<ClassFile, Error> {
+    read_classfile_with(reader, container, &mut ParseOptions::default())
+}
+
+/// Reads a class file according to the given [ParseOptions]. In lenient mode, the diagnostics
+/// recorded while reading are collected in the `options`.
+pub fn read_classfile_with(
+    reader: &mut BufferedReader,
+    container: &Container,
+    options: &mut ParseOptions,
+) -> Result<ClassFile, Error> {
+    let start = options.diagnostics().len();
+    let classfile = read_classfile_contents(reader, container, options)
+        .map_err(|err| err.within(Context::Class));
+    options.within(start, Context::Class);
+    classfile
+}
+
+fn read_classfile_contents(
+    reader: &mut BufferedReader,
+    container: &Container,
+    options: &mut ParseOptions,
+) -> Result<ClassFile, Error> {
     let magic_number = reader.take::<u32>()?;
     if magic_number != 0xCAFEBABE {
-        println!("Magic number is invalid: 0x{:X}", magic_number);
-        return Err(BytecodeError::InvalidClassFile);
+        return Err(reader.error(BytecodeError::InvalidMagicNumber(magic_number)));
     }
 
     let minor_version = reader.take::<u16>()?;
@@ -30,15 +51,36 @@ pub fn read_classfile(
     };
 
     let constant_pool_count = reader.take::<u16>()?;
-    for idx in 1..=constant_pool_count - 1 {
-        let entry = read_constant_pool_entry(reader, cp)?;
-        cp.insert(idx.into(), entry)?;
+    let max_constant_pool_size = reader.limits().max_constant_pool_size;
+    if constant_pool_count.saturating_sub(1) as usize > max_constant_pool_size {
+        return Err(reader.error(BytecodeError::LimitExceeded {
+            limit: "constant pool size",
+            max: max_constant_pool_size,
+        }));
+    }
+    let mut constant_pool = ConstantPool::new();
+    let cp = &mut constant_pool;
+    let mut idx = 1;
+    while idx < constant_pool_count as usize {
+        let entry = read_constant_pool_entry(reader, cp)
+            .map_err(|err| err.within(Context::ConstantPoolEntry(idx)))?;
+        let width = if entry.is_wide() { 2 } else { 1 };
+        if idx + width > constant_pool_count as usize {
+            // A long or double constant can't occupy the last slot of the pool.
+            return Err(reader.error(BytecodeError::WideConstantOutOfBounds(idx)));
+        }
+        cp.insert(idx.into(), entry)
+            .map_err(|kind| reader.error(kind))?;
+        idx += width;
     }
 
     let access_flags = reader.take::<u16>()?;
     let access_flags = match ClassAccessFlags::from_bits(access_flags) {
         Some(flags) => flags,
-        None => return Err(BytecodeError::InvalidClassFile),
+        None => {
+            options.recover(reader.error(BytecodeError::InvalidAccessFlags(access_flags)))?;
+            ClassAccessFlags::from_bits_retain(access_flags)
+        }
     };
 
     let this_class = reader.take::<u16>()?;
@@ -47,38 +89,34 @@ pub fn read_classfile(
     let super_class = ConstantPoolIndex::new(super_class);
 
     let interfaces_count = reader.take::<u16>()?;
-    let mut interfaces = Vec::with_capacity(interfaces_count as usize);
+    let mut interfaces = reader.allocate(interfaces_count as usize)?;
     for _ in 0..interfaces_count {
         let interface = read_interface(reader, cp)?;
         interfaces.push(interface);
     }
 
     let fields_count = reader.take::<u16>()?;
-    let mut fields = Vec::with_capacity(fields_count as usize);
+    let mut fields = reader.allocate(fields_count as usize)?;
     for _ in 0..fields_count {
-        let field = read_field(reader, cp, container)?;
+        let field = read_field_with(reader, cp, container, options)?;
         fields.push(field);
     }
 
     let methods_count = reader.take::<u16>()?;
-    let mut methods = Vec::with_capacity(methods_count as usize);
+    let mut methods = reader.allocate(methods_count as usize)?;
     for _ in 0..methods_count {
-        let method = read_method(reader, cp, container)?;
+        let method = read_method_with(reader, cp, container, options)?;
         methods.push(method);
     }
 
     let attributes_count = reader.take::<u16>()?;
-    let mut attributes = HashMap::with_capacity(attributes_count as usize);
-    for _ in 0..attributes_count {
-        let attribute = read_attribute(reader, cp, container)?;
-        attributes.insert(attribute.name_any(), attribute);
-    }
+    let attributes = read_attributes_with(reader, cp, container, options, attributes_count)?;
 
     Ok(ClassFile {
         magic_number,
         version,
         constant_pool_count,
-        constant_pool: cp.clone(),
+        constant_pool,
         access_flags,
         this_class,
         super_class,
@@ -96,7 +134,7 @@ pub fn read_classfile(
 pub fn read_interface(
     reader: &mut BufferedReader,
     _cp: &mut ConstantPool,
-) -> Result<Interface, BytecodeError> {
+) -> Result<Interface, Error> {
     let name_index = reader.take::<u16>()?;
 
     Ok(Interface {
@@ -108,36 +146,60 @@ pub fn read_field(
     reader: &mut BufferedReader,
     cp: &mut ConstantPool,
     container: &Container,
-) -> Result<Field, BytecodeError> {
+) -> Result<Field, Error> {
+    read_field_with(reader, cp, container, &mut ParseOptions::default())
+}
+
+/// Reads a field according to the given [ParseOptions].
+pub fn read_field_with(
+    reader: &mut BufferedReader,
+    cp: &mut ConstantPool,
+    container: &Container,
+    options: &mut ParseOptions,
+) -> Result<Field, Error> {
+    let access_flags_offset = reader.offset();
     let access_flags = reader.take::<u16>()?;
-    let Some(access_flags) = FieldAccessFlags::from_bits(access_flags) else {
-        return Err(BytecodeError::InvalidClassFile);
-    };
 
     let name_index = reader.take::<u16>()?;
-    let Some(name) = cp.text_of(name_index.into()) else {
-        return Err(BytecodeError::InvalidClassFile);
-    };
+    let name = cp
+        .utf8_at(name_index.into())
+        .map_err(|kind| reader.error(kind))?;
+    reader.reserve(name.len())?;
 
     let descriptor_index = reader.take::<u16>()?;
-    let Some(descriptor) = cp.text_of(descriptor_index.into()) else {
-        return Err(BytecodeError::InvalidClassFile);
+    let descriptor = cp
+        .utf8_at(descriptor_index.into())
+        .map_err(|kind| reader.error(kind))?;
+    reader.reserve(descriptor.len())?;
+    let descriptor = match Descriptor::parse_from_field(descriptor) {
+        Ok(descriptor) => Some(descriptor),
+        Err(kind) => {
+            options.recover(reader.error(kind).within(Context::Field(name.clone())))?;
+            None
+        }
+    };
+
+    let access_flags = match FieldAccessFlags::from_bits(access_flags) {
+        Some(flags) => flags,
+        None => {
+            let err = Error::new(BytecodeError::InvalidAccessFlags(access_flags))
+                .at(access_flags_offset)
+                .within(Context::Field(name.clone()));
+            options.recover(err)?;
+            FieldAccessFlags::from_bits_retain(access_flags)
+        }
     };
-    let descriptor = Descriptor::parse_from_field(descriptor).unwrap_or(Descriptor {
-        kind: DescriptorKind::Type,
-        ty: FieldType::Base(BaseType::Void),
-    });
 
     let attributes_count = reader.take::<u16>()?;
-    // let mut attributes = Vec::with_capacity(attributes_count as usize);
-    let mut attributes = HashMap::with_capacity(attributes_count as usize);
-    for _ in 0..attributes_count {
-        let attribute = read_attribute(reader, cp, container)?;
-        attributes.insert(attribute.name_any(), attribute);
-    }
+    let start = options.diagnostics().len();
+    let attributes = read_attributes_with(reader, cp, container, options, attributes_count)
+        .map_err(|err| err.within(Context::Field(name.clone())))?;
+    options.within(start, Context::Field(name.clone()));
 
     Ok(Field {
+        name_index: ConstantPoolIndex::new(name_index),
         name,
+        descriptor_index: ConstantPoolIndex::new(descriptor_index),
         descriptor,
         access_flags,
         attributes,
@@ -148,33 +210,61 @@ pub fn read_method(
     reader: &mut BufferedReader,
     cp: &mut ConstantPool,
     container: &Container,
-) -> Result<Method, BytecodeError> {
+) -> Result<Method, Error> {
+    read_method_with(reader, cp, container, &mut ParseOptions::default())
+}
+
+/// Reads a method according to the given [ParseOptions].
+pub fn read_method_with(
+    reader: &mut BufferedReader,
+    cp: &mut ConstantPool,
+    container: &Container,
+    options: &mut ParseOptions,
+) -> Result<Method, Error> {
+    let access_flags_offset = reader.offset();
     let access_flags = reader.take::<u16>()?;
-    let Some(access_flags) = MethodAccessFlags::from_bits(access_flags) else {
-        return Err(BytecodeError::InvalidClassFile);
-    };
 
     let name_index = reader.take::<u16>()?;
-    let Some(name) = cp.text_of(name_index.into()) else {
-        return Err(BytecodeError::InvalidClassFile);
-    };
+    let name = cp
+        .utf8_at(name_index.into())
+        .map_err(|kind| reader.error(kind))?;
+    reader.reserve(name.len())?;
 
     let descriptor_index = reader.take::<u16>()?;
-    let Some(descriptor) = cp.text_of(descriptor_index.into()) else {
-        return Err(BytecodeError::InvalidClassFile);
+    let descriptor = cp
+        .utf8_at(descriptor_index.into())
+        .map_err(|kind| reader.error(kind))?;
+    reader.reserve(descriptor.len())?;
+    let descriptor = match MethodDescriptor::parse(&descriptor) {
+        Ok(descriptor) => Some(descriptor),
+        Err(kind) => {
+            options.recover(reader.error(kind).within(Context::Method(name.clone())))?;
+            None
+        }
+    };
+
+    let access_flags = match MethodAccessFlags::from_bits(access_flags) {
+        Some(flags) => flags,
+        None => {
+            let err = Error::new(BytecodeError::InvalidAccessFlags(access_flags))
+                .at(access_flags_offset)
+                .within(Context::Method(name.clone()));
+            options.recover(err)?;
+            MethodAccessFlags::from_bits_retain(access_flags)
+        }
     };
-    let descriptor = Descriptor::parse_from_method(descriptor);
 
     let attributes_count = reader.take::<u16>()?;
-    let mut attributes = HashMap::with_capacity(attributes_count as usize);
-    for _ in 0..attributes_count {
-        let attribute = read_attribute(reader, cp, container)?;
-        attributes.insert(attribute.name_any(), attribute);
-    }
+    let start = options.diagnostics().len();
+    let attributes = read_attributes_with(reader, cp, container, options, attributes_count)
+        .map_err(|err| err.within(Context::Method(name.clone())))?;
+    options.within(start, Context::Method(name.clone()));
 
     Ok(Method {
         access_flags,
+        name_index: ConstantPoolIndex::new(name_index),
         name,
+        descriptor_index: ConstantPoolIndex::new(descriptor_index),
         descriptor,
         attributes,
     })
@@ -1,6 +1,9 @@
 pub mod attributes;
+pub mod borrowed;
 pub mod constants;
 pub mod containers;
+pub mod options;
+pub mod parser;
 #[allow(clippy::module_inception)]
 mod reader;
 
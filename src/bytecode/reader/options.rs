@@ -0,0 +1,416 @@
+use std::fmt;
+
+use crate::error::{Context, Error};
+
+/// Determines how the reader reacts to malformed structures in a class file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Abort reading with an [Error] on the first malformed structure.
+    #[default]
+    Strict,
+    /// Record a [Diagnostic] for malformed attributes, access flags and descriptors and continue
+    /// with the next structure.
+    Lenient,
+}
+
+/// A problem that has been recovered from while reading a class file in [ParseMode::Lenient].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    error: Error,
+}
+
+impl Diagnostic {
+    /// Returns the [Error] that has been recovered from, including its location.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+/// Limits that bound the resources used for reading a class file, so that hostile input can
+/// neither exhaust the stack nor the memory of the reading process. Exceeding a limit results in a
+/// [BytecodeError::LimitExceeded](crate::bytecode::BytecodeError::LimitExceeded).
+///
+/// The limits are attached to a [BufferedReader](crate::bytecode::reader::BufferedReader) with
+/// [with_limits](crate::bytecode::reader::BufferedReader::with_limits) and shared with all readers
+/// derived from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// The maximum nesting depth of attributes and annotation element values.
+    pub max_nesting_depth: usize,
+    /// The maximum length of the `code` array of a `Code` attribute in bytes.
+    pub max_code_length: usize,
+    /// The maximum number of entries in the constant pool, i.e. `constant_pool_count - 1`.
+    pub max_constant_pool_size: usize,
+    /// The maximum number of bytes that may be allocated for the structures of a class file.
+    pub max_allocation: usize,
+}
+
+impl Default for ParseLimits {
+    /// Returns limits that accept every class file that is valid according to the JVM
+    /// specification and that is not excessively nested or large.
+    fn default() -> Self {
+        Self {
+            max_nesting_depth: 64,
+            max_code_length: 65535,
+            max_constant_pool_size: 65535,
+            max_allocation: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// Options for reading a class file, which also collect the [diagnostics](Diagnostic) that are
+/// recorded while reading in [ParseMode::Lenient].
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    mode: ParseMode,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseOptions {
+    pub fn new(mode: ParseMode) -> Self {
+        Self {
+            mode,
+            diagnostics: vec![],
+        }
+    }
+
+    /// Convenience function to create options for [ParseMode::Lenient].
+    pub fn lenient() -> Self {
+        Self::new(ParseMode::Lenient)
+    }
+
+    /// Returns the [ParseMode] of the options.
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// Returns all diagnostics that have been recorded so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Removes and returns all diagnostics that have been recorded so far.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Records the given `error` as a [Diagnostic] if reading is lenient, or returns it otherwise.
+    pub(crate) fn recover(&mut self, error: Error) -> Result<(), Error> {
+        match self.mode {
+            ParseMode::Strict => Err(error),
+            ParseMode::Lenient => {
+                self.diagnostics.push(Diagnostic { error });
+                Ok(())
+            }
+        }
+    }
+
+    /// Adds the given `context` to all diagnostics that have been recorded since `start`, which
+    /// is the number of diagnostics before reading the enclosing structure.
+    pub(crate) fn within(&mut self, start: usize, context: Context) {
+        for diagnostic in &mut self.diagnostics[start..] {
+            diagnostic.error = diagnostic.error.clone().within(context.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::attributes::{
+        element_value_string, CodeInfo, Container, RuntimeInvisibleAnnotationsInfo,
+        UnknownAttributePolicy,
+    };
+    use crate::bytecode::flags::FieldAccessFlags;
+    use crate::bytecode::reader::containers::{read_classfile, read_classfile_with};
+    use crate::bytecode::reader::options::{ParseLimits, ParseOptions};
+    use crate::bytecode::reader::parser::ClassParser;
+    use crate::bytecode::reader::BufferedReader;
+    use crate::bytecode::tests::{Layout, FIXTURES, SIMPLE};
+    use crate::bytecode::{BaseType, BytecodeError, ClassFile, FieldType};
+    use crate::decoder::instructions::parse_instruction;
+    use crate::error::{Context, Error};
+
+    /// Returns the offsets of the access flags of the field `age` and of the tag of the element
+    /// value in the annotation of the method `something` in `Simple.class`.
+    fn malformed_offsets() -> (usize, usize) {
+        let layout = Layout::of(SIMPLE);
+        // The attribute header, `num_annotations`, `type_index`, `num_element_value_pairs` and
+        // `element_name_index` precede the tag.
+        let tag = layout.method_attribute("something", "RuntimeInvisibleAnnotations") + 14;
+        (layout.field("age"), tag)
+    }
+
+    /// Returns a copy of `Simple.class` with invalid access flags and an invalid element value tag
+    /// in an annotation.
+    fn malformed() -> Vec<u8> {
+        let (access_flags, tag) = malformed_offsets();
+        let mut input = SIMPLE.to_vec();
+        input[access_flags..access_flags + 2].copy_from_slice(&[0xFF, 0xFF]);
+        input[tag] = b'x';
+        input
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let input = malformed();
+        let mut reader = BufferedReader::new(&input);
+        let err = read_classfile(&mut reader, &Container::standard()).unwrap_err();
+        assert_eq!(err.kind(), &BytecodeError::InvalidAccessFlags(0xFFFF));
+        assert_eq!(
+            err.context(),
+            [Context::Class, Context::Field("age".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_lenient_mode() {
+        let input = malformed();
+        let mut reader = BufferedReader::new(&input);
+        let mut options = ParseOptions::lenient();
+        let cf = read_classfile_with(&mut reader, &Container::standard(), &mut options).unwrap();
+
+        let age = &cf.fields[1];
+        assert_eq!(
+            age.descriptor.as_ref().unwrap().ty,
+            FieldType::Base(BaseType::Int)
+        );
+        assert_eq!(age.access_flags, FieldAccessFlags::from_bits_retain(0xFFFF));
+        let something = &cf.methods[3];
+        assert!(something.attributes.contains("Code"));
+        assert!(!something.attributes.contains("RuntimeInvisibleAnnotations"));
+        assert_eq!(cf.attributes.len(), 3);
+
+        let diagnostics = options.take_diagnostics();
+        let errors = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.error())
+            .collect::<Vec<_>>();
+        let (access_flags, tag) = malformed_offsets();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].kind(), &BytecodeError::InvalidAccessFlags(0xFFFF));
+        assert_eq!(errors[0].offset(), Some(access_flags));
+        assert_eq!(
+            errors[0].context(),
+            [Context::Class, Context::Field("age".to_string())]
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            format!(
+                "invalid element value tag 120 at offset {tag} in class > method `something` > \
+                 attribute `RuntimeInvisibleAnnotations` > annotation entry 0"
+            )
+        );
+        assert!(options.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_lenient_descriptors() {
+        let layout = Layout::of(SIMPLE);
+        let mut input = SIMPLE.to_vec();
+        // The descriptors of the field `age` and of the method `main` refer to their names.
+        for member in [layout.field("age"), layout.method("main")] {
+            input.copy_within(member + 2..member + 4, member + 4);
+        }
+        let mut reader = BufferedReader::new(&input);
+        let mut options = ParseOptions::lenient();
+        let cf = read_classfile_with(&mut reader, &Container::standard(), &mut options).unwrap();
+
+        assert_eq!(cf.fields[1].descriptor, None);
+        let main = &cf.methods[1];
+        assert_eq!(main.descriptor, None);
+        assert_eq!(main.parameter_indices(), None);
+        assert_eq!(
+            crate::Method::from_bytecode(main, &cf.constant_pool)
+                .unwrap_err()
+                .kind(),
+            &BytecodeError::InvalidDescriptor {
+                descriptor: "main".to_string(),
+                position: 0
+            }
+        );
+
+        let errors = options
+            .take_diagnostics()
+            .into_iter()
+            .map(|diagnostic| diagnostic.error().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(errors.len(), 2);
+        assert!(
+            errors[0].ends_with("in class > field `age`"),
+            "{}",
+            errors[0]
+        );
+        assert!(
+            errors[1].ends_with("in class > method `main`"),
+            "{}",
+            errors[1]
+        );
+    }
+
+    fn read_with_limits(input: &[u8], limits: ParseLimits) -> Result<ClassFile, Error> {
+        ClassParser::new().limits(limits).parse(input)
+    }
+
+    fn limit_exceeded(result: Result<ClassFile, Error>) -> BytecodeError {
+        result.unwrap_err().kind().clone()
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let defaults = ParseLimits::default();
+        assert!(read_with_limits(SIMPLE, defaults).is_ok());
+
+        let limits = ParseLimits {
+            max_constant_pool_size: 10,
+            ..defaults
+        };
+        assert_eq!(
+            limit_exceeded(read_with_limits(SIMPLE, limits)),
+            BytecodeError::LimitExceeded {
+                limit: "constant pool size",
+                max: 10
+            }
+        );
+
+        let limits = ParseLimits {
+            max_code_length: 4,
+            ..defaults
+        };
+        assert_eq!(
+            limit_exceeded(read_with_limits(SIMPLE, limits)),
+            BytecodeError::LimitExceeded {
+                limit: "code length",
+                max: 4
+            }
+        );
+
+        // The attributes of a `Code` attribute are nested one level deeper than the attribute.
+        let limits = ParseLimits {
+            max_nesting_depth: 1,
+            ..defaults
+        };
+        assert_eq!(
+            limit_exceeded(read_with_limits(SIMPLE, limits)),
+            BytecodeError::LimitExceeded {
+                limit: "nesting depth",
+                max: 1
+            }
+        );
+
+        let limits = ParseLimits {
+            max_allocation: 1024,
+            ..defaults
+        };
+        assert_eq!(
+            limit_exceeded(read_with_limits(SIMPLE, limits)),
+            BytecodeError::LimitExceeded {
+                limit: "allocated memory",
+                max: 1024
+            }
+        );
+
+        // A reader and its allocation budget can be moved to another thread.
+        let mut reader = BufferedReader::with_limits(SIMPLE, defaults);
+        let parsed =
+            std::thread::spawn(move || read_classfile(&mut reader, &Container::standard()).is_ok());
+        assert!(parsed.join().unwrap());
+    }
+
+    /// Reads the `input` in all modes and processes everything that has been read, which must not
+    /// panic regardless of the input.
+    fn read_hostile(input: &[u8]) {
+        let limits = ParseLimits {
+            max_allocation: 1024 * 1024,
+            ..ParseLimits::default()
+        };
+        let mut raw = Container::standard();
+        raw.set_unknown_attribute_policy(UnknownAttributePolicy::KeepRaw);
+
+        let parser = ClassParser::new().limits(limits);
+        if let Ok(cf) = parser.parse_borrowed(input) {
+            for method in &cf.methods {
+                if let Ok(Some(code)) = method.code() {
+                    for attr in &code.attributes {
+                        let _ = attr.decode();
+                    }
+                }
+            }
+            let _ = cf.into_owned();
+        }
+
+        for container in [Container::standard(), raw] {
+            for mut options in [ParseOptions::default(), ParseOptions::lenient()] {
+                let mut reader = BufferedReader::with_limits(input, limits);
+                let Ok(cf) = read_classfile_with(&mut reader, &container, &mut options) else {
+                    continue;
+                };
+                let cp = &cf.constant_pool;
+
+                for index in 0..cp.size() + 2 {
+                    let _ = cp.text_of(index.into());
+                }
+                for method in &cf.methods {
+                    let _ = crate::Method::from_bytecode(method, cp);
+                    if let Some(code) = method.attribute::<CodeInfo>() {
+                        let mut code_reader = BufferedReader::new(&code.code);
+                        while let Ok(opcode) = code_reader.take::<u8>() {
+                            if parse_instruction(opcode, &mut code_reader).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    if let Some(attr) = method.attribute::<RuntimeInvisibleAnnotationsInfo>() {
+                        for annotation in &attr.annotations {
+                            for pair in &annotation.element_value_pairs {
+                                let _ = element_value_string(&pair.value, cp);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        for fixture in FIXTURES {
+            for length in 0..fixture.len() {
+                read_hostile(&fixture[..length]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mutated_input() {
+        // A simple xorshift generator, so that the mutations are reproducible.
+        let mut state = 0x2545F4914F6CDD1Du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for fixture in FIXTURES {
+            for _ in 0..500 {
+                let mut input = fixture.to_vec();
+                for _ in 0..1 + next() % 4 {
+                    let offset = next() as usize % input.len();
+                    input[offset] = match next() % 4 {
+                        0 => 0x00,
+                        1 => 0xFF,
+                        2 => input[offset].wrapping_add(1),
+                        _ => next() as u8,
+                    };
+                }
+                read_hostile(&input);
+            }
+        }
+    }
+}
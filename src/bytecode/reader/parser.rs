@@ -0,0 +1,247 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::bytecode::attributes::Container;
+use crate::bytecode::borrowed;
+use crate::bytecode::reader::borrowed::read_borrowed_classfile;
+use crate::bytecode::reader::containers::read_classfile_with;
+use crate::bytecode::reader::options::{Diagnostic, ParseLimits, ParseMode, ParseOptions};
+use crate::bytecode::reader::BufferedReader;
+use crate::bytecode::{BytecodeError, ClassFile};
+use crate::error::Error;
+
+/// Parses class files from bytes with a configurable [Container], [ParseLimits] and [ParseMode].
+///
+/// Every parsed [ClassFile] owns its constant pool, so a parser can be reused for any number of
+/// class files.
+///
+/// ```
+/// use rjvm::bytecode::attributes::Container;
+/// use rjvm::bytecode::reader::options::ParseMode;
+/// use rjvm::bytecode::reader::parser::ClassParser;
+///
+/// let input = include_bytes!("../../../examples/testdata/org/example/Simple.class");
+/// let parser = ClassParser::new()
+///     .container(Container::for_version(65))
+///     .mode(ParseMode::Lenient);
+/// let (cf, diagnostics) = parser.parse_with_diagnostics(input).unwrap();
+/// assert_eq!(cf.methods.len(), 4);
+/// assert!(diagnostics.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct ClassParser {
+    container: Container,
+    limits: ParseLimits,
+    mode: ParseMode,
+}
+
+impl Default for ClassParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClassParser {
+    /// Creates a strict parser that reads all predefined attributes with the default
+    /// [ParseLimits].
+    pub fn new() -> Self {
+        Self {
+            container: Container::standard(),
+            limits: ParseLimits::default(),
+            mode: ParseMode::default(),
+        }
+    }
+
+    /// Sets the [Container] with the factories used for reading attributes.
+    pub fn container(mut self, container: Container) -> Self {
+        self.container = container;
+        self
+    }
+
+    /// Sets the [ParseLimits] that are enforced while reading.
+    pub fn limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the [ParseMode] that determines how malformed structures are handled.
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Parses the class file contained in `bytes`. In [ParseMode::Lenient], recovered problems
+    /// are dropped; use [parse_with_diagnostics](ClassParser::parse_with_diagnostics) to
+    /// inspect them.
+    pub fn parse(&self, bytes: &[u8]) -> Result<ClassFile, Error> {
+        self.parse_with_diagnostics(bytes).map(|(cf, _)| cf)
+    }
+
+    /// Parses the class file contained in `bytes` and returns it together with the diagnostics
+    /// that have been recorded in [ParseMode::Lenient].
+    pub fn parse_with_diagnostics(
+        &self,
+        bytes: &[u8],
+    ) -> Result<(ClassFile, Vec<Diagnostic>), Error> {
+        let mut reader = BufferedReader::with_limits(bytes, self.limits);
+        let mut options = ParseOptions::new(self.mode);
+        let cf = read_classfile_with(&mut reader, &self.container, &mut options)?;
+        Ok((cf, options.take_diagnostics()))
+    }
+
+    /// Reads the class file from `input` and parses it. Reading fails with
+    /// [BytecodeError::LimitExceeded] if the input is larger than
+    /// [max_allocation](ParseLimits::max_allocation).
+    pub fn parse_reader(&self, input: impl Read) -> Result<ClassFile, Error> {
+        let max_allocation = self.limits.max_allocation;
+        let mut bytes = vec![];
+        input
+            .take(max_allocation as u64 + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|err| Error::new(err.into()))?;
+        if bytes.len() > max_allocation {
+            return Err(input_size_exceeded(max_allocation));
+        }
+        self.parse(&bytes)
+    }
+
+    /// Reads the class file at `path` and parses it, see
+    /// [parse_reader](ClassParser::parse_reader).
+    pub fn parse_file(&self, path: impl AsRef<Path>) -> Result<ClassFile, Error> {
+        let file = File::open(path).map_err(|err| Error::new(err.into()))?;
+        self.parse_reader(file)
+    }
+
+    /// Maps the class file at `path` into memory and parses it without reading it into a buffer
+    /// first, which is faster for large collections of class files.
+    ///
+    /// The file must not be modified while it is being parsed, as the mapping would reflect the
+    /// changes.
+    #[cfg(feature = "mmap")]
+    pub fn parse_mapped(&self, path: impl AsRef<Path>) -> Result<ClassFile, Error> {
+        let file = File::open(path).map_err(|err| Error::new(err.into()))?;
+        let len = file.metadata().map_err(|err| Error::new(err.into()))?.len();
+        let max_allocation = self.limits.max_allocation;
+        if len > max_allocation as u64 {
+            return Err(input_size_exceeded(max_allocation));
+        }
+        // SAFETY: The mapping is only read while parsing and dropped afterwards, as the parsed
+        // class file owns all of its data. Concurrent modifications of the file are documented
+        // as unsupported above.
+        let mmap = unsafe { memmap2::Mmap::map(&file) }.map_err(|err| Error::new(err.into()))?;
+        self.parse(&mmap)
+    }
+
+    /// Parses the class file contained in `bytes` into its [borrowed](borrowed::ClassFile)
+    /// representation, which references `bytes` instead of copying them and decodes attributes
+    /// only when needed. In [ParseMode::Lenient], the diagnostics are collected in the class file,
+    /// see [diagnostics](borrowed::ClassFile::diagnostics).
+    pub fn parse_borrowed<'a>(&'a self, bytes: &'a [u8]) -> Result<borrowed::ClassFile<'a>, Error> {
+        let mut reader = BufferedReader::with_limits(bytes, self.limits);
+        read_borrowed_classfile(&mut reader, &self.container, self.mode)
+    }
+}
+
+fn input_size_exceeded(max: usize) -> Error {
+    Error::new(BytecodeError::LimitExceeded {
+        limit: "input size",
+        max,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::reader::options::{ParseLimits, ParseMode};
+    use crate::bytecode::reader::parser::ClassParser;
+    use crate::bytecode::tests::{Layout, CONSTANTS, SIMPLE};
+    use crate::bytecode::BytecodeError;
+
+    #[test]
+    fn test_parser_reuse() {
+        let parser = ClassParser::new();
+        let simple = parser.parse(SIMPLE).unwrap();
+        let constants = parser.parse(CONSTANTS).unwrap();
+        assert_eq!(
+            simple.constant_pool.text_of(simple.this_class).unwrap(),
+            "org/example/Simple"
+        );
+        assert_eq!(
+            constants
+                .constant_pool
+                .text_of(constants.this_class)
+                .unwrap(),
+            "org/example/Constants"
+        );
+        assert_eq!(constants.constant_pool.size(), 44);
+    }
+
+    #[test]
+    fn test_parser_options() {
+        let mut input = SIMPLE.to_vec();
+        // The access flags of the field `age`.
+        let age = Layout::of(SIMPLE).field("age");
+        input[age..age + 2].copy_from_slice(&[0xFF, 0xFF]);
+
+        let err = ClassParser::new().parse(&input).unwrap_err();
+        assert_eq!(err.kind(), &BytecodeError::InvalidAccessFlags(0xFFFF));
+
+        let parser = ClassParser::new().mode(ParseMode::Lenient);
+        let (cf, diagnostics) = parser.parse_with_diagnostics(&input).unwrap();
+        assert_eq!(cf.fields.len(), 2);
+        assert_eq!(diagnostics.len(), 1);
+
+        let limits = ParseLimits {
+            max_code_length: 4,
+            ..ParseLimits::default()
+        };
+        let err = ClassParser::new().limits(limits).parse(SIMPLE).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &BytecodeError::LimitExceeded {
+                limit: "code length",
+                max: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/testdata/org/example/Simple.class"
+        );
+        let parser = ClassParser::new();
+        let cf = parser.parse_file(path).unwrap();
+        assert_eq!(cf.methods.len(), 4);
+        let cf = parser.parse_reader(SIMPLE).unwrap();
+        assert_eq!(cf.methods.len(), 4);
+        #[cfg(feature = "mmap")]
+        assert_eq!(parser.parse_mapped(path).unwrap().methods.len(), 4);
+
+        let err = parser.parse_file("missing.class").unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            BytecodeError::Io {
+                kind: std::io::ErrorKind::NotFound,
+                ..
+            }
+        ));
+
+        let limits = ParseLimits {
+            max_allocation: 16,
+            ..ParseLimits::default()
+        };
+        let err = ClassParser::new()
+            .limits(limits)
+            .parse_reader(SIMPLE)
+            .unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &BytecodeError::LimitExceeded {
+                limit: "input size",
+                max: 16
+            }
+        );
+    }
+}
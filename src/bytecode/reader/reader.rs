@@ -1,4 +1,9 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::bytecode::reader::options::ParseLimits;
 use crate::bytecode::BytecodeError;
+use crate::error::Error;
 
 pub trait FromBytes: Sized {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError>;
@@ -7,7 +12,10 @@ pub trait FromBytes: Sized {
 impl FromBytes for u8 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<u8>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<u8>(),
+                found: bytes.len(),
+            });
         }
         Ok(bytes[0])
     }
@@ -16,7 +24,10 @@ impl FromBytes for u8 {
 impl FromBytes for u16 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<u16>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<u16>(),
+                found: bytes.len(),
+            });
         }
         Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
     }
@@ -25,7 +36,10 @@ impl FromBytes for u16 {
 impl FromBytes for u32 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<u32>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<u32>(),
+                found: bytes.len(),
+            });
         }
         // TODO: we should find a better way to convert bytes to u32 without
         // converting it by hand...
@@ -36,7 +50,10 @@ impl FromBytes for u32 {
 impl FromBytes for i8 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<i8>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<i8>(),
+                found: bytes.len(),
+            });
         }
         Ok(i8::from_be_bytes([bytes[0]]))
     }
@@ -45,7 +62,10 @@ impl FromBytes for i8 {
 impl FromBytes for i16 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<i16>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<i16>(),
+                found: bytes.len(),
+            });
         }
         Ok(i16::from_be_bytes([bytes[0], bytes[1]]))
     }
@@ -54,7 +74,10 @@ impl FromBytes for i16 {
 impl FromBytes for i32 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<i32>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<i32>(),
+                found: bytes.len(),
+            });
         }
         Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
     }
@@ -63,7 +86,10 @@ impl FromBytes for i32 {
 impl FromBytes for i64 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<i64>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<i64>(),
+                found: bytes.len(),
+            });
         }
         Ok(i64::from_be_bytes([
             bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
@@ -74,7 +100,10 @@ impl FromBytes for i64 {
 impl FromBytes for f32 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<f32>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<f32>(),
+                found: bytes.len(),
+            });
         }
         Ok(f32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
     }
@@ -83,7 +112,10 @@ impl FromBytes for f32 {
 impl FromBytes for f64 {
     fn from_bytes(bytes: &[u8]) -> Result<Self, BytecodeError> {
         if bytes.len() != std::mem::size_of::<f64>() {
-            return Err(BytecodeError::InvalidData);
+            return Err(BytecodeError::UnexpectedEndOfData {
+                expected: std::mem::size_of::<f64>(),
+                found: bytes.len(),
+            });
         }
         Ok(f64::from_be_bytes([
             bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
@@ -100,51 +132,151 @@ impl FromBytes for Vec<u8> {
 #[derive(Debug, Clone)]
 pub struct BufferedReader<'a> {
     data: &'a [u8],
+    /// The offset of `data` within the class file, which is non-zero for readers created with
+    /// [sub_reader](BufferedReader::sub_reader).
+    base: usize,
     position: usize,
+    /// The position of the value that has been read last.
+    last_position: usize,
     size: usize,
+    limits: ParseLimits,
+    /// The current nesting depth, see [nested](BufferedReader::nested).
+    depth: usize,
+    /// The number of bytes that have been allocated so far, which is shared with all readers
+    /// derived from this one.
+    allocated: Arc<AtomicUsize>,
 }
 
 impl<'a> BufferedReader<'a> {
     pub fn new(data: &'a [u8]) -> Self {
+        Self::with_limits(data, ParseLimits::default())
+    }
+
+    /// Creates a reader for the given `data` that enforces the given [ParseLimits].
+    pub fn with_limits(data: &'a [u8], limits: ParseLimits) -> Self {
         Self {
             data,
+            base: 0,
             position: 0,
+            last_position: 0,
             size: data.len(),
+            limits,
+            depth: 0,
+            allocated: Arc::new(AtomicUsize::new(0)),
         }
     }
 
-    fn advance(&mut self, n: usize) -> Result<&'a [u8], BytecodeError> {
-        if self.position + n > self.size {
-            Err(BytecodeError::UnexpectedEndOfData)
+    fn advance(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if n > self.remaining() {
+            Err(Error::new(BytecodeError::UnexpectedEndOfData {
+                expected: n,
+                found: self.remaining(),
+            })
+            .at(self.offset()))
         } else {
             let slice = &self.data[self.position..self.position + n];
+            self.last_position = self.position;
             self.position += n;
             Ok(slice)
         }
     }
 
-    pub fn take<T>(&mut self) -> Result<T, BytecodeError>
+    pub fn take<T>(&mut self) -> Result<T, Error>
     where
         T: FromBytes,
     {
         let length = std::mem::size_of::<T>();
         let slice = self.advance(length)?;
-        T::from_bytes(slice)
+        T::from_bytes(slice).map_err(|kind| self.error(kind))
     }
 
-    pub fn peek_bytes<T>(&self) -> Result<T, BytecodeError>
+    pub fn peek_bytes<T>(&self) -> Result<T, Error>
     where
         T: FromBytes,
     {
         let length = std::mem::size_of::<T>();
-        let slice = &self.data[self.position..self.position + length];
-        T::from_bytes(slice)
+        let end = self.position + length.min(self.remaining());
+        let slice = &self.data[self.position..end];
+        T::from_bytes(slice).map_err(|kind| Error::new(kind).at(self.offset()))
     }
 
-    pub fn take_bytes(&mut self, length: usize) -> Result<&'a [u8], BytecodeError> {
+    pub fn take_bytes(&mut self, length: usize) -> Result<&'a [u8], Error> {
         self.advance(length)
     }
 
+    /// Takes the next `length` bytes and returns a [BufferedReader] that is bounded to them. The
+    /// offsets reported by the returned reader are relative to the start of the class file.
+    pub fn sub_reader(&mut self, length: usize) -> Result<BufferedReader<'a>, Error> {
+        let offset = self.offset();
+        let data = self.advance(length)?;
+        Ok(BufferedReader {
+            data,
+            base: offset,
+            position: 0,
+            last_position: 0,
+            size: length,
+            limits: self.limits,
+            depth: self.depth,
+            allocated: Arc::clone(&self.allocated),
+        })
+    }
+
+    /// Returns the [ParseLimits] enforced by the reader.
+    pub fn limits(&self) -> &ParseLimits {
+        &self.limits
+    }
+
+    /// Calls `f` one nesting level deeper, failing if this exceeds the
+    /// [max_nesting_depth](ParseLimits::max_nesting_depth). This is used for structures that
+    /// contain structures of the same kind, e.g. attributes within a `Code` attribute.
+    pub fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth >= self.limits.max_nesting_depth {
+            return Err(self.limit_exceeded("nesting depth", self.limits.max_nesting_depth));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Accounts for the allocation of `count` values of type `T` and returns the capacity to use
+    /// for them. Since every value occupies at least one byte of the class file, the capacity is
+    /// bounded by the number of remaining bytes, so that bogus counts don't result in huge
+    /// allocations.
+    pub fn capacity_for<T>(&self, count: usize) -> Result<usize, Error> {
+        let capacity = count.min(self.remaining());
+        self.reserve(capacity.saturating_mul(std::mem::size_of::<T>()))?;
+        Ok(capacity)
+    }
+
+    /// Returns an empty [Vec] for `count` values of type `T` with a capacity according to
+    /// [capacity_for](BufferedReader::capacity_for).
+    pub fn allocate<T>(&self, count: usize) -> Result<Vec<T>, Error> {
+        Ok(Vec::with_capacity(self.capacity_for::<T>(count)?))
+    }
+
+    /// Accounts for the allocation of `bytes` bytes, failing if this exceeds the
+    /// [max_allocation](ParseLimits::max_allocation).
+    pub fn reserve(&self, bytes: usize) -> Result<(), Error> {
+        let max = self.limits.max_allocation;
+        self.allocated
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |allocated| {
+                Some(allocated.saturating_add(bytes)).filter(|&allocated| allocated <= max)
+            })
+            .map_err(|_| self.limit_exceeded("allocated memory", max))?;
+        Ok(())
+    }
+
+    fn limit_exceeded(&self, limit: &'static str, max: usize) -> Error {
+        Error::new(BytecodeError::LimitExceeded { limit, max }).at(self.offset())
+    }
+
+    /// Creates an [Error] of the given `kind` located at the value that has been read last. This
+    /// is used to report values that have been read successfully, but turned out to be invalid.
+    pub fn error(&self, kind: BytecodeError) -> Error {
+        Error::new(kind).at(self.base + self.last_position)
+    }
+
     /// Returns the size of [BufferedReader's](BufferedReader) data in bytes.
     pub fn size(&self) -> usize {
         self.size
@@ -155,8 +287,20 @@ impl<'a> BufferedReader<'a> {
         self.position
     }
 
+    /// Returns the current position of [BufferedReader](BufferedReader) relative to the start of
+    /// the class file. This only differs from [position](BufferedReader::position) for readers
+    /// created with [sub_reader](BufferedReader::sub_reader).
+    pub fn offset(&self) -> usize {
+        self.base + self.position
+    }
+
+    /// Returns the number of bytes that have not been read yet.
+    pub fn remaining(&self) -> usize {
+        self.size - self.position
+    }
+
     /// Indicates whether the reader has remaining data to be read.
     pub fn has_remaining_data(&self) -> bool {
-        self.position == self.data.len()
+        self.position < self.size
     }
 }
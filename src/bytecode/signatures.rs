@@ -0,0 +1,751 @@
+//! Parsing of the generic signatures stored in `Signature` attributes and rendering them as Java
+//! source.
+//! <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.9.1>
+
+use std::fmt;
+
+use crate::bytecode::attributes::SignatureInfo;
+use crate::bytecode::pool::ConstantPool;
+use crate::bytecode::{BaseType, BytecodeError};
+
+/// The maximum nesting of type arguments and array components, which bounds the recursion of the
+/// parser for hostile input.
+const MAX_DEPTH: usize = 255;
+
+/// The generic signature of a class, e.g. `<T:Ljava/lang/Object;>Ljava/lang/Object;`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub superclass: ClassTypeSignature,
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+/// The generic signature of a method, e.g. `<T:Ljava/lang/Object;>(TT;)Ljava/util/List<TT;>;`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<JavaTypeSignature>,
+    /// The result type, or `None` if the method returns `void`.
+    pub result: Option<JavaTypeSignature>,
+    /// The thrown exceptions, which are either class types or type variables.
+    pub throws: Vec<ReferenceTypeSignature>,
+}
+
+/// The generic signature of a field, record component or local variable.
+pub type FieldSignature = ReferenceTypeSignature;
+
+/// A type parameter with its bounds, e.g. `T:Ljava/lang/Object;` for `T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeParameter {
+    pub name: String,
+    /// The class bound, which is missing if the parameter is only bounded by interfaces.
+    pub class_bound: Option<ReferenceTypeSignature>,
+    pub interface_bounds: Vec<ReferenceTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JavaTypeSignature {
+    Base(BaseType),
+    Reference(ReferenceTypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReferenceTypeSignature {
+    Class(ClassTypeSignature),
+    /// A type variable with the given name, e.g. `T`.
+    TypeVariable(String),
+    Array(Box<JavaTypeSignature>),
+}
+
+/// A possibly parameterized class type, e.g. `Ljava/util/Map<TK;TV;>.Entry<TK;TV;>;`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassTypeSignature {
+    /// The package of the class with `/` as separator, e.g. `java/util`, or an empty string for
+    /// the unnamed package.
+    pub package: String,
+    pub class: SimpleClassTypeSignature,
+    /// The inner classes, each nested in the previous one, e.g. `Entry<TK;TV;>` of
+    /// `Map<TK;TV;>.Entry<TK;TV;>`.
+    pub suffixes: Vec<SimpleClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimpleClassTypeSignature {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeArgument {
+    /// The unbounded wildcard `?`.
+    Any,
+    Exact(ReferenceTypeSignature),
+    /// A wildcard with an upper bound, e.g. `? extends T`.
+    Extends(ReferenceTypeSignature),
+    /// A wildcard with a lower bound, e.g. `? super T`.
+    Super(ReferenceTypeSignature),
+}
+
+/// Determines how class names are rendered as Java source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Names {
+    /// Fully qualified names, e.g. `java.util.Map.Entry`.
+    Qualified,
+    /// Simple names without the package, e.g. `Map.Entry`.
+    Simple,
+}
+
+impl SignatureInfo {
+    /// Parses the signature of a class, which is resolved with the `pool`.
+    pub fn class_signature(&self, pool: &ConstantPool) -> Result<ClassSignature, BytecodeError> {
+        ClassSignature::parse(&pool.utf8_at(self.signature_index)?)
+    }
+
+    /// Parses the signature of a method, which is resolved with the `pool`.
+    pub fn method_signature(&self, pool: &ConstantPool) -> Result<MethodSignature, BytecodeError> {
+        MethodSignature::parse(&pool.utf8_at(self.signature_index)?)
+    }
+
+    /// Parses the signature of a field or record component, which is resolved with the `pool`.
+    pub fn field_signature(&self, pool: &ConstantPool) -> Result<FieldSignature, BytecodeError> {
+        ReferenceTypeSignature::parse(&pool.utf8_at(self.signature_index)?)
+    }
+}
+
+impl ClassSignature {
+    /// ```text
+    /// ClassSignature:
+    ///     [TypeParameters] SuperclassSignature {SuperinterfaceSignature}
+    /// ```
+    pub fn parse(signature: &str) -> Result<ClassSignature, BytecodeError> {
+        Parser::parse(signature, |parser| {
+            let type_parameters = parser.type_parameters()?;
+            let superclass = parser.class_type()?;
+            let mut interfaces = vec![];
+            while !parser.is_at_end() {
+                interfaces.push(parser.class_type()?);
+            }
+            Some(ClassSignature {
+                type_parameters,
+                superclass,
+                interfaces,
+            })
+        })
+    }
+
+    /// Renders the signature as Java source, e.g. `<T> extends Object implements List<T>`.
+    pub fn to_java(&self, names: Names) -> String {
+        let mut out = String::new();
+        if !self.type_parameters.is_empty() {
+            write_type_parameters(&mut out, &self.type_parameters, names);
+            out.push(' ');
+        }
+        out.push_str("extends ");
+        self.superclass.write_java(&mut out, names);
+        for (index, interface) in self.interfaces.iter().enumerate() {
+            out.push_str(if index == 0 { " implements " } else { ", " });
+            interface.write_java(&mut out, names);
+        }
+        out
+    }
+}
+
+impl MethodSignature {
+    /// ```text
+    /// MethodSignature:
+    ///     [TypeParameters] ( {JavaTypeSignature} ) Result {ThrowsSignature}
+    ///
+    /// Result:
+    ///     JavaTypeSignature
+    ///     VoidDescriptor
+    ///
+    /// ThrowsSignature:
+    ///     ^ ClassTypeSignature
+    ///     ^ TypeVariableSignature
+    /// ```
+    pub fn parse(signature: &str) -> Result<MethodSignature, BytecodeError> {
+        Parser::parse(signature, |parser| {
+            let type_parameters = parser.type_parameters()?;
+            parser.expect(b'(')?;
+            let mut parameters = vec![];
+            while !parser.eat(b')') {
+                parameters.push(parser.java_type()?);
+            }
+            let result = match parser.eat(b'V') {
+                true => None,
+                false => Some(parser.java_type()?),
+            };
+            let mut throws = vec![];
+            while parser.eat(b'^') {
+                let thrown = parser.reference_type()?;
+                if matches!(thrown, ReferenceTypeSignature::Array(_)) {
+                    return None;
+                }
+                throws.push(thrown);
+            }
+            Some(MethodSignature {
+                type_parameters,
+                parameters,
+                result,
+                throws,
+            })
+        })
+    }
+
+    /// Renders the signature as Java source without the name of the method, e.g.
+    /// `<T> List<T> (T[], int) throws IOException`.
+    pub fn to_java(&self, names: Names) -> String {
+        let mut out = String::new();
+        if !self.type_parameters.is_empty() {
+            write_type_parameters(&mut out, &self.type_parameters, names);
+            out.push(' ');
+        }
+        match &self.result {
+            Some(result) => result.write_java(&mut out, names),
+            None => out.push_str("void"),
+        }
+        out.push_str(" (");
+        write_separated(&mut out, &self.parameters, ", ", |out, parameter| {
+            parameter.write_java(out, names)
+        });
+        out.push(')');
+        if !self.throws.is_empty() {
+            out.push_str(" throws ");
+            write_separated(&mut out, &self.throws, ", ", |out, thrown| {
+                thrown.write_java(out, names)
+            });
+        }
+        out
+    }
+}
+
+impl ReferenceTypeSignature {
+    /// ```text
+    /// FieldSignature:
+    ///     ReferenceTypeSignature
+    /// ```
+    pub fn parse(signature: &str) -> Result<ReferenceTypeSignature, BytecodeError> {
+        Parser::parse(signature, |parser| parser.reference_type())
+    }
+
+    /// Renders the type as Java source, e.g. `List<? extends T>` with [Names::Simple].
+    pub fn to_java(&self, names: Names) -> String {
+        let mut out = String::new();
+        self.write_java(&mut out, names);
+        out
+    }
+
+    fn write_java(&self, out: &mut String, names: Names) {
+        match self {
+            ReferenceTypeSignature::Class(class) => class.write_java(out, names),
+            ReferenceTypeSignature::TypeVariable(name) => out.push_str(name),
+            ReferenceTypeSignature::Array(component) => {
+                component.write_java(out, names);
+                out.push_str("[]");
+            }
+        }
+    }
+}
+
+impl JavaTypeSignature {
+    /// Renders the type as Java source, e.g. `int` or `List<T>[]` with [Names::Simple].
+    pub fn to_java(&self, names: Names) -> String {
+        let mut out = String::new();
+        self.write_java(&mut out, names);
+        out
+    }
+
+    fn write_java(&self, out: &mut String, names: Names) {
+        match self {
+            JavaTypeSignature::Base(ty) => out.push_str(&ty.to_string()),
+            JavaTypeSignature::Reference(ty) => ty.write_java(out, names),
+        }
+    }
+}
+
+impl ClassTypeSignature {
+    /// Returns the binary name of the class, e.g. `java/util/Map$Entry`.
+    pub fn binary_name(&self) -> String {
+        let mut name = self.package.clone();
+        if !name.is_empty() {
+            name.push('/');
+        }
+        name.push_str(&self.class.name);
+        for suffix in &self.suffixes {
+            name.push('$');
+            name.push_str(&suffix.name);
+        }
+        name
+    }
+
+    fn write_java(&self, out: &mut String, names: Names) {
+        if names == Names::Qualified && !self.package.is_empty() {
+            out.push_str(&self.package.replace('/', "."));
+            out.push('.');
+        }
+        self.class.write_java(out, names);
+        for suffix in &self.suffixes {
+            out.push('.');
+            suffix.write_java(out, names);
+        }
+    }
+}
+
+impl SimpleClassTypeSignature {
+    fn write_java(&self, out: &mut String, names: Names) {
+        // Nested classes without type arguments are part of the name, e.g. `Map$Entry`.
+        out.push_str(&self.name.replace('$', "."));
+        if !self.type_arguments.is_empty() {
+            out.push('<');
+            write_separated(out, &self.type_arguments, ", ", |out, argument| {
+                argument.write_java(out, names)
+            });
+            out.push('>');
+        }
+    }
+}
+
+impl TypeArgument {
+    fn write_java(&self, out: &mut String, names: Names) {
+        match self {
+            TypeArgument::Any => out.push('?'),
+            TypeArgument::Exact(ty) => ty.write_java(out, names),
+            TypeArgument::Extends(ty) => {
+                out.push_str("? extends ");
+                ty.write_java(out, names);
+            }
+            TypeArgument::Super(ty) => {
+                out.push_str("? super ");
+                ty.write_java(out, names);
+            }
+        }
+    }
+}
+
+impl TypeParameter {
+    fn write_java(&self, out: &mut String, names: Names) {
+        out.push_str(&self.name);
+        let bounds = self
+            .class_bound
+            .iter()
+            .chain(&self.interface_bounds)
+            .collect::<Vec<_>>();
+        // `<T>` is bounded by `Object`, which is omitted in Java source.
+        let is_object = |bound: &ReferenceTypeSignature| {
+            matches!(bound, ReferenceTypeSignature::Class(class)
+                if class.binary_name() == "java/lang/Object" && class.class.type_arguments.is_empty())
+        };
+        if bounds.len() == 1 && is_object(bounds[0]) {
+            return;
+        }
+        out.push_str(" extends ");
+        write_separated(out, &bounds, " & ", |out, bound| {
+            bound.write_java(out, names)
+        });
+    }
+}
+
+impl fmt::Display for ClassSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_java(Names::Qualified))
+    }
+}
+
+impl fmt::Display for MethodSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_java(Names::Qualified))
+    }
+}
+
+impl fmt::Display for ReferenceTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_java(Names::Qualified))
+    }
+}
+
+impl fmt::Display for JavaTypeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_java(Names::Qualified))
+    }
+}
+
+fn write_type_parameters(out: &mut String, parameters: &[TypeParameter], names: Names) {
+    out.push('<');
+    write_separated(out, parameters, ", ", |out, parameter| {
+        parameter.write_java(out, names)
+    });
+    out.push('>');
+}
+
+fn write_separated<T>(
+    out: &mut String,
+    items: &[T],
+    separator: &str,
+    mut write: impl FnMut(&mut String, &T),
+) {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            out.push_str(separator);
+        }
+        write(out, item);
+    }
+}
+
+/// A recursive descent parser over the bytes of a signature. The parsing functions return `None`
+/// for malformed input, which [Parser::parse] reports as [BytecodeError::InvalidSignature].
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parses the whole `signature` with the given function.
+    fn parse<T>(
+        signature: &'a str,
+        parse: impl FnOnce(&mut Parser<'a>) -> Option<T>,
+    ) -> Result<T, BytecodeError> {
+        let mut parser = Parser {
+            input: signature,
+            position: 0,
+            depth: 0,
+        };
+        match parse(&mut parser) {
+            Some(result) if parser.is_at_end() => Ok(result),
+            _ => Err(BytecodeError::InvalidSignature(signature.to_string())),
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position == self.input.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.position).copied()
+    }
+
+    /// Consumes the next byte if it is `expected`.
+    fn eat(&mut self, expected: u8) -> bool {
+        let matches = self.peek() == Some(expected);
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, expected: u8) -> Option<()> {
+        self.eat(expected).then_some(())
+    }
+
+    /// Runs `parse` one level deeper, failing if the input is nested too deeply.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// ```text
+    /// Identifier:
+    ///     any sequence of characters except . ; [ / < > :
+    /// ```
+    fn identifier(&mut self) -> Option<String> {
+        let rest = &self.input[self.position..];
+        let length = rest
+            .find(['.', ';', '[', '/', '<', '>', ':'])
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return None;
+        }
+        self.position += length;
+        Some(rest[..length].to_string())
+    }
+
+    /// ```text
+    /// TypeParameters:
+    ///     < TypeParameter {TypeParameter} >
+    ///
+    /// TypeParameter:
+    ///     Identifier ClassBound {InterfaceBound}
+    ///
+    /// ClassBound:
+    ///     : [ReferenceTypeSignature]
+    ///
+    /// InterfaceBound:
+    ///     : ReferenceTypeSignature
+    /// ```
+    fn type_parameters(&mut self) -> Option<Vec<TypeParameter>> {
+        let mut parameters = vec![];
+        if !self.eat(b'<') {
+            return Some(parameters);
+        }
+        loop {
+            let name = self.identifier()?;
+            self.expect(b':')?;
+            let class_bound = match self.peek() {
+                Some(b':') => None,
+                _ => Some(self.reference_type()?),
+            };
+            let mut interface_bounds = vec![];
+            while self.eat(b':') {
+                interface_bounds.push(self.reference_type()?);
+            }
+            parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+            if self.eat(b'>') {
+                return Some(parameters);
+            }
+        }
+    }
+
+    /// ```text
+    /// JavaTypeSignature:
+    ///     ReferenceTypeSignature
+    ///     BaseType
+    /// ```
+    fn java_type(&mut self) -> Option<JavaTypeSignature> {
+        let base = match self.peek()? {
+            b'B' => BaseType::Byte,
+            b'C' => BaseType::Char,
+            b'D' => BaseType::Double,
+            b'F' => BaseType::Float,
+            b'I' => BaseType::Int,
+            b'J' => BaseType::Long,
+            b'S' => BaseType::Short,
+            b'Z' => BaseType::Boolean,
+            _ => return self.reference_type().map(JavaTypeSignature::Reference),
+        };
+        self.position += 1;
+        Some(JavaTypeSignature::Base(base))
+    }
+
+    /// ```text
+    /// ReferenceTypeSignature:
+    ///     ClassTypeSignature
+    ///     TypeVariableSignature
+    ///     ArrayTypeSignature
+    ///
+    /// TypeVariableSignature:
+    ///     T Identifier ;
+    ///
+    /// ArrayTypeSignature:
+    ///     [ JavaTypeSignature
+    /// ```
+    fn reference_type(&mut self) -> Option<ReferenceTypeSignature> {
+        match self.peek()? {
+            b'L' => self.class_type().map(ReferenceTypeSignature::Class),
+            b'T' => {
+                self.position += 1;
+                let name = self.identifier()?;
+                self.expect(b';')?;
+                Some(ReferenceTypeSignature::TypeVariable(name))
+            }
+            b'[' => {
+                self.position += 1;
+                let component = self.nested(|parser| parser.java_type())?;
+                Some(ReferenceTypeSignature::Array(Box::new(component)))
+            }
+            _ => None,
+        }
+    }
+
+    /// ```text
+    /// ClassTypeSignature:
+    ///     L [PackageSpecifier] SimpleClassTypeSignature {ClassTypeSignatureSuffix} ;
+    ///
+    /// PackageSpecifier:
+    ///     Identifier / {PackageSpecifier}
+    ///
+    /// ClassTypeSignatureSuffix:
+    ///     . SimpleClassTypeSignature
+    /// ```
+    fn class_type(&mut self) -> Option<ClassTypeSignature> {
+        self.expect(b'L')?;
+        let mut package = String::new();
+        let mut name = self.identifier()?;
+        while self.eat(b'/') {
+            if !package.is_empty() {
+                package.push('/');
+            }
+            package.push_str(&name);
+            name = self.identifier()?;
+        }
+        let class = self.simple_class_type(name)?;
+        let mut suffixes = vec![];
+        while self.eat(b'.') {
+            let name = self.identifier()?;
+            suffixes.push(self.simple_class_type(name)?);
+        }
+        self.expect(b';')?;
+        Some(ClassTypeSignature {
+            package,
+            class,
+            suffixes,
+        })
+    }
+
+    /// ```text
+    /// SimpleClassTypeSignature:
+    ///     Identifier [TypeArguments]
+    ///
+    /// TypeArguments:
+    ///     < TypeArgument {TypeArgument} >
+    ///
+    /// TypeArgument:
+    ///     [WildcardIndicator] ReferenceTypeSignature
+    ///     *
+    /// ```
+    fn simple_class_type(&mut self, name: String) -> Option<SimpleClassTypeSignature> {
+        let mut type_arguments = vec![];
+        if self.eat(b'<') {
+            loop {
+                let argument = match self.peek()? {
+                    b'*' => {
+                        self.position += 1;
+                        TypeArgument::Any
+                    }
+                    b'+' => {
+                        self.position += 1;
+                        TypeArgument::Extends(self.nested(|parser| parser.reference_type())?)
+                    }
+                    b'-' => {
+                        self.position += 1;
+                        TypeArgument::Super(self.nested(|parser| parser.reference_type())?)
+                    }
+                    _ => TypeArgument::Exact(self.nested(|parser| parser.reference_type())?),
+                };
+                type_arguments.push(argument);
+                if self.eat(b'>') {
+                    break;
+                }
+            }
+        }
+        Some(SimpleClassTypeSignature {
+            name,
+            type_arguments,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::attributes::{RecordInfo, SignatureInfo};
+    use crate::bytecode::signatures::{
+        ClassSignature, JavaTypeSignature, MethodSignature, Names, ReferenceTypeSignature,
+        TypeArgument,
+    };
+    use crate::bytecode::tests::POINT;
+    use crate::bytecode::{BaseType, ClassFile};
+
+    #[test]
+    fn test_parse_class_signature() {
+        let signature = "<K:Ljava/lang/Object;V::Ljava/lang/Comparable<-TV;>;>\
+            Ljava/util/AbstractMap<TK;TV;>;Ljava/io/Serializable;";
+        let parsed = ClassSignature::parse(signature).unwrap();
+        assert_eq!(parsed.type_parameters.len(), 2);
+        assert!(parsed.type_parameters[1].class_bound.is_none());
+        assert_eq!(parsed.superclass.binary_name(), "java/util/AbstractMap");
+        assert_eq!(
+            parsed.to_java(Names::Simple),
+            "<K, V extends Comparable<? super V>> extends AbstractMap<K, V> implements Serializable"
+        );
+    }
+
+    #[test]
+    fn test_parse_method_signature() {
+        let signature = "<T:Ljava/lang/Object;>([TT;ILjava/util/List<+TT;>;)\
+            Ljava/util/Map<TT;*>.Entry<TT;[I>;^Ljava/io/IOException;^TT;";
+        let parsed = MethodSignature::parse(signature).unwrap();
+        assert_eq!(parsed.parameters[1], JavaTypeSignature::Base(BaseType::Int));
+        assert_eq!(parsed.throws.len(), 2);
+        let Some(JavaTypeSignature::Reference(ReferenceTypeSignature::Class(result))) =
+            &parsed.result
+        else {
+            panic!("unexpected result {:?}", parsed.result);
+        };
+        assert_eq!(result.binary_name(), "java/util/Map$Entry");
+        assert_eq!(result.class.type_arguments[1], TypeArgument::Any);
+        assert_eq!(
+            parsed.to_string(),
+            "<T> java.util.Map<T, ?>.Entry<T, int[]> (T[], int, java.util.List<? extends T>) \
+            throws java.io.IOException, T"
+        );
+
+        let parsed = MethodSignature::parse("()V").unwrap();
+        assert_eq!(parsed.result, None);
+        assert_eq!(parsed.to_java(Names::Simple), "void ()");
+    }
+
+    #[test]
+    fn test_parse_field_signature() {
+        let parsed = ReferenceTypeSignature::parse("Ljava/util/List<+TT;>;").unwrap();
+        assert_eq!(parsed.to_java(Names::Simple), "List<? extends T>");
+        let parsed = ReferenceTypeSignature::parse("LOuter$Inner;").unwrap();
+        assert_eq!(parsed.to_java(Names::Qualified), "Outer.Inner");
+
+        let invalid = [
+            "",
+            "I",
+            "Ljava/util/List",
+            "Ljava/util/List<>;",
+            "TT",
+            "Ljava//List;",
+            "Ljava/lang/String;;",
+        ];
+        for signature in invalid {
+            assert!(
+                ReferenceTypeSignature::parse(signature).is_err(),
+                "{signature}"
+            );
+        }
+
+        let nested = format!(
+            "{}Ljava/lang/String;{}",
+            "Ljava/util/List<".repeat(300),
+            ">;".repeat(300)
+        );
+        assert!(ReferenceTypeSignature::parse(&nested).is_err());
+    }
+
+    #[test]
+    fn test_signature_attributes() {
+        let cf = ClassFile::parse(POINT).unwrap();
+        let pool = &cf.constant_pool;
+        let signature = cf.attribute::<SignatureInfo>().unwrap();
+        assert_eq!(
+            signature.class_signature(pool).unwrap().to_string(),
+            "<T> extends java.lang.Record"
+        );
+
+        let record = cf.attribute::<RecordInfo>().unwrap();
+        let signatures = record
+            .components
+            .iter()
+            .filter_map(|component| component.attribute::<SignatureInfo>())
+            .map(|signature| {
+                signature
+                    .field_signature(pool)
+                    .unwrap()
+                    .to_java(Names::Simple)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(signatures, ["T", "List<String>"]);
+
+        let label = cf
+            .methods
+            .iter()
+            .find(|method| method.name == "label")
+            .unwrap();
+        let signature = label.attribute::<SignatureInfo>().unwrap();
+        assert_eq!(
+            signature.method_signature(pool).unwrap().to_string(),
+            "T ()"
+        );
+    }
+}
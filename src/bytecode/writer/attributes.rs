@@ -0,0 +1,254 @@
+use crate::bytecode::attributes::{
+    Annotation, AnyAttribute, ElementValue, ParameterAnnotation, StackMapFrame, TypeAnnotation,
+    TypeAnnotationTargetInfoType, VerificationTypeInfo,
+};
+use crate::bytecode::pool::ConstantPoolIndex;
+use crate::bytecode::writer::BufferedWriter;
+use crate::bytecode::BytecodeError;
+
+pub fn write_attribute(
+    writer: &mut BufferedWriter,
+    attribute: &dyn AnyAttribute,
+) -> Result<(), BytecodeError> {
+    attribute.write_any(writer)
+}
+
+pub(crate) fn write_annotation(
+    writer: &mut BufferedWriter,
+    annotation: &Annotation,
+) -> Result<(), BytecodeError> {
+    writer.put_index(annotation.type_index)?;
+    writer.put_count::<u16>(annotation.element_value_pairs.len())?;
+    for pair in &annotation.element_value_pairs {
+        writer.put_index(pair.element_name_index)?;
+        write_elementvalue(writer, &pair.value)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_parameter_annotation(
+    writer: &mut BufferedWriter,
+    parameter: &ParameterAnnotation,
+) -> Result<(), BytecodeError> {
+    writer.put_count::<u16>(parameter.annotations.len())?;
+    for annotation in &parameter.annotations {
+        write_annotation(writer, annotation)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_typeannotation(
+    writer: &mut BufferedWriter,
+    annotation: &TypeAnnotation,
+) -> Result<(), BytecodeError> {
+    writer.put(annotation.target_type);
+    match &annotation.target_info.target_info {
+        TypeAnnotationTargetInfoType::TypeParameter {
+            type_parameter_index,
+        } => {
+            put_narrow_index(writer, *type_parameter_index)?;
+        }
+        TypeAnnotationTargetInfoType::SuperType { super_type_index } => {
+            writer.put_index(*super_type_index)?;
+        }
+        TypeAnnotationTargetInfoType::TypeParameterBound {
+            type_parameter_index,
+            bound_index,
+        } => {
+            put_narrow_index(writer, *type_parameter_index)?;
+            put_narrow_index(writer, *bound_index)?;
+        }
+        TypeAnnotationTargetInfoType::Empty => {}
+        TypeAnnotationTargetInfoType::FormalParameter {
+            formal_parameter_index,
+        } => {
+            put_narrow_index(writer, *formal_parameter_index)?;
+        }
+        TypeAnnotationTargetInfoType::Throws { throws_type_index } => {
+            writer.put_index(*throws_type_index)?;
+        }
+        TypeAnnotationTargetInfoType::LocalVar { table } => {
+            writer.put_count::<u16>(table.len())?;
+            for entry in table {
+                writer.put(entry.start_pc);
+                writer.put(entry.length);
+                writer.put_index(entry.index)?;
+            }
+        }
+        TypeAnnotationTargetInfoType::Catch {
+            exception_table_index,
+        } => {
+            writer.put_index(*exception_table_index)?;
+        }
+        TypeAnnotationTargetInfoType::Offset { offset } => {
+            writer.put(*offset);
+        }
+        TypeAnnotationTargetInfoType::TypeArgument {
+            offset,
+            type_argument_index,
+        } => {
+            writer.put(*offset);
+            put_narrow_index(writer, *type_argument_index)?;
+        }
+    }
+
+    writer.put_count::<u8>(annotation.target_path.path.len())?;
+    for entry in &annotation.target_path.path {
+        writer.put(entry.type_path_kind);
+        put_narrow_index(writer, entry.type_argument_index)?;
+    }
+
+    writer.put_index(annotation.type_index)?;
+    writer.put_count::<u16>(annotation.element_value_pairs.len())?;
+    for pair in &annotation.element_value_pairs {
+        writer.put_index(pair.element_name_index)?;
+        write_elementvalue(writer, &pair.value)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_elementvalue(
+    writer: &mut BufferedWriter,
+    value: &ElementValue,
+) -> Result<(), BytecodeError> {
+    match value {
+        ElementValue::ConstValueIndex {
+            tag,
+            const_value_index,
+        } => {
+            writer.put(*tag);
+            writer.put_index(*const_value_index)?;
+        }
+        ElementValue::EnumConstValue {
+            type_name_index,
+            const_name_index,
+        } => {
+            writer.put(b'e');
+            writer.put_index(*type_name_index)?;
+            writer.put_index(*const_name_index)?;
+        }
+        ElementValue::ClassInfoIndex(class_info_index) => {
+            writer.put(b'c');
+            writer.put_index(*class_info_index)?;
+        }
+        ElementValue::Annotation(annotation) => {
+            writer.put(b'@');
+            write_annotation(writer, annotation)?;
+        }
+        ElementValue::Array { values, .. } => {
+            writer.put(b'[');
+            writer.put_count::<u16>(values.len())?;
+            for value in values {
+                write_elementvalue(writer, value)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_stackmapframe(
+    writer: &mut BufferedWriter,
+    frame: &StackMapFrame,
+) -> Result<(), BytecodeError> {
+    match frame {
+        StackMapFrame::SameFrame { frame_type } => {
+            writer.put(*frame_type);
+        }
+        StackMapFrame::SameLocals1StackItemFrame { frame_type, stack } => {
+            writer.put(*frame_type);
+            write_verification_type(writer, stack)?;
+        }
+        StackMapFrame::SameLocals1StackItemFrameExtended {
+            frame_type,
+            offset_delta,
+            stack,
+        } => {
+            writer.put(*frame_type);
+            writer.put(*offset_delta);
+            write_verification_type(writer, stack)?;
+        }
+        StackMapFrame::ChopFrame {
+            frame_type,
+            offset_delta,
+        }
+        | StackMapFrame::SameFrameExtended {
+            frame_type,
+            offset_delta,
+        } => {
+            writer.put(*frame_type);
+            writer.put(*offset_delta);
+        }
+        StackMapFrame::AppendFrame {
+            frame_type,
+            offset_delta,
+            locals,
+        } => {
+            writer.put(*frame_type);
+            writer.put(*offset_delta);
+            for local in locals {
+                write_verification_type(writer, local)?;
+            }
+        }
+        StackMapFrame::FullFrame {
+            frame_type,
+            offset_delta,
+            locals,
+            stack,
+            ..
+        } => {
+            writer.put(*frame_type);
+            writer.put(*offset_delta);
+            writer.put_count::<u16>(locals.len())?;
+            for local in locals {
+                write_verification_type(writer, local)?;
+            }
+            writer.put_count::<u16>(stack.len())?;
+            for item in stack {
+                write_verification_type(writer, item)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_verification_type(
+    writer: &mut BufferedWriter,
+    verification_type: &VerificationTypeInfo,
+) -> Result<(), BytecodeError> {
+    match verification_type {
+        VerificationTypeInfo::Top => writer.put(0u8),
+        VerificationTypeInfo::Integer => writer.put(1u8),
+        VerificationTypeInfo::Float => writer.put(2u8),
+        VerificationTypeInfo::Double => writer.put(3u8),
+        VerificationTypeInfo::Long => writer.put(4u8),
+        VerificationTypeInfo::Null => writer.put(5u8),
+        VerificationTypeInfo::UninitializedThis => writer.put(6u8),
+        VerificationTypeInfo::Object { class } => {
+            writer.put(7u8);
+            writer.put_index(*class)?;
+        }
+        VerificationTypeInfo::Uninitialized { offset } => {
+            writer.put(8u8);
+            writer.put(*offset);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes an index that is stored as a single byte, e.g., the index of a type parameter.
+fn put_narrow_index(
+    writer: &mut BufferedWriter,
+    index: ConstantPoolIndex,
+) -> Result<(), BytecodeError> {
+    let Ok(index) = u8::try_from(index.index()) else {
+        return Err(BytecodeError::ValueOutOfRange(index.index()));
+    };
+    writer.put(index);
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::bytecode::pool::ConstantPoolEntry;
+use crate::bytecode::writer::BufferedWriter;
+use crate::bytecode::BytecodeError;
+
+pub fn write_constant_pool_entry(
+    writer: &mut BufferedWriter,
+    entry: &ConstantPoolEntry,
+) -> Result<(), BytecodeError> {
+    let Some(tag) = entry.tag() else {
+        // Unusable slots are implied by the preceding entry and not written.
+        return Ok(());
+    };
+    writer.put(tag.tag());
+
+    match entry {
+        ConstantPoolEntry::Class { name_index } => {
+            writer.put_index(*name_index)?;
+        }
+        ConstantPoolEntry::FieldRef {
+            class_index,
+            name_and_type_index,
+        }
+        | ConstantPoolEntry::MethodRef {
+            class_index,
+            name_and_type_index,
+        }
+        | ConstantPoolEntry::InterfaceMethodRef {
+            class_index,
+            name_and_type_index,
+        } => {
+            writer.put_index(*class_index)?;
+            writer.put_index(*name_and_type_index)?;
+        }
+        ConstantPoolEntry::String { string_index } => {
+            writer.put_index(*string_index)?;
+        }
+        ConstantPoolEntry::Integer { bytes } => {
+            writer.put(*bytes);
+        }
+        ConstantPoolEntry::Float { bytes } => {
+            writer.put(*bytes);
+        }
+        ConstantPoolEntry::Long {
+            high_bytes,
+            low_bytes,
+        }
+        | ConstantPoolEntry::Double {
+            high_bytes,
+            low_bytes,
+        } => {
+            writer.put(*high_bytes);
+            writer.put(*low_bytes);
+        }
+        ConstantPoolEntry::NameAndType {
+            name_index,
+            descriptor_index,
+        } => {
+            writer.put_index(*name_index)?;
+            writer.put_index(*descriptor_index)?;
+        }
+        ConstantPoolEntry::Utf8 { bytes, .. } => {
+            writer.put_count::<u16>(bytes.len())?;
+            writer.put_bytes(bytes);
+        }
+        ConstantPoolEntry::MethodHandle {
+            reference_kind,
+            reference_index,
+        } => {
+            writer.put(*reference_kind);
+            writer.put_index(*reference_index)?;
+        }
+        ConstantPoolEntry::MethodType { descriptor_index } => {
+            writer.put_index(*descriptor_index)?;
+        }
+        ConstantPoolEntry::Dynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        }
+        | ConstantPoolEntry::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } => {
+            writer.put_index(*bootstrap_method_attr_index)?;
+            writer.put_index(*name_and_type_index)?;
+        }
+        ConstantPoolEntry::Module { name_index } | ConstantPoolEntry::Package { name_index } => {
+            writer.put_index(*name_index)?;
+        }
+        ConstantPoolEntry::Unusable => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use crate::bytecode::writer::attributes::write_attribute;
+use crate::bytecode::writer::constants::write_constant_pool_entry;
+use crate::bytecode::writer::BufferedWriter;
+use crate::bytecode::{BytecodeError, ClassFile, Field, Interface, Method};
+
+/// Writes the given [ClassFile] to the `writer`.
+///
+/// All counts and `attribute_length` values are computed from the actual contents of the
+/// [ClassFile] rather than taken from the stored count fields, so the output stays valid after
+/// elements have been added or removed.
+pub fn write_classfile(
+    writer: &mut BufferedWriter,
+    classfile: &ClassFile,
+) -> Result<(), BytecodeError> {
+    writer.put(classfile.magic_number);
+    writer.put(classfile.version.minor);
+    writer.put(classfile.version.major);
+
+    let pool = &classfile.constant_pool;
+    writer.put_count::<u16>(pool.size() + 1)?;
+    for idx in 1..=pool.size() {
+        let Some(entry) = pool.get(idx.into()) else {
+            return Err(BytecodeError::ConstantPoolEntryNotFound(idx));
+        };
+        write_constant_pool_entry(writer, entry)?;
+    }
+
+    writer.put(classfile.access_flags.bits());
+    writer.put_index(classfile.this_class)?;
+    writer.put_index(classfile.super_class)?;
+
+    writer.put_count::<u16>(classfile.interfaces.len())?;
+    for interface in &classfile.interfaces {
+        write_interface(writer, interface)?;
+    }
+
+    writer.put_count::<u16>(classfile.fields.len())?;
+    for field in &classfile.fields {
+        write_field(writer, field)?;
+    }
+
+    writer.put_count::<u16>(classfile.methods.len())?;
+    for method in &classfile.methods {
+        write_method(writer, method)?;
+    }
+
+    writer.put_count::<u16>(classfile.attributes.len())?;
+    for attribute in classfile.attributes.values() {
+        write_attribute(writer, attribute)?;
+    }
+
+    Ok(())
+}
+
+pub fn write_interface(
+    writer: &mut BufferedWriter,
+    interface: &Interface,
+) -> Result<(), BytecodeError> {
+    writer.put_index(interface.name_index)
+}
+
+pub fn write_field(writer: &mut BufferedWriter, field: &Field) -> Result<(), BytecodeError> {
+    writer.put(field.access_flags.bits());
+    writer.put_index(field.name_index)?;
+    writer.put_index(field.descriptor_index)?;
+
+    writer.put_count::<u16>(field.attributes.len())?;
+    for attribute in field.attributes.values() {
+        write_attribute(writer, attribute)?;
+    }
+
+    Ok(())
+}
+
+pub fn write_method(writer: &mut BufferedWriter, method: &Method) -> Result<(), BytecodeError> {
+    writer.put(method.access_flags.bits());
+    writer.put_index(method.name_index)?;
+    writer.put_index(method.descriptor_index)?;
+
+    writer.put_count::<u16>(method.attributes.len())?;
+    for attribute in method.attributes.values() {
+        write_attribute(writer, attribute)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::tests::FIXTURES;
+    use crate::bytecode::writer::containers::write_classfile;
+    use crate::bytecode::writer::BufferedWriter;
+    use crate::bytecode::ClassFile;
+
+    #[test]
+    fn test_write_classfile_roundtrip() {
+        for input in FIXTURES {
+            let original = ClassFile::parse(input).unwrap();
+            let mut writer = BufferedWriter::new();
+            write_classfile(&mut writer, &original).unwrap();
+            assert_eq!(writer.size(), input.len());
+            // The attributes are written in their original order.
+            assert_eq!(writer.as_bytes(), input);
+
+            let written = ClassFile::parse(writer.as_bytes()).unwrap();
+            assert_eq!(written.version.major, original.version.major);
+            assert_eq!(written.constant_pool, original.constant_pool);
+            assert_eq!(written.access_flags, original.access_flags);
+            assert_eq!(written.this_class, original.this_class);
+            assert_eq!(written.super_class, original.super_class);
+            assert_eq!(written.interfaces.len(), original.interfaces.len());
+            assert_eq!(written.fields.len(), original.fields.len());
+            assert_eq!(written.methods.len(), original.methods.len());
+            for (written, original) in written.methods.iter().zip(&original.methods) {
+                assert_eq!(written.name, original.name);
+                assert_eq!(written.access_flags, original.access_flags);
+                let names = written.attributes.names().collect::<Vec<_>>();
+                let expected = original.attributes.names().collect::<Vec<_>>();
+                assert_eq!(names, expected);
+            }
+            assert_eq!(written.attributes.len(), original.attributes.len());
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod attributes;
+pub mod constants;
+pub mod containers;
+#[allow(clippy::module_inception)]
+mod writer;
+
+pub use writer::BufferedWriter;
@@ -0,0 +1,143 @@
+use crate::bytecode::pool::ConstantPoolIndex;
+use crate::bytecode::BytecodeError;
+
+pub trait ToBytes {
+    fn to_bytes(&self, bytes: &mut Vec<u8>);
+}
+
+impl ToBytes for u8 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self);
+    }
+}
+
+impl ToBytes for u16 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToBytes for u32 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToBytes for i8 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToBytes for i16 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToBytes for i32 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToBytes for i64 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToBytes for f32 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToBytes for f64 {
+    fn to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BufferedWriter {
+    data: Vec<u8>,
+}
+
+impl BufferedWriter {
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    pub fn put<T>(&mut self, value: T)
+    where
+        T: ToBytes,
+    {
+        value.to_bytes(&mut self.data);
+    }
+
+    pub fn put_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    /// Writes the given [ConstantPoolIndex] as `u16`. Returns an error if the index does not fit
+    /// into two bytes.
+    pub fn put_index(&mut self, index: ConstantPoolIndex) -> Result<(), BytecodeError> {
+        let Ok(index) = u16::try_from(index.index()) else {
+            return Err(BytecodeError::ValueOutOfRange(index.index()));
+        };
+        self.put(index);
+        Ok(())
+    }
+
+    /// Writes the given `count` (e.g., the length of a table) as `T`. Returns an error if the count
+    /// does not fit into `T`.
+    pub fn put_count<T>(&mut self, count: usize) -> Result<(), BytecodeError>
+    where
+        T: ToBytes + TryFrom<usize>,
+    {
+        let Ok(count) = T::try_from(count) else {
+            return Err(BytecodeError::ValueOutOfRange(count));
+        };
+        self.put(count);
+        Ok(())
+    }
+
+    /// Writes an attribute with the given `name_index`. The attribute's info is written by `info`,
+    /// and the `attribute_length` is computed from the number of bytes written by it.
+    pub fn put_attribute<F>(
+        &mut self,
+        name_index: ConstantPoolIndex,
+        info: F,
+    ) -> Result<(), BytecodeError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), BytecodeError>,
+    {
+        self.put_index(name_index)?;
+        let length_position = self.data.len();
+        self.put(0u32);
+        info(self)?;
+
+        let length = self.data.len() - length_position - 4;
+        let Ok(length) = u32::try_from(length) else {
+            return Err(BytecodeError::ValueOutOfRange(length));
+        };
+        self.data[length_position..length_position + 4].copy_from_slice(&length.to_be_bytes());
+        Ok(())
+    }
+
+    /// Returns the number of bytes written to the [BufferedWriter].
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns a reference to the bytes written to the [BufferedWriter].
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consumes the [BufferedWriter] and returns the written bytes.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
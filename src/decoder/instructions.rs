@@ -1,11 +1,9 @@
 use crate::bytecode::reader::BufferedReader;
 use crate::bytecode::BytecodeError;
+use crate::error::Error;
 use crate::types::instructions::Instruction;
 
-pub fn parse_instruction(
-    op: u8,
-    buffer: &mut BufferedReader,
-) -> Result<Instruction, BytecodeError> {
+pub fn parse_instruction(op: u8, buffer: &mut BufferedReader) -> Result<Instruction, Error> {
     match op {
         0x32 => Ok(Instruction::Aaload),
         0x53 => Ok(Instruction::Aastore),
@@ -129,9 +127,8 @@ pub fn parse_instruction(
             Ok(Instruction::Goto(index))
         }
         0xc8 => {
-            // let index = buffer.take::<u16>()?;
-            // Ok(Instruction::GotoW(index))
-            todo!("GotoW")
+            let _offset = buffer.take::<i32>()?;
+            Ok(Instruction::GotoW)
         }
         0x91 => Ok(Instruction::I2b),
         0x92 => Ok(Instruction::I2c),
@@ -275,7 +272,10 @@ pub fn parse_instruction(
             let index = buffer.take::<u16>()?;
             Ok(Instruction::Jsr(index))
         }
-        0xc9 => Ok(Instruction::JsrW),
+        0xc9 => {
+            let _offset = buffer.take::<i32>()?;
+            Ok(Instruction::JsrW)
+        }
         0x8a => Ok(Instruction::L2d),
         0x89 => Ok(Instruction::L2f),
         0x88 => Ok(Instruction::L2i),
@@ -309,7 +309,17 @@ pub fn parse_instruction(
         0x21 => Ok(Instruction::Lload3),
         0x69 => Ok(Instruction::Lmul),
         0x75 => Ok(Instruction::Lneg),
-        0xab => todo!("lookupswitch"),
+        0xab => {
+            skip_padding(buffer)?;
+            let _default = buffer.take::<i32>()?;
+            let npairs = buffer.take::<i32>()?;
+            if npairs < 0 {
+                return Err(buffer.error(BytecodeError::NegativeSwitchPairs(npairs)));
+            }
+            // Each pair consists of a match and an offset.
+            buffer.take_bytes(operands_length(npairs as i64, 8))?;
+            Ok(Instruction::Lookupswitch)
+        }
         0x81 => Ok(Instruction::Lor),
         0x71 => Ok(Instruction::Lrem),
         0xad => Ok(Instruction::Lreturn),
@@ -337,7 +347,10 @@ pub fn parse_instruction(
             let index = buffer.take::<u16>()?;
             Ok(Instruction::New(index))
         }
-        0xbc => todo!("newarray"),
+        0xbc => {
+            let atype = buffer.take::<u8>()?;
+            Ok(Instruction::Newarray(atype))
+        }
         0x00 => Ok(Instruction::Nop),
         0x57 => Ok(Instruction::Pop),
         0x58 => Ok(Instruction::Pop2),
@@ -361,8 +374,156 @@ pub fn parse_instruction(
             Ok(Instruction::Sipush(index))
         }
         0x5f => Ok(Instruction::Swap),
-        0xaa => todo!("tableswitch"),
-        0xc4 => todo!("wide"),
-        _ => Err(BytecodeError::UnsupportedInstruction),
+        0xaa => {
+            skip_padding(buffer)?;
+            let _default = buffer.take::<i32>()?;
+            let low = buffer.take::<i32>()?;
+            let high = buffer.take::<i32>()?;
+            if high < low {
+                return Err(buffer.error(BytecodeError::InvalidSwitchRange { low, high }));
+            }
+            let count = high as i64 - low as i64 + 1;
+            buffer.take_bytes(operands_length(count, 4))?;
+            Ok(Instruction::Tableswitch)
+        }
+        0xc4 => {
+            let opcode = buffer.take::<u8>()?;
+            match opcode {
+                // iinc takes a two byte index and a two byte constant.
+                0x84 => buffer.take_bytes(4)?,
+                0x15..=0x19 | 0x36..=0x3a | 0xa9 => buffer.take_bytes(2)?,
+                _ => return Err(buffer.error(BytecodeError::UnsupportedInstruction(opcode))),
+            };
+            Ok(Instruction::Wide)
+        }
+        _ => Err(buffer.error(BytecodeError::UnsupportedInstruction(op))),
+    }
+}
+
+/// Skips the padding after a `tableswitch` or `lookupswitch` opcode, which aligns the operands to
+/// a multiple of four bytes from the start of the code. The `buffer` is expected to be positioned
+/// relative to the start of the code.
+fn skip_padding(buffer: &mut BufferedReader) -> Result<(), Error> {
+    let padding = (4 - buffer.position() % 4) % 4;
+    buffer.take_bytes(padding)?;
+    Ok(())
+}
+
+/// Returns the length of `count` operands of `size` bytes, which saturates so that a bogus count
+/// results in an error when taking the operands instead of an overflow.
+fn operands_length(count: i64, size: i64) -> usize {
+    usize::try_from(count * size).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::reader::BufferedReader;
+    use crate::bytecode::BytecodeError;
+    use crate::decoder::instructions::parse_instruction;
+    use crate::error::Error;
+    use crate::types::instructions::Instruction;
+
+    /// Decodes all instructions of `code`, which must be consumed completely.
+    fn decode(code: &[u8]) -> Result<Vec<Instruction>, Error> {
+        let mut reader = BufferedReader::new(code);
+        let mut instructions = vec![];
+        while reader.remaining() > 0 {
+            let opcode = reader.take::<u8>()?;
+            instructions.push(parse_instruction(opcode, &mut reader)?);
+        }
+        Ok(instructions)
+    }
+
+    /// Returns `nops` times `nop` followed by the `opcode` of a switch, its padding and `operands`
+    /// and a final `return`.
+    fn switch(nops: usize, opcode: u8, operands: &[i32]) -> Vec<u8> {
+        let mut code = vec![0x00; nops];
+        code.push(opcode);
+        code.resize(code.len().next_multiple_of(4), 0);
+        for operand in operands {
+            code.extend(operand.to_be_bytes());
+        }
+        code.push(0xb1);
+        code
+    }
+
+    #[test]
+    fn test_switch_padding() {
+        for nops in 0..4 {
+            let mut expected = vec![Instruction::Nop; nops];
+            expected.extend([Instruction::Tableswitch, Instruction::Return]);
+            // default 8, low 1, high 2 and two jump offsets
+            let code = switch(nops, 0xaa, &[8, 1, 2, 12, 16]);
+            assert_eq!(decode(&code).unwrap(), expected, "{nops} nops");
+
+            expected[nops] = Instruction::Lookupswitch;
+            // default 8, one pair 5 => 12
+            let code = switch(nops, 0xab, &[8, 1, 5, 12]);
+            assert_eq!(decode(&code).unwrap(), expected, "{nops} nops");
+        }
+    }
+
+    #[test]
+    fn test_invalid_switch() {
+        let err = decode(&switch(1, 0xab, &[8, -1])).unwrap_err();
+        assert_eq!(err.kind(), &BytecodeError::NegativeSwitchPairs(-1));
+        assert_eq!(err.offset(), Some(8));
+        assert_eq!(
+            err.to_string(),
+            "lookupswitch has a negative number of pairs -1 at offset 8"
+        );
+
+        let err = decode(&switch(0, 0xaa, &[8, 1, 0])).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &BytecodeError::InvalidSwitchRange { low: 1, high: 0 }
+        );
+
+        let err = decode(&switch(0, 0xaa, &[8, i32::MIN, i32::MAX])).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            BytecodeError::UnexpectedEndOfData { .. }
+        ));
+    }
+
+    #[test]
+    fn test_wide() {
+        // iload, lload, fload, dload, aload, istore, lstore, fstore, dstore, astore and ret
+        let opcodes = [
+            0x15, 0x16, 0x17, 0x18, 0x19, 0x36, 0x37, 0x38, 0x39, 0x3a, 0xa9,
+        ];
+        for opcode in opcodes {
+            let code = [0xc4, opcode, 0x01, 0x00, 0xb1];
+            let expected = [Instruction::Wide, Instruction::Return];
+            assert_eq!(decode(&code).unwrap(), expected, "opcode 0x{opcode:02X}");
+        }
+
+        // iinc with a two byte index and a two byte constant
+        let code = [0xc4, 0x84, 0x01, 0x00, 0xff, 0xff, 0xb1];
+        assert_eq!(
+            decode(&code).unwrap(),
+            [Instruction::Wide, Instruction::Return]
+        );
+
+        let err = decode(&[0xc4, 0xb1]).unwrap_err();
+        assert_eq!(err.kind(), &BytecodeError::UnsupportedInstruction(0xb1));
+        assert_eq!(err.offset(), Some(1));
+    }
+
+    #[test]
+    fn test_wide_jumps_and_newarray() {
+        let code = [
+            0xc8, 0x00, 0x01, 0x00, 0x00, // goto_w 65536
+            0xc9, 0xff, 0xff, 0xff, 0xfb, // jsr_w -5
+            0xbc, 0x0a, // newarray int
+        ];
+        assert_eq!(
+            decode(&code).unwrap(),
+            [
+                Instruction::GotoW,
+                Instruction::JsrW,
+                Instruction::Newarray(10)
+            ]
+        );
     }
 }
@@ -1,2 +1,172 @@
-#[derive(thiserror::Error, Debug)]
-pub enum Error {}
+use std::fmt;
+
+use crate::bytecode::BytecodeError;
+
+/// Describes the element of a class file that was being processed when an [Error] occurred.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Context {
+    /// The class file itself.
+    Class,
+    /// The constant pool entry at the given index.
+    ConstantPoolEntry(usize),
+    /// The field with the given name.
+    Field(String),
+    /// The method with the given name.
+    Method(String),
+    /// The attribute with the given name.
+    Attribute(String),
+    /// The entry at the given position of a table, e.g. the exception table of a `Code`
+    /// attribute.
+    Entry { table: &'static str, index: usize },
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Context::Class => write!(f, "class"),
+            Context::ConstantPoolEntry(index) => write!(f, "constant pool entry #{}", index),
+            Context::Field(name) => write!(f, "field `{}`", name),
+            Context::Method(name) => write!(f, "method `{}`", name),
+            Context::Attribute(name) => write!(f, "attribute `{}`", name),
+            Context::Entry { table, index } => write!(f, "{} entry {}", table, index),
+        }
+    }
+}
+
+/// The error returned when reading a class file fails.
+///
+/// Besides the [kind](BytecodeError) of the error, it records the byte offset within the class
+/// file at which the error was detected and the path of [Context] elements leading to it, e.g.
+/// `class > method `main` > attribute `Code` > exception table entry 3`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub struct Error {
+    kind: BytecodeError,
+    offset: Option<usize>,
+    context: Vec<Context>,
+}
+
+impl Error {
+    pub fn new(kind: BytecodeError) -> Self {
+        Self {
+            kind,
+            offset: None,
+            context: vec![],
+        }
+    }
+
+    /// Sets the byte offset at which the error occurred.
+    pub fn at(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Adds the given `context` as the outermost element of the error's context path.
+    pub fn within(mut self, context: Context) -> Self {
+        self.context.insert(0, context);
+        self
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> &BytecodeError {
+        &self.kind
+    }
+
+    /// Returns the byte offset at which the error occurred, if known.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// Returns the context path of the error, starting with the outermost element.
+    pub fn context(&self) -> &[Context] {
+        &self.context
+    }
+}
+
+impl From<BytecodeError> for Error {
+    fn from(kind: BytecodeError) -> Self {
+        Error::new(kind)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {}", offset)?;
+        }
+        for (idx, context) in self.context.iter().enumerate() {
+            let separator = if idx == 0 { " in " } else { " > " };
+            write!(f, "{}{}", separator, context)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::bytecode::reader::parser::ClassParser;
+    use crate::bytecode::tests::SIMPLE;
+    use crate::bytecode::{BytecodeError, ClassFile};
+    use crate::error::{Context, Error};
+
+    fn read(input: &[u8]) -> Error {
+        ClassFile::parse(input).unwrap_err()
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = Error::new(BytecodeError::InvalidTag {
+            kind: "verification type",
+            found: 9,
+        })
+        .at(42)
+        .within(Context::Entry {
+            table: "exception table",
+            index: 3,
+        })
+        .within(Context::Attribute("Code".to_string()))
+        .within(Context::Method("name".to_string()))
+        .within(Context::Class);
+
+        assert_eq!(
+            err.to_string(),
+            "invalid verification type tag 9 at offset 42 in class > method `name` > attribute \
+             `Code` > exception table entry 3"
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        let mut input = SIMPLE.to_vec();
+        input[0] = 0xCB;
+        let err = read(&input);
+        assert_eq!(err.kind(), &BytecodeError::InvalidMagicNumber(0xCBFEBABE));
+        assert_eq!(err.offset(), Some(0));
+        assert_eq!(err.context(), [Context::Class]);
+
+        // Cut the class file within the `Code` attribute of the constructor.
+        let err = read(&SIMPLE[..660]);
+        assert_eq!(
+            err.kind(),
+            &BytecodeError::UnexpectedEndOfData {
+                expected: 35,
+                found: 11
+            }
+        );
+        assert_eq!(err.offset(), Some(649));
+        assert_eq!(
+            err.context(),
+            [
+                Context::Class,
+                Context::Method("<init>".to_string()),
+                Context::Attribute("Code".to_string())
+            ]
+        );
+
+        let borrowed = ClassParser::new()
+            .parse_borrowed(&SIMPLE[..660])
+            .unwrap_err();
+        assert_eq!(borrowed.offset(), err.offset());
+        assert_eq!(borrowed.context(), err.context());
+    }
+}
@@ -1,11 +1,10 @@
 use std::collections::HashMap;
 
 use bytecode::attributes::{
-    element_value_string, MethodParametersInfo, RuntimeInvisibleAnnotationsInfo,
-    RuntimeVisibleAnnotationsInfo,
+    element_value_string, Attribute, MethodParametersInfo, NamedAttribute,
+    RuntimeInvisibleAnnotationsInfo, RuntimeVisibleAnnotationsInfo,
 };
-use bytecode::DescriptorKind;
-use error::Error;
+use error::{Context, Error};
 
 pub mod bytecode;
 pub mod decoder;
@@ -29,23 +28,40 @@ impl Annotation {
         bytecode: &bytecode::attributes::Annotation,
         pool: &bytecode::pool::ConstantPool,
     ) -> Result<Annotation, Error> {
-        let name = pool.text_of(bytecode.type_index).unwrap();
+        let name = pool.utf8_at(bytecode.type_index)?;
         let field = bytecode
             .element_value_pairs
             .iter()
             .map(|pair| {
-                let key = pool.text_of(pair.element_name_index).unwrap();
-                let value = match element_value_string(&pair.value, pool) {
-                    Ok(value) => value,
-                    Err(_) => unreachable!(),
-                };
-                (key, value)
+                let key = pool.utf8_at(pair.element_name_index)?;
+                let value = element_value_string(&pair.value, pool)?;
+                Ok((key, value))
             })
-            .collect();
+            .collect::<Result<_, Error>>()?;
         Ok(Annotation { name, field })
     }
 }
 
+/// Transforms all `annotations` of an annotations attribute, recording the position of the
+/// annotation that failed in the error.
+fn annotations_from_bytecode(
+    annotations: &[bytecode::attributes::Annotation],
+    pool: &bytecode::pool::ConstantPool,
+) -> Result<Vec<Annotation>, Error> {
+    annotations
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            Annotation::from_bytecode(item, pool).map_err(|err| {
+                err.within(Context::Entry {
+                    table: "annotation",
+                    index,
+                })
+            })
+        })
+        .collect()
+}
+
 #[derive(Debug, PartialEq)]
 pub struct TypeRef {
     pub name: String,
@@ -128,36 +144,48 @@ impl Method {
     pub fn from_bytecode(
         bytecode: &bytecode::Method,
         pool: &bytecode::pool::ConstantPool,
-        // TODO: use a proper error type
+    ) -> Result<Self, Error> {
+        Self::from_bytecode_method(bytecode, pool)
+            .map_err(|err| err.within(Context::Method(bytecode.name.clone())))
+    }
+
+    fn from_bytecode_method(
+        bytecode: &bytecode::Method,
+        pool: &bytecode::pool::ConstantPool,
     ) -> Result<Self, Error> {
         // -----------------------------------------------------------------------------
         //  - Transform parameters from bytecode representation to IR representation -
         // -----------------------------------------------------------------------------
-        let attr_params: Vec<String> = bytecode
-            .attributes
-            .get("MethodParameters")
-            .and_then(|attr| attr.as_any_ref().downcast_ref::<MethodParametersInfo>())
-            .map(|params| {
-                params
-                    .parameters
-                    .iter()
-                    .map(|param| pool.text_of(param.name_index).unwrap())
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let desc_params = bytecode
-            .descriptor
-            .iter()
-            .filter(|desc| desc.kind == DescriptorKind::Parameter)
-            .collect::<Vec<&bytecode::Descriptor>>();
+        let attr_params: Vec<Option<String>> = match bytecode.attribute::<MethodParametersInfo>() {
+            Some(params) => params
+                .parameters
+                .iter()
+                .map(|param| match param.name_index.index() {
+                    // A zero index indicates a formal parameter without a name.
+                    0 => Ok(None),
+                    _ => pool.utf8_at(param.name_index).map(Some),
+                })
+                .collect::<Result<_, _>>()
+                .map_err(|kind| {
+                    Error::new(kind)
+                        .within(Context::Attribute(MethodParametersInfo::NAME.to_string()))
+                })?,
+            None => vec![],
+        };
 
-        let parameters: Vec<Parameter> = desc_params
+        // The descriptor is only missing if the method was read leniently, parsing it again reports
+        // why it is invalid.
+        let descriptor = match &bytecode.descriptor {
+            Some(descriptor) => descriptor.clone(),
+            None => bytecode::MethodDescriptor::parse(&pool.utf8_at(bytecode.descriptor_index)?)?,
+        };
+        let parameters: Vec<Parameter> = descriptor
+            .params
             .iter()
             .enumerate()
-            .map(|(i, desc)| {
-                let name: Option<String> = attr_params.get(i).cloned();
-                let ty = desc.ty.to_string();
+            .map(|(i, param)| {
+                let name: Option<String> = attr_params.get(i).cloned().flatten();
+                let ty = param.to_string();
                 Parameter {
                     name,
                     ty: TypeRef { name: ty },
@@ -169,14 +197,8 @@ impl Method {
         // -----------------------------------------------------------------------------
         //  - Extract the method's return type from the bytecode representation -
         // -----------------------------------------------------------------------------
-        let ret_ty: Option<TypeRef> = bytecode.descriptor.iter().find_map(|desc| {
-            if desc.kind == DescriptorKind::Return {
-                Some(TypeRef {
-                    name: desc.ty.to_string(),
-                })
-            } else {
-                None
-            }
+        let ret_ty: Option<TypeRef> = Some(TypeRef {
+            name: descriptor.ret.to_string(),
         });
 
         // -----------------------------------------------------------------------------
@@ -185,25 +207,15 @@ impl Method {
         // TODO: we should add support to read only annotations that are available in the
         // desired JVM specification ...
         let mut annotations: Vec<Annotation> = vec![];
-        if let Some(attr) =
-            bytecode.get_attribute::<RuntimeVisibleAnnotationsInfo>("RuntimeVisibleAnnotations")
-        {
-            let items: Vec<Annotation> = attr
-                .annotations
-                .iter()
-                .map(|item| Annotation::from_bytecode(item, pool).unwrap())
-                .collect();
+        if let Some(attr) = bytecode.attribute::<RuntimeVisibleAnnotationsInfo>() {
+            let items = annotations_from_bytecode(&attr.annotations, pool)
+                .map_err(|err| err.within(Context::Attribute(attr.name().to_string())))?;
             annotations.extend(items);
         }
 
-        if let Some(attr) =
-            bytecode.get_attribute::<RuntimeInvisibleAnnotationsInfo>("RuntimeInvisibleAnnotations")
-        {
-            let items: Vec<Annotation> = attr
-                .annotations
-                .iter()
-                .map(|item| Annotation::from_bytecode(item, pool).unwrap())
-                .collect();
+        if let Some(attr) = bytecode.attribute::<RuntimeInvisibleAnnotationsInfo>() {
+            let items = annotations_from_bytecode(&attr.annotations, pool)
+                .map_err(|err| err.within(Context::Attribute(attr.name().to_string())))?;
             annotations.extend(items);
         }
 